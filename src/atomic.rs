@@ -0,0 +1,174 @@
+//! Replacing output files only once they have been written completely
+
+use crate::Tabwriter;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the temporary files of one process
+static TEMPORARY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// A file that is written to a temporary file next to it and renamed when it is complete.
+///
+/// See [Tabwriter::create_atomic](struct.Tabwriter.html#method.create_atomic). Until
+/// [commit](#method.commit) is called, the file at the target path is left alone, so other
+/// programs either see the old file or the complete new one. If the `AtomicFile` is dropped
+/// without being committed, e.g. after an error, the temporary file is removed. Only a crash
+/// of the process leaves it behind, as a hidden file in the same directory.
+pub struct AtomicFile {
+    writer: Option<BufWriter<File>>,
+    path: PathBuf,
+    temporary_path: PathBuf,
+}
+
+impl AtomicFile {
+    /// Create a temporary file in the directory of `path`, which must exist
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<AtomicFile, Error> {
+        let path = path.as_ref().to_path_buf();
+        let file_name = path.file_name().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("`{}` is not the path of a file", path.display()),
+            )
+        })?;
+        loop {
+            let number = TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed);
+            let temporary_name = format!(
+                ".{}.{}.{}.tmp",
+                file_name.to_string_lossy(),
+                process::id(),
+                number
+            );
+            let temporary_path = path.with_file_name(temporary_name);
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temporary_path)
+            {
+                Ok(file) => {
+                    return Ok(AtomicFile {
+                        writer: Some(BufWriter::new(file)),
+                        path,
+                        temporary_path,
+                    })
+                }
+                // left behind by a crashed process with the same ID
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Get the path that the file is renamed to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the path of the temporary file
+    pub fn temporary_path(&self) -> &Path {
+        &self.temporary_path
+    }
+
+    /// Write the rest of the file to the disk and rename it to its path, which replaces a file
+    /// that is already there
+    ///
+    /// A replaced file keeps its permissions. On Unix, the directory is synced after the rename,
+    /// so the new file is still there after a crash of the system.
+    pub fn commit(mut self) -> Result<(), Error> {
+        let writer = self.writer.take().expect("the file is committed only once");
+        let renamed = self.persist(writer);
+        if renamed.is_err() {
+            let _ = fs::remove_file(&self.temporary_path);
+        }
+        renamed?;
+        sync_directory(&self.path)
+    }
+
+    /// Write the temporary file to the disk and rename it
+    fn persist(&self, writer: BufWriter<File>) -> Result<(), Error> {
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        match fs::metadata(&self.path) {
+            Ok(metadata) => file.set_permissions(metadata.permissions())?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&self.temporary_path, &self.path)
+    }
+
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        self.writer
+            .as_mut()
+            .expect("the file is not written after it was committed")
+    }
+}
+
+/// Sync the directory that contains `path`, which makes a rename in it durable
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> Result<(), Error> {
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+/// Directories cannot be synced on other systems
+#[cfg(not(unix))]
+fn sync_directory(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.temporary_path);
+        }
+    }
+}
+
+impl Tabwriter<AtomicFile> {
+    /// Write to a temporary file next to `path` that replaces the file at `path` only when
+    /// [finish](#method.finish) is called.
+    ///
+    /// This way a pipeline that crashes or stops with an error never leaves a truncated file
+    /// behind that the next job would read as if it was complete. The temporary file is in the
+    /// same directory, so the rename cannot cross file systems. See
+    /// [AtomicFile](struct.AtomicFile.html) for the details.
+    ///
+    /// ```
+    /// # use tempfile::tempdir;
+    /// # let directory = tempdir().unwrap();
+    /// # let path = directory.path().join("counts.tsv");
+    /// use std::fs;
+    /// use tabfile::Tabwriter;
+    ///
+    /// let mut writer = Tabwriter::create_atomic(&path).unwrap();
+    /// writer.write_fields(&["BRCA1", "5"]).unwrap();
+    /// assert!(!path.exists());
+    /// writer.finish().unwrap();
+    /// assert_eq!(fs::read_to_string(&path).unwrap(), "BRCA1\t5\n");
+    /// ```
+    pub fn create_atomic<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Tabwriter::new(AtomicFile::create(path)?))
+    }
+
+    /// Complete the file and move it to its path, see
+    /// [AtomicFile::commit](struct.AtomicFile.html#method.commit)
+    pub fn finish(self) -> Result<(), Error> {
+        self.into_inner()?.commit()
+    }
+}
@@ -0,0 +1,229 @@
+//! Iterator adaptors that yield several records at once
+
+use crate::collect::required_field;
+use crate::{Record, RowIterator, Tabfile};
+use std::io::Error;
+
+/// Iterator over runs of consecutive records that share the same key.
+///
+/// See [Tabfile::batches_by](struct.Tabfile.html#method.batches_by).
+pub struct BatchesBy {
+    records: RowIterator,
+    key_column: usize,
+    pending: Option<Record>,
+    error: Option<Error>,
+}
+
+impl Iterator for BatchesBy {
+    type Item = Result<Vec<Record>, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let first = match self.pending.take() {
+            Some(record) => record,
+            None => match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        let key = match required_field(&first, self.key_column) {
+            Ok(key) => key.to_string(),
+            Err(e) => return Some(Err(e)),
+        };
+        let mut batch = vec![first];
+        let key_column = self.key_column;
+        for record in &mut self.records {
+            let same_key = record.and_then(|record| {
+                let same_key = required_field(&record, key_column)? == key;
+                Ok((record, same_key))
+            });
+            match same_key {
+                Ok((record, true)) => batch.push(record),
+                Ok((record, false)) => {
+                    self.pending = Some(record);
+                    break;
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+        Some(Ok(batch))
+    }
+}
+
+/// Iterator over runs of consecutive records and their key.
+///
+/// See [Tabfile::group_by_column](struct.Tabfile.html#method.group_by_column).
+pub struct GroupByColumn {
+    records: RowIterator,
+    column: usize,
+    pending: Option<(String, Record)>,
+    error: Option<Error>,
+}
+
+impl GroupByColumn {
+    /// Read the next record and its key
+    fn next_keyed(&mut self) -> Option<Result<(String, Record), Error>> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let key = match required_field(&record, self.column) {
+            Ok(key) => key.to_string(),
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok((key, record)))
+    }
+}
+
+impl Iterator for GroupByColumn {
+    type Item = Result<(String, Vec<Record>), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let (key, first) = match self.pending.take().map(Ok).or_else(|| self.next_keyed())? {
+            Ok(keyed) => keyed,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut group = vec![first];
+        while let Some(keyed) = self.next_keyed() {
+            match keyed {
+                Ok((next_key, record)) if next_key == key => group.push(record),
+                Ok(keyed) => {
+                    self.pending = Some(keyed);
+                    break;
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+        Some(Ok((key, group)))
+    }
+}
+
+/// Iterator over batches of a fixed number of records.
+///
+/// See [Tabfile::chunks](struct.Tabfile.html#method.chunks).
+pub struct Chunks {
+    records: RowIterator,
+    size: usize,
+    error: Option<Error>,
+}
+
+impl Iterator for Chunks {
+    type Item = Result<Vec<Record>, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let mut chunk = Vec::with_capacity(self.size);
+        for record in self.records.by_ref().take(self.size) {
+            match record {
+                Ok(record) => chunk.push(record),
+                Err(e) if chunk.is_empty() => return Some(Err(e)),
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(Ok(chunk))
+        }
+    }
+}
+
+impl Tabfile {
+    /// Yield the records in batches that end whenever the value in `key_column` changes.
+    ///
+    /// The input should be sorted by the key column, otherwise records with the same key end up
+    /// in different batches. Only one batch is kept in memory at a time. The keys are compared
+    /// as they are, so null tokens are keys like any other value, and a record without a field
+    /// at `key_column` is an error of kind `InvalidData`. If reading a record fails, the batch
+    /// so far is yielded first and the error follows as the next item.
+    pub fn batches_by(self, key_column: usize) -> BatchesBy {
+        BatchesBy {
+            records: self.into_iter(),
+            key_column,
+            pending: None,
+            error: None,
+        }
+    }
+
+    /// Yield the runs of consecutive records with the same field at `column`, together with
+    /// that field.
+    ///
+    /// This is the same as [batches_by](#method.batches_by), e.g. for the records of a file
+    /// that is sorted by gene or sample, but the key is part of every group. The fields are
+    /// compared as they are, so null tokens are keys like any other value, and a record
+    /// without a field at `column` is an error of kind `InvalidData`. If reading a record
+    /// fails, the group so far is yielded first and the error follows as the next item. Only
+    /// one group is kept in memory at a time.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"BRCA1\texon1\nBRCA1\texon2\nTP53\texon1\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let genes: Vec<(String, usize)> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .group_by_column(0)
+    ///     .map(|group| {
+    ///         let (gene, exons) = group.unwrap();
+    ///         (gene, exons.len())
+    ///     })
+    ///     .collect();
+    /// assert_eq!(genes, vec![("BRCA1".to_string(), 2), ("TP53".to_string(), 1)]);
+    /// ```
+    pub fn group_by_column(self, column: usize) -> GroupByColumn {
+        GroupByColumn {
+            records: self.into_iter(),
+            column,
+            pending: None,
+            error: None,
+        }
+    }
+
+    /// Yield the records in batches of `size` records, e.g. for bulk inserts into a database.
+    ///
+    /// Every batch except the last one has exactly `size` records, and there is no empty batch
+    /// at the end. If reading a record fails, the records before it are yielded as a shorter
+    /// batch first and the error follows as the next item, so no record is lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..250 { writeln!(file, "id{}\t{}", i, i * 2).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let sizes: Vec<usize> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .chunks(100)
+    ///     .map(|chunk| chunk.unwrap().len())
+    ///     .collect();
+    /// assert_eq!(sizes, vec![100, 100, 50]);
+    /// ```
+    pub fn chunks(self, size: usize) -> Chunks {
+        assert!(size > 0, "the chunk size must be positive");
+        Chunks {
+            records: self.into_iter(),
+            size,
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Helpers for bioinformatics formats
+
+use crate::code::{self, ErrorCode};
+use crate::percent::percent_decode;
+use crate::{Record, RowIterator, Tabfile};
+use std::borrow::Cow;
+use std::io::Error;
+use std::slice;
+
+/// The column of GFF and GTF files that holds the attributes
+const ATTRIBUTES_COLUMN: usize = 8;
+
+/// The attributes of a GFF3 or GTF feature in the order of the file.
+///
+/// See [parse_attributes].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes<'a> {
+    pairs: Vec<(&'a str, Cow<'a, str>)>,
+}
+
+impl<'a> Attributes<'a> {
+    /// Get the value of the first attribute called `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Check whether there is an attribute called `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterate over the keys and values in the order of the field
+    pub fn iter(&self) -> slice::Iter<'_, (&'a str, Cow<'a, str>)> {
+        self.pairs.iter()
+    }
+
+    /// Get the number of attributes
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Check whether there are no attributes
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b Attributes<'a> {
+    type Item = &'b (&'a str, Cow<'a, str>);
+    type IntoIter = slice::Iter<'b, (&'a str, Cow<'a, str>)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Parse the attributes field of a GFF3 or GTF file.
+///
+/// GFF3 attributes look like `ID=gene1;Name=BRCA1` and their values are
+/// [percent-decoded](crate::Record::field_decoded). GTF attributes look like
+/// `gene_id "g1"; transcript_id "t1";` and their values lose the quotes. Both styles can be
+/// mixed. Empty attributes, e.g. after a trailing `;`, are skipped and a `.` field has no
+/// attributes at all. Multiple values like `Parent=a,b` are kept as one value.
+///
+/// This function is only available with the `bio` feature.
+///
+/// ```
+/// use tabfile::parse_attributes;
+///
+/// let attributes = parse_attributes("ID=gene1;Note=a%3Bb;Dbxref=x,y;");
+/// assert_eq!(attributes.get("Note"), Some("a;b"));
+/// assert_eq!(attributes.len(), 3);
+///
+/// let attributes = parse_attributes(r#"gene_id "g1"; transcript_id "t1";"#);
+/// assert_eq!(attributes.get("transcript_id"), Some("t1"));
+/// ```
+pub fn parse_attributes(field: &str) -> Attributes<'_> {
+    let field = field.trim();
+    if field == "." {
+        return Attributes::default();
+    }
+    let pairs = field
+        .split(';')
+        .map(str::trim)
+        .filter(|attribute| !attribute.is_empty())
+        .map(|attribute| match attribute.split_once('=') {
+            Some((key, value)) => (key, percent_decode(value)),
+            None => {
+                let (key, value) = attribute.split_once(' ').unwrap_or((attribute, ""));
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                (key, Cow::Borrowed(value))
+            }
+        })
+        .collect();
+    Attributes { pairs }
+}
+
+impl Record {
+    /// Parse the ninth column of a GFF3 or GTF record with
+    /// [parse_attributes].
+    ///
+    /// Returns `None` if the record has fewer than nine fields or the attributes field is null.
+    ///
+    /// This method is only available with the `bio` feature.
+    pub fn attributes(&self) -> Option<Attributes<'_>> {
+        self.get_opt(ATTRIBUTES_COLUMN).map(parse_attributes)
+    }
+}
+
+/// The first fields of the lines that configure genome browsers in BED files
+const BED_BROWSER_LINES: &[&str] = &["track", "browser"];
+
+/// An interval of a BED file.
+///
+/// Like in BED files, the interval is half-open and 0-based: it starts at `start` and ends
+/// before `end`, so its length is `end - start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BedRecord {
+    record: Record,
+    start: u64,
+    end: u64,
+}
+
+impl BedRecord {
+    /// Convert a record with at least the three fields chrom, start and end.
+    ///
+    /// An error of kind `InvalidData` is returned if a field is missing, start or end is not a
+    /// non-negative integer or start is greater than end.
+    pub fn from_record(record: Record) -> Result<BedRecord, Error> {
+        if record.len() < 3 {
+            return Err(code::invalid_data(
+                ErrorCode::MissingField,
+                format!(
+                    "line {}: expected at least 3 BED fields but found {}",
+                    record.line_number(),
+                    record.len()
+                ),
+            ));
+        }
+        let position = |index: usize, name: &str| {
+            record[index].parse::<u64>().map_err(|e| {
+                code::invalid_data(
+                    ErrorCode::InvalidField,
+                    format!(
+                        "line {}: invalid {} `{}`: {}",
+                        record.line_number(),
+                        name,
+                        &record[index],
+                        e
+                    ),
+                )
+            })
+        };
+        let start = position(1, "start")?;
+        let end = position(2, "end")?;
+        if start > end {
+            return Err(code::invalid_data(
+                ErrorCode::InvalidField,
+                format!(
+                    "line {}: start {} is after end {}",
+                    record.line_number(),
+                    start,
+                    end
+                ),
+            ));
+        }
+        Ok(BedRecord { record, start, end })
+    }
+
+    /// Get the name of the chromosome
+    pub fn chrom(&self) -> &str {
+        &self.record[0]
+    }
+
+    /// Get the 0-based position of the first base
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Get the position behind the last base
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Get the number of bases
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Check whether the interval has no bases, e.g. for an insertion point
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Check whether the 0-based `position` lies within the interval
+    pub fn contains(&self, position: u64) -> bool {
+        self.start <= position && position < self.end
+    }
+
+    /// Check whether the interval shares at least one base with `other`
+    pub fn overlaps(&self, other: &BedRecord) -> bool {
+        self.chrom() == other.chrom() && self.start.max(other.start) < self.end.min(other.end)
+    }
+
+    /// Get the optional fields after end, like name, score and strand
+    pub fn rest(&self) -> Vec<&str> {
+        self.record.iter().skip(3).collect()
+    }
+
+    /// Get the line number of the interval
+    pub fn line_number(&self) -> usize {
+        self.record.line_number()
+    }
+
+    /// Get the underlying record
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+}
+
+/// Iterator over the intervals of a BED file.
+///
+/// See [Tabfile::bed_records].
+pub struct BedRecords {
+    rows: RowIterator,
+}
+
+impl Iterator for BedRecords {
+    type Item = Result<BedRecord, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.rows.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+            let first = record.iter().next().unwrap_or("");
+            let is_browser_line = BED_BROWSER_LINES.iter().any(|keyword| {
+                first
+                    .strip_prefix(keyword)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+            });
+            if !is_browser_line {
+                return Some(BedRecord::from_record(record));
+            }
+        }
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the intervals of a BED file.
+    ///
+    /// Every record is converted with [BedRecord::from_record], and `track` and `browser`
+    /// lines are skipped. Since BED files are tab-separated, the options of the `Tabfile` like
+    /// the separator usually keep their defaults, but they are applied.
+    ///
+    /// This method is only available with the `bio` feature.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"track name=peaks\nchr1\t100\t250\tpeak1\t7\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut intervals = Tabfile::open(file.path()).unwrap().bed_records();
+    /// let interval = intervals.next().unwrap().unwrap();
+    /// assert_eq!((interval.chrom(), interval.start(), interval.end()), ("chr1", 100, 250));
+    /// assert_eq!(interval.len(), 150);
+    /// assert!(interval.contains(100) && !interval.contains(250));
+    /// assert_eq!(interval.rest(), vec!["peak1", "7"]);
+    /// ```
+    pub fn bed_records(self) -> BedRecords {
+        BedRecords {
+            rows: self.into_iter(),
+        }
+    }
+}
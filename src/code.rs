@@ -0,0 +1,192 @@
+//! Stable codes for the errors of this crate
+
+use crate::{EmptyInput, MissingColumns};
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+/// A stable, machine-readable code for the category of an error.
+///
+/// Every error that this crate creates itself carries a code, which is also shown at the start
+/// of its message like `[TF002] line 2: expected 3 fields but found 2`. Unlike the message, the
+/// code of an error never changes between releases, so programs can branch on it with
+/// [ErrorCode::of](#method.of) instead of matching strings. Errors of the operating system, e.g.
+/// for a file that does not exist, have no code. New codes may be added in the future.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"a\tb\tc\nd\te\n");
+/// use tabfile::{ErrorCode, Tabfile};
+///
+/// for record in Tabfile::open(file.path()).unwrap().columns(3) {
+///     match record {
+///         Ok(record) => println!("{:?}", record.fields()),
+///         Err(e) if ErrorCode::of(&e) == Some(ErrorCode::ColumnCount) => {
+///             assert_eq!(e.to_string(), "[TF002] line 2: expected 3 fields but found 2");
+///         }
+///         Err(e) => panic!("{}", e),
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// `TF001`: a quoted field is not closed before the end of the file
+    UnclosedQuote,
+    /// `TF002`: a record has a different number of fields than expected
+    ColumnCount,
+    /// `TF003`: a record is too short to have a field that is needed
+    MissingField,
+    /// `TF004`: a field cannot be converted into the required type
+    InvalidField,
+    /// `TF005`: the header does not have columns that are required, see
+    /// [MissingColumns](struct.MissingColumns.html)
+    MissingColumns,
+    /// `TF006`: the input has no records, see [EmptyInput](enum.EmptyInput.html)
+    EmptyInput,
+    /// `TF007`: a line is longer than the
+    /// [maximum line length](struct.Tabfile.html#method.max_line_length)
+    LineTooLong,
+    /// `TF008`: a field cannot be written because it contains the separator
+    SeparatorInField,
+    /// `TF009`: two files that are read together have a different number of records
+    LengthMismatch,
+    /// `TF010`: the header does not match any known format version
+    UnknownFormatVersion,
+    /// `TF011`: a pseudonym map is malformed or cannot be written
+    InvalidPseudonymMap,
+    /// `TF012`: serialized sketch data is malformed
+    InvalidSketch,
+    /// `TF013`: a contract is malformed
+    InvalidContract,
+    /// `TF014`: only a compressed variant of the file exists, which cannot be read
+    CompressedFile,
+    /// `TF015`: a dialect cannot be converted from or into the format of another tool
+    UnsupportedDialect,
+    /// `TF016`: a record has a different line ending than the first one, see
+    /// [Tabfile::line_endings](struct.Tabfile.html#method.line_endings)
+    MixedLineEndings,
+    /// `TF017`: a line is not valid UTF-8
+    InvalidUtf8,
+    /// `TF018`: a genomic region cannot be parsed
+    InvalidRegion,
+    /// `TF019`: a tabix or CSI index or its BGZF file is malformed
+    InvalidIndex,
+    /// `TF020`: a key occurs more than once, see
+    /// [DuplicatePolicy::Error](enum.DuplicatePolicy.html#variant.Error)
+    DuplicateKey,
+    /// `TF021`: the records of a file are not sorted by a key that must be sorted
+    UnsortedInput,
+    /// `TF022`: a value cannot be used as the name of an output file
+    InvalidFileName,
+    /// `TF023`: more output files would be created than allowed, see
+    /// [SplitOptions::max_files](struct.SplitOptions.html#method.max_files)
+    TooManyFiles,
+    /// `TF024`: a followed file was rotated or truncated and has been opened again, see
+    /// [Tabfile::reopen_on_rotation](struct.Tabfile.html#method.reopen_on_rotation)
+    FileReopened,
+    /// `TF025`: a cursor does not point to the start of a line of the file, see
+    /// [Tabfile::resume_from](struct.Tabfile.html#method.resume_from)
+    InvalidCursor,
+    /// `TF026`: a field cannot be written without breaking the line apart, see
+    /// [QuotePolicy::Never](enum.QuotePolicy.html#variant.Never)
+    UnwritableField,
+}
+
+impl ErrorCode {
+    /// Get the code as a string like `"TF002"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::UnclosedQuote => "TF001",
+            ErrorCode::ColumnCount => "TF002",
+            ErrorCode::MissingField => "TF003",
+            ErrorCode::InvalidField => "TF004",
+            ErrorCode::MissingColumns => "TF005",
+            ErrorCode::EmptyInput => "TF006",
+            ErrorCode::LineTooLong => "TF007",
+            ErrorCode::SeparatorInField => "TF008",
+            ErrorCode::LengthMismatch => "TF009",
+            ErrorCode::UnknownFormatVersion => "TF010",
+            ErrorCode::InvalidPseudonymMap => "TF011",
+            ErrorCode::InvalidSketch => "TF012",
+            ErrorCode::InvalidContract => "TF013",
+            ErrorCode::CompressedFile => "TF014",
+            ErrorCode::UnsupportedDialect => "TF015",
+            ErrorCode::MixedLineEndings => "TF016",
+            ErrorCode::InvalidUtf8 => "TF017",
+            ErrorCode::InvalidRegion => "TF018",
+            ErrorCode::InvalidIndex => "TF019",
+            ErrorCode::DuplicateKey => "TF020",
+            ErrorCode::UnsortedInput => "TF021",
+            ErrorCode::InvalidFileName => "TF022",
+            ErrorCode::TooManyFiles => "TF023",
+            ErrorCode::FileReopened => "TF024",
+            ErrorCode::InvalidCursor => "TF025",
+            ErrorCode::UnwritableField => "TF026",
+        }
+    }
+
+    /// Get the code of an error returned by this crate
+    ///
+    /// Returns `None` for errors that do not come from this crate, like I/O errors.
+    pub fn of(error: &Error) -> Option<ErrorCode> {
+        let inner = error.get_ref()?;
+        if let Some(coded) = inner.downcast_ref::<CodedError>() {
+            Some(coded.code)
+        } else if inner.is::<MissingColumns>() {
+            Some(ErrorCode::MissingColumns)
+        } else if inner.is::<EmptyInput>() {
+            Some(ErrorCode::EmptyInput)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The inner error of the errors of this crate that have no dedicated type
+#[derive(Debug)]
+struct CodedError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_coded(f, self.code, &self.message)
+    }
+}
+
+/// Write the message of an error with its code in front, like `[TF002] line 2: ...`
+pub(crate) fn write_coded<M: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    code: ErrorCode,
+    message: M,
+) -> fmt::Result {
+    write!(f, "[{}] {}", code, message)
+}
+
+impl error::Error for CodedError {}
+
+/// Create an error of kind `InvalidData` with a code
+pub(crate) fn invalid_data<M: Into<String>>(code: ErrorCode, message: M) -> Error {
+    error(ErrorKind::InvalidData, code, message)
+}
+
+/// Create an error with a code
+pub(crate) fn error<M: Into<String>>(kind: ErrorKind, code: ErrorCode, message: M) -> Error {
+    Error::new(
+        kind,
+        CodedError {
+            code,
+            message: message.into(),
+        },
+    )
+}
@@ -0,0 +1,151 @@
+//! Loading small files into memory at once
+
+use crate::code::{self, ErrorCode};
+use crate::{Record, Tabfile};
+use std::collections::hash_map::{Entry, HashMap};
+use std::io::Error;
+
+/// What happens when a key occurs in more than one record.
+///
+/// See [Tabfile::index_by](struct.Tabfile.html#method.index_by).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the first record with the key
+    First,
+    /// Keep the last record with the key
+    Last,
+    /// Return an error of kind `InvalidData` for the second record with the key
+    Error,
+}
+
+/// Get the field at `column` or an error that names the line of `record`
+pub(crate) fn required_field(record: &Record, column: usize) -> Result<&str, Error> {
+    record.iter().nth(column).ok_or_else(|| {
+        code::invalid_data(
+            ErrorCode::MissingField,
+            format!(
+                "line {}: expected a field at column {} but the line has {} fields",
+                record.line_number(),
+                column,
+                record.len()
+            ),
+        )
+    })
+}
+
+impl Tabfile {
+    /// Read all records into a vector.
+    ///
+    /// Stops at the first error.
+    pub fn collect_records(self) -> Result<Vec<Record>, Error> {
+        self.into_iter().collect()
+    }
+
+    /// Read the values of one column into a vector.
+    ///
+    /// Every record must have a field at `column`, otherwise an error of kind `InvalidData` is
+    /// returned. [Null tokens](#method.null_tokens) are returned as they are.
+    pub fn read_column(self, column: usize) -> Result<Vec<String>, Error> {
+        self.into_iter()
+            .map(|record| Ok(required_field(&record?, column)?.to_string()))
+            .collect()
+    }
+
+    /// Read two columns into a map, e.g. to load a lookup table.
+    ///
+    /// If a key occurs several times, the value of the last occurrence is kept. Like with
+    /// [read_column](#method.read_column), every record must have both columns.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"chr1\t248956422\nchr2\t242193529\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let lengths = Tabfile::open(file.path()).unwrap().read_map(0, 1).unwrap();
+    /// assert_eq!(lengths["chr2"], "242193529");
+    /// ```
+    pub fn read_map(
+        self,
+        key_column: usize,
+        value_column: usize,
+    ) -> Result<HashMap<String, String>, Error> {
+        let mut map = HashMap::new();
+        for record in self {
+            let record = record?;
+            map.insert(
+                required_field(&record, key_column)?.to_string(),
+                required_field(&record, value_column)?.to_string(),
+            );
+        }
+        Ok(map)
+    }
+
+    /// Load the records into a map from the field at `column` to the record, e.g. to look up
+    /// rows of a table by their ID.
+    ///
+    /// `duplicates` decides which record is kept if a key occurs several times, use
+    /// [group_by_key](#method.group_by_key) to keep all of them. Every record must have a field
+    /// at `column`, otherwise an error of kind `InvalidData` is returned.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"BRCA1\tchr17\t43044295\nTP53\tchr17\t7668402\nBRCA1\tchr17\t0\n");
+    /// use tabfile::{DuplicatePolicy, Tabfile};
+    ///
+    /// let genes = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .index_by(0, DuplicatePolicy::First)
+    ///     .unwrap();
+    /// assert_eq!(&genes["BRCA1"][2], "43044295");
+    /// ```
+    pub fn index_by(
+        self,
+        column: usize,
+        duplicates: DuplicatePolicy,
+    ) -> Result<HashMap<String, Record>, Error> {
+        let mut map = HashMap::new();
+        for record in self {
+            let record = record?;
+            let key = required_field(&record, column)?.to_string();
+            match (map.entry(key), duplicates) {
+                (Entry::Vacant(entry), _) => {
+                    entry.insert(record);
+                }
+                (Entry::Occupied(_), DuplicatePolicy::First) => {}
+                (Entry::Occupied(mut entry), DuplicatePolicy::Last) => {
+                    entry.insert(record);
+                }
+                (Entry::Occupied(entry), DuplicatePolicy::Error) => {
+                    return Err(code::invalid_data(
+                        ErrorCode::DuplicateKey,
+                        format!(
+                            "line {}: the key `{}` already occurs in line {}",
+                            record.line_number(),
+                            entry.key(),
+                            entry.get().line_number()
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// Load the records into a map from the field at `column` to all records with that key.
+    ///
+    /// The records of a key keep the order of the file. Like with
+    /// [index_by](#method.index_by), every record must have a field at `column`.
+    pub fn group_by_key(self, column: usize) -> Result<HashMap<String, Vec<Record>>, Error> {
+        let mut map: HashMap<String, Vec<Record>> = HashMap::new();
+        for record in self {
+            let record = record?;
+            let key = required_field(&record, column)?.to_string();
+            map.entry(key).or_default().push(record);
+        }
+        Ok(map)
+    }
+}
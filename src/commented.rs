@@ -0,0 +1,84 @@
+//! Iterating over records together with the comment lines between them
+
+use crate::{Record, RowIterator, Tabfile};
+use std::io::Error;
+
+/// A record or a comment line of a tab file.
+///
+/// See [Tabfile::with_comments](struct.Tabfile.html#method.with_comments).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Line {
+    /// A data line split into fields
+    Record(Record),
+    /// A comment line including its comment prefix, but without its line terminator
+    Comment(String),
+}
+
+/// Iterator over the records and comment lines of a tab file.
+///
+/// See [Tabfile::with_comments](struct.Tabfile.html#method.with_comments).
+pub struct WithComments {
+    rows: RowIterator,
+}
+
+impl WithComments {
+    /// Get the underlying row iterator, e.g. for its [fixes](struct.RowIterator.html#method.fixes)
+    pub fn rows(&self) -> &RowIterator {
+        &self.rows
+    }
+}
+
+impl Iterator for WithComments {
+    type Item = Result<Line, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pool = self.rows.tabfile.record_pool.clone();
+        let (mut line, ranges) = match &pool {
+            Some(pool) => pool.take(),
+            None => (String::new(), Vec::new()),
+        };
+        match self.rows.next_line(&mut line, true) {
+            Ok(Some((_, true))) => {
+                let content_length = self.rows.tabfile.dialect.content(&line).len();
+                line.truncate(content_length);
+                Some(Ok(Line::Comment(line)))
+            }
+            Ok(Some((byte_offset, false))) => {
+                let dialect = &self.rows.tabfile.dialect;
+                let record =
+                    dialect.record(line, ranges, self.rows.next_line_number, byte_offset, pool);
+                Some(dialect.check_width(&record).map(|_| Line::Record(record)))
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the records and the comment lines in the order of the file.
+    ///
+    /// Comment lines are normally dropped, which loses information for formats that carry
+    /// metadata in comments. This iterator yields them as [Line::Comment](enum.Line.html) instead,
+    /// so a tool can transform a file and still write the comments back. Skipped and empty lines
+    /// are dropped as usual.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"##fileformat=VCFv4.2\nchr1\t1234\n# end of chr1\nchr2\t5678\n");
+    /// use tabfile::{Line, Tabfile};
+    ///
+    /// for line in Tabfile::open(file.path()).unwrap().with_comments() {
+    ///     match line.unwrap() {
+    ///         Line::Record(record) => println!("{}", record.fields().join("\t")),
+    ///         Line::Comment(comment) => println!("{}", comment),
+    ///     }
+    /// }
+    /// ```
+    pub fn with_comments(self) -> WithComments {
+        WithComments {
+            rows: self.into_iter(),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Reading several files as one stream of records
+
+use crate::{Record, RowIterator, Tabfile};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+type Configure = dyn Fn(Tabfile) -> Tabfile;
+
+/// The records of several files, one file after the other.
+///
+/// See [Tabfile::open_many](struct.Tabfile.html#method.open_many).
+pub struct ConcatTabfile {
+    paths: VecDeque<PathBuf>,
+    configure: Box<Configure>,
+    skip_headers: bool,
+    current: Option<(PathBuf, RowIterator)>,
+    /// Whether a record was returned, so that the header has been seen
+    header_seen: bool,
+}
+
+impl ConcatTabfile {
+    /// Configure the reader of every file with `configure`, e.g. to set the separator.
+    ///
+    /// The files are opened one after the other while iterating, so only the files that have
+    /// not been opened yet are affected.
+    pub fn configure<F: Fn(Tabfile) -> Tabfile + 'static>(mut self, configure: F) -> Self {
+        self.configure = Box::new(configure);
+        self
+    }
+
+    /// Drop the first record of every file except the first one that has records.
+    ///
+    /// Use this if every file starts with the same header line, so that the header occurs only
+    /// once in the records. The first record is the first one that the configured `Tabfile`
+    /// returns, i.e. after the lines that are skipped or commented out. Empty files are
+    /// skipped, so the header is kept if the first file is empty. The default is `false`.
+    pub fn skip_headers(mut self, skip: bool) -> Self {
+        self.skip_headers = skip;
+        self
+    }
+
+    /// Get the path of the file that the last record was read from
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Open the next file, returns whether there is one
+    fn open_next(&mut self) -> Result<bool, Error> {
+        let path = match self.paths.pop_front() {
+            Some(path) => path,
+            None => {
+                self.current = None;
+                return Ok(false);
+            }
+        };
+        let rows = (self.configure)(Tabfile::open(&path)?).into_iter();
+        // the records after a header that cannot be read are still returned
+        let (_, rows) = self.current.insert((path, rows));
+        if self.skip_headers && self.header_seen {
+            rows.next().transpose()?;
+        }
+        Ok(true)
+    }
+}
+
+impl Iterator for ConcatTabfile {
+    type Item = Result<Record, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, rows)) = &mut self.current {
+                if let Some(record) = rows.next() {
+                    self.header_seen = true;
+                    return Some(record);
+                }
+            }
+            match self.open_next() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Tabfile {
+    /// Read several files as one stream of records, e.g. the shards `part-0000.tsv` to
+    /// `part-0099.tsv` of a table.
+    ///
+    /// The files are read in the order of `paths` and opened one at a time, so there is only
+    /// one open file at any time. Every file is opened with [open](#method.open) and can be
+    /// configured with [ConcatTabfile::configure](struct.ConcatTabfile.html#method.configure).
+    /// This function only checks that all files exist, so that a missing shard is reported
+    /// before any record is read. The line numbers and byte offsets of the records refer to
+    /// the file they come from, see
+    /// [current_path](struct.ConcatTabfile.html#method.current_path).
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut part0 = NamedTempFile::new().unwrap();
+    /// # part0.write_all(b"id,score\na,1\n");
+    /// # let mut part1 = NamedTempFile::new().unwrap();
+    /// # part1.write_all(b"id,score\nb,2\nc,3\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let records: Vec<String> = Tabfile::open_many(&[part0.path(), part1.path()])
+    ///     .unwrap()
+    ///     .configure(|tsv_file| tsv_file.separator(','))
+    ///     .skip_headers(true)
+    ///     .map(|record| record.unwrap()[0].to_string())
+    ///     .collect();
+    /// assert_eq!(records, vec!["id", "a", "b", "c"]);
+    /// ```
+    pub fn open_many<I, P>(paths: I) -> Result<ConcatTabfile, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                fs::metadata(path)?;
+                Ok(path.to_path_buf())
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(ConcatTabfile {
+            paths,
+            configure: Box::new(|tabfile| tabfile),
+            skip_headers: false,
+            current: None,
+            header_seen: false,
+        })
+    }
+}
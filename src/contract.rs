@@ -0,0 +1,285 @@
+//! Validating tab files against a sidecar contract
+
+use crate::code::{self, ErrorCode};
+use crate::parse::Separator;
+use crate::{ColumnType, Tabfile};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+/// The expectations on one column of a [Contract](struct.Contract.html).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnContract {
+    pub(crate) name: Option<String>,
+    #[serde(default = "text", rename = "type")]
+    pub(crate) column_type: ColumnType,
+    #[serde(default)]
+    pub(crate) required: bool,
+    #[serde(default)]
+    pub(crate) unique: bool,
+    pub(crate) min: Option<f64>,
+    pub(crate) max: Option<f64>,
+    pub(crate) allowed: Option<Vec<String>>,
+}
+
+fn text() -> ColumnType {
+    ColumnType::Text
+}
+
+fn default_header() -> bool {
+    true
+}
+
+/// A machine-readable description of what a tab file has to look like.
+///
+/// Contracts are stored as JSON next to the data file, for example:
+///
+/// ```json
+/// {
+///     "separator": "\t",
+///     "header": true,
+///     "columns": [
+///         {"name": "id", "type": "integer", "required": true, "unique": true},
+///         {"name": "score", "type": "float", "min": 0, "max": 1},
+///         {"name": "status", "allowed": ["active", "inactive"]}
+///     ]
+/// }
+/// ```
+///
+/// All keys except `columns` are optional. If `header` is `true` (the default), the first record
+/// is compared to the column names instead of being validated as data. The column types are the
+/// lowercase names of [ColumnType](enum.ColumnType.html). A value is missing if the field does
+/// not exist, is empty or is a [null token](crate::Tabfile::null_tokens).
+///
+/// This type is only available with the `contract` feature.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Contract {
+    pub(crate) separator: Option<String>,
+    #[serde(default = "default_header")]
+    pub(crate) header: bool,
+    pub(crate) columns: Vec<ColumnContract>,
+}
+
+impl Contract {
+    /// Parse a contract from a JSON string
+    pub fn from_json(json: &str) -> Result<Contract, Error> {
+        let contract: Contract = serde_json::from_str(json)
+            .map_err(|e| code::invalid_data(ErrorCode::InvalidContract, e.to_string()))?;
+        if contract.separator.as_deref() == Some("") {
+            return Err(code::invalid_data(
+                ErrorCode::InvalidContract,
+                "the separator of a contract must not be empty",
+            ));
+        }
+        Ok(contract)
+    }
+
+    /// Read a contract from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Contract, Error> {
+        Contract::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// What is wrong with a value or record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// The header does not have the expected column name
+    HeaderMismatch { expected: String, found: String },
+    /// The record does not have the expected number of fields
+    ColumnCount { expected: usize, found: usize },
+    /// The value cannot be parsed as the type of the column
+    Type { expected: ColumnType, value: String },
+    /// A required value is missing
+    Missing,
+    /// The value of a unique column occurred before
+    Duplicate { value: String },
+    /// The number is smaller than `min` or larger than `max`
+    OutOfRange { value: String },
+    /// The value is not in the list of allowed values
+    NotAllowed { value: String },
+}
+
+/// A single violation of a contract.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    line_number: usize,
+    column: Option<usize>,
+    kind: ViolationKind,
+}
+
+impl Violation {
+    /// Get the line number of the offending record
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the index of the offending column or `None` if the whole record is affected
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Get the kind of the violation
+    pub fn kind(&self) -> &ViolationKind {
+        &self.kind
+    }
+}
+
+/// The result of checking a file against a [Contract](struct.Contract.html).
+#[derive(Clone, Debug)]
+pub struct ContractReport {
+    records: usize,
+    violations: Vec<Violation>,
+}
+
+impl ContractReport {
+    /// Get the number of data records that were checked
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Get all violations in the order of their occurrence
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Check whether the file fulfills the contract
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Tabfile {
+    /// Validate the file against the contract stored at `contract_path`.
+    ///
+    /// See [Contract](struct.Contract.html) for the format of the contract and
+    /// [check_contract_with](#method.check_contract_with) for details on the checks.
+    ///
+    /// This method is only available with the `contract` feature.
+    pub fn check_contract<P: AsRef<Path>>(self, contract_path: P) -> Result<ContractReport, Error> {
+        let contract = Contract::load(contract_path)?;
+        self.check_contract_with(&contract)
+    }
+
+    /// Validate the file against a contract.
+    ///
+    /// If the contract declares a separator, it replaces the separator of this `Tabfile`, so a
+    /// file with the wrong separator shows up as a column count violation on every record. All
+    /// violations are collected instead of stopping at the first one. I/O errors and invalid
+    /// UTF-8 are returned as errors.
+    ///
+    /// This method is only available with the `contract` feature.
+    pub fn check_contract_with(mut self, contract: &Contract) -> Result<ContractReport, Error> {
+        if let Some(separator) = &contract.separator {
+            let mut chars = separator.chars();
+            self.dialect.separator = match (chars.next(), chars.next()) {
+                (Some(c), None) => Separator::Char(c),
+                _ => Separator::Str(separator.clone()),
+            };
+        }
+        let mut seen: Vec<HashSet<String>> = vec![HashSet::new(); contract.columns.len()];
+        let mut report = ContractReport {
+            records: 0,
+            violations: Vec::new(),
+        };
+        let mut expect_header = contract.header;
+        for record in self {
+            let record = record?;
+            let line_number = record.line_number();
+            let is_header = expect_header;
+            expect_header = false;
+            if !is_header {
+                report.records += 1;
+            }
+            let mut violation = |column, kind| {
+                report.violations.push(Violation {
+                    line_number,
+                    column,
+                    kind,
+                })
+            };
+            if record.len() != contract.columns.len() {
+                violation(
+                    None,
+                    ViolationKind::ColumnCount {
+                        expected: contract.columns.len(),
+                        found: record.len(),
+                    },
+                );
+            }
+            if is_header {
+                for (i, column) in contract.columns.iter().enumerate() {
+                    let found = record.iter().nth(i).unwrap_or("");
+                    match &column.name {
+                        Some(expected) if expected != found => violation(
+                            Some(i),
+                            ViolationKind::HeaderMismatch {
+                                expected: expected.clone(),
+                                found: found.to_string(),
+                            },
+                        ),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            for (i, column) in contract.columns.iter().enumerate() {
+                let value = match record.get_opt(i) {
+                    Some(value) if !value.is_empty() => value,
+                    _ => {
+                        if column.required {
+                            violation(Some(i), ViolationKind::Missing);
+                        }
+                        continue;
+                    }
+                };
+                if !column.column_type.accepts(value) {
+                    violation(
+                        Some(i),
+                        ViolationKind::Type {
+                            expected: column.column_type,
+                            value: value.to_string(),
+                        },
+                    );
+                    continue;
+                }
+                if column.min.is_some() || column.max.is_some() {
+                    let in_range = value.parse::<f64>().is_ok_and(|number| {
+                        column.min.is_none_or(|min| number >= min)
+                            && column.max.is_none_or(|max| number <= max)
+                    });
+                    if !in_range {
+                        violation(
+                            Some(i),
+                            ViolationKind::OutOfRange {
+                                value: value.to_string(),
+                            },
+                        );
+                    }
+                }
+                if let Some(allowed) = &column.allowed {
+                    if !allowed.iter().any(|allowed| allowed == value) {
+                        violation(
+                            Some(i),
+                            ViolationKind::NotAllowed {
+                                value: value.to_string(),
+                            },
+                        );
+                    }
+                }
+                if column.unique && !seen[i].insert(value.to_string()) {
+                    violation(
+                        Some(i),
+                        ViolationKind::Duplicate {
+                            value: value.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,172 @@
+//! Counting the records of a file without building them
+
+use crate::{Dialect, Tabfile};
+use std::collections::VecDeque;
+use std::io::{Error, Read, Seek, SeekFrom};
+use std::mem;
+
+/// The number of bytes that are scanned at once
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Check whether a line with its terminator is a data line without decoding it if possible
+fn is_data_bytes(dialect: &Dialect, line: &[u8]) -> bool {
+    let is_comment = dialect
+        .comment_prefixes
+        .iter()
+        .any(|prefix| line.starts_with(prefix.as_bytes()));
+    if is_comment {
+        return false;
+    }
+    if !dialect.skip_empty_lines {
+        return true;
+    }
+    // `str::trim` also removes the vertical tab and non-ASCII whitespace
+    match line
+        .iter()
+        .find(|&&byte| !(byte.is_ascii_whitespace() || byte == b'\x0b'))
+    {
+        None => false,
+        Some(byte) if byte.is_ascii() => true,
+        Some(_) => dialect.is_data_line(&String::from_utf8_lossy(line)),
+    }
+}
+
+/// Counts the data lines of a file that do not belong to its footer
+struct LineCounter<'a> {
+    dialect: &'a Dialect,
+    records: usize,
+    footer_size: usize,
+    /// Whether the lines that might belong to the footer are data lines
+    footer: VecDeque<bool>,
+}
+
+impl LineCounter<'_> {
+    fn line(&mut self, line: &[u8]) {
+        let is_data = is_data_bytes(self.dialect, line);
+        if self.footer_size == 0 {
+            self.records += is_data as usize;
+            return;
+        }
+        self.footer.push_back(is_data);
+        if self.footer.len() > self.footer_size {
+            let no_footer = self.footer.pop_front().expect("the footer is not empty");
+            self.records += no_footer as usize;
+        }
+    }
+}
+
+impl Tabfile {
+    /// Count the records of the file without building them.
+    ///
+    /// The file is scanned in large blocks for line terminators, so this is much faster than
+    /// counting the records of the iterator, e.g. to report a total or to allocate space for
+    /// all records up front. The lines are filtered like by the iterator, i.e. with
+    /// `skip_lines`, `skip_lines_while`, `skip_footer`, comment prefixes, `skip_empty_lines`
+    /// and [limit](#method.limit), but the lines are not checked for valid UTF-8 or the
+    /// number of their fields. With a [quote character](#method.quote_character), `auto_fix`
+    /// or a maximum line length, the lines are read one by one instead, so that records with
+    /// line breaks and errors are handled like by the iterator. Iterating the `Tabfile`
+    /// afterwards starts at the beginning again.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# results\nid\tscore\n\na\t1\nb\t2\n").unwrap();
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut tsv_file = Tabfile::open(file.path()).unwrap();
+    /// assert_eq!(tsv_file.count_records().unwrap(), 3);
+    /// let mut records = Vec::with_capacity(3);
+    /// for record in tsv_file {
+    ///     records.push(record.unwrap());
+    /// }
+    /// ```
+    pub fn count_records(&mut self) -> Result<usize, Error> {
+        let needs_parsing =
+            self.dialect.quote.is_some() || self.auto_fix || self.max_line_length.is_some();
+        let result = if needs_parsing {
+            self.count_parsed()
+        } else {
+            self.count_scanned()
+        };
+        self.reader.seek(SeekFrom::Start(self.start_offset))?;
+        let records = result?;
+        Ok(self.limit.map_or(records, |limit| records.min(limit)))
+    }
+
+    /// Count the records by reading the lines like the iterator does
+    fn count_parsed(&mut self) -> Result<usize, Error> {
+        let placeholder = Tabfile::open(&self.path)?;
+        // neither report progress nor wait for more lines while counting
+        let progress_callback = self.progress_callback.take();
+        let follow = mem::replace(&mut self.follow, false);
+        let mut rows = mem::replace(self, placeholder).into_iter();
+        let mut line = String::new();
+        let mut result = Ok(0);
+        loop {
+            match rows.next_data_line(&mut line) {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if result.is_ok() {
+            result = Ok(rows.records_read());
+        }
+        *self = rows.tabfile;
+        self.progress_callback = progress_callback;
+        self.follow = follow;
+        result
+    }
+
+    /// Count the records by searching for line terminators in blocks of the file
+    fn count_scanned(&mut self) -> Result<usize, Error> {
+        let start = self.skipped_offset(&mut self.line_reader())?;
+        // the buffer of the reader is discarded by seeking, so the file can be read directly
+        self.reader.seek(SeekFrom::Start(start))?;
+        let terminator = self.dialect.terminator;
+        let mut counter = LineCounter {
+            dialect: &self.dialect,
+            records: 0,
+            footer_size: self.skip_footer,
+            footer: VecDeque::with_capacity(self.skip_footer + 1),
+        };
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut partial = Vec::new();
+        loop {
+            let length = self.reader.get_mut().read(&mut chunk)?;
+            if length == 0 {
+                break;
+            }
+            let mut rest = &chunk[..length];
+            while let Some(end) = find_terminator(terminator, rest) {
+                let (line, after) = rest.split_at(end + 1);
+                if partial.is_empty() {
+                    counter.line(line);
+                } else {
+                    partial.extend_from_slice(line);
+                    counter.line(&partial);
+                    partial.clear();
+                }
+                rest = after;
+            }
+            partial.extend_from_slice(rest);
+        }
+        if !partial.is_empty() {
+            counter.line(&partial);
+        }
+        Ok(counter.records)
+    }
+}
+
+/// Find the first `terminator` in `bytes`
+fn find_terminator(terminator: u8, bytes: &[u8]) -> Option<usize> {
+    #[cfg(feature = "memchr")]
+    return memchr::memchr(terminator, bytes);
+    #[cfg(not(feature = "memchr"))]
+    bytes.iter().position(|&byte| byte == terminator)
+}
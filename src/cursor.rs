@@ -0,0 +1,147 @@
+//! Saving the position of an iteration to continue it later
+
+use crate::code::{self, ErrorCode};
+use crate::{RowIterator, Tabfile};
+use std::fmt;
+use std::io::{Error, Read, Seek, SeekFrom};
+use std::str::FromStr;
+
+/// The position of an iteration over a file, see
+/// [RowIterator::cursor](struct.RowIterator.html#method.cursor).
+///
+/// A cursor can be stored with `serde` if the `serde` feature is enabled, or as text: it is
+/// displayed as `byte_offset:line_number:records_read` and parsed from the same form.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cursor {
+    byte_offset: u64,
+    line_number: usize,
+    records_read: usize,
+}
+
+impl Cursor {
+    /// Get the byte offset of the next line that will be read
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// Get the line number of the line that was read last, or zero before the first line
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the number of records that were read before
+    pub fn records_read(&self) -> usize {
+        self.records_read
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.byte_offset, self.line_number, self.records_read
+        )
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+    fn from_str(text: &str) -> Result<Cursor, Error> {
+        let invalid = || {
+            code::invalid_data(
+                ErrorCode::InvalidCursor,
+                format!("`{}` is not a cursor like `1024:17:15`", text),
+            )
+        };
+        let mut parts = text.trim().split(':');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let cursor = Cursor {
+            byte_offset: next()?.parse().map_err(|_| invalid())?,
+            line_number: next()?.parse().map_err(|_| invalid())?,
+            records_read: next()?.parse().map_err(|_| invalid())?,
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(cursor)
+    }
+}
+
+impl RowIterator {
+    /// Get the position of the iteration, to continue it later with
+    /// [Tabfile::resume_from](struct.Tabfile.html#method.resume_from).
+    ///
+    /// Take the cursor after a record has been processed, then resuming starts with the
+    /// following record.
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            byte_offset: self.byte_offset(),
+            line_number: self.line_number(),
+            records_read: self.records_read(),
+        }
+    }
+}
+
+impl Tabfile {
+    /// Continue an iteration at a [Cursor](struct.Cursor.html) taken in an earlier run.
+    ///
+    /// Unlike [seek_to](#method.seek_to), the line numbers of the records and the number of
+    /// records read continue where the cursor was taken. Lines skipped with `skip_lines` and
+    /// `skip_lines_while` are not skipped again and the empty input policy does not apply, but
+    /// `skip_footer` does. The file must not have changed in front of the cursor, or the
+    /// records are misaligned. As a check against a replaced or truncated file, the cursor
+    /// must point behind a line terminator or to the end of the file, otherwise an error of
+    /// kind `InvalidData` is returned. A cursor taken before the first line starts from the
+    /// beginning.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# batch job input\n1\tfoo\n2\tbar\n3\tbaz\n");
+    /// use tabfile::{Cursor, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().into_iter();
+    /// rows.next().unwrap().unwrap();
+    /// let saved = rows.cursor().to_string();
+    ///
+    /// // in the next run
+    /// let cursor: Cursor = saved.parse().unwrap();
+    /// let mut rows = Tabfile::open(file.path()).unwrap().resume_from(cursor).unwrap();
+    /// let record = rows.next().unwrap().unwrap();
+    /// assert_eq!((record.fields(), record.line_number()), (vec!["2", "bar"], 3));
+    /// assert_eq!(rows.records_read(), 2);
+    /// ```
+    pub fn resume_from(mut self, cursor: Cursor) -> Result<RowIterator, Error> {
+        if cursor.line_number == 0 {
+            return Ok(self.into_iter());
+        }
+        let file_size = self.reader.seek(SeekFrom::End(0))?;
+        let at_line_start = if cursor.byte_offset == 0 || cursor.byte_offset > file_size {
+            false
+        } else {
+            let mut previous = [0];
+            self.reader.seek(SeekFrom::Start(cursor.byte_offset - 1))?;
+            self.reader.read_exact(&mut previous)?;
+            previous[0] == self.dialect.terminator || cursor.byte_offset == file_size
+        };
+        if !at_line_start {
+            return Err(code::invalid_data(
+                ErrorCode::InvalidCursor,
+                format!(
+                    "the cursor at byte offset {} does not point to the start of a line",
+                    cursor.byte_offset
+                ),
+            ));
+        }
+        let mut rows = self.into_iter();
+        rows.seek_data_line(
+            cursor.byte_offset,
+            cursor.line_number + 1,
+            cursor.records_read,
+        )?;
+        Ok(rows)
+    }
+}
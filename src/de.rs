@@ -0,0 +1,852 @@
+//! Deserializing records into structs with serde
+
+use crate::code::{self, ErrorCode};
+use crate::{Header, MissingColumns, Record, RowIterator, Schema, SchemaVersions, Tabfile};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::Deserialize;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::marker::PhantomData;
+
+/// An error that occurred while deserializing a record
+#[derive(Debug)]
+struct DeError(String);
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        DeError(format!("missing column `{}`", field))
+    }
+}
+
+/// The outcome of probing which fields a type deserializes from
+#[derive(Debug)]
+enum Probe {
+    Struct(&'static [&'static str]),
+    /// A field that is required, but was not given
+    Missing(&'static str),
+    Other,
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("probe")
+    }
+}
+
+impl std::error::Error for Probe {}
+
+impl de::Error for Probe {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Probe::Other
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Probe::Missing(field)
+    }
+}
+
+/// A deserializer that only finds out the names of the fields of a struct
+struct FieldsProbe;
+
+impl<'de> de::Deserializer<'de> for FieldsProbe {
+    type Error = Probe;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Probe> {
+        Err(Probe::Other)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Probe> {
+        Err(Probe::Struct(fields))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Probe> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// A deserializer that only gives the listed fields of a struct, to find out which are required
+struct RequiredProbe<'a>(&'a [&'static str]);
+
+impl<'de, 'a> de::Deserializer<'de> for RequiredProbe<'a> {
+    type Error = Probe;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Probe> {
+        Err(Probe::Other)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Probe> {
+        visitor.visit_map(GivenFields {
+            fields: fields.iter(),
+            given: self.0,
+        })
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Probe> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Yields the given fields of a struct with empty values
+struct GivenFields<'a> {
+    fields: std::slice::Iter<'static, &'static str>,
+    given: &'a [&'static str],
+}
+
+impl<'de, 'a> MapAccess<'de> for GivenFields<'a> {
+    type Error = Probe;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Probe> {
+        let given = self.given;
+        match self.fields.find(|field| given.contains(field)) {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Probe> {
+        // a missing field of a nested struct is not a missing column
+        seed.deserialize(EmptyValue).map_err(|_| Probe::Other)
+    }
+}
+
+macro_rules! deserialize_empty {
+    ($($method:ident => $visit:ident($value:expr),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+/// A deserializer for a zero, empty or missing value of any type
+struct EmptyValue;
+
+impl<'de> de::Deserializer<'de> for EmptyValue {
+    type Error = Probe;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Probe> {
+        Err(Probe::Other)
+    }
+
+    deserialize_empty! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_identifier => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Probe> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Probe> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+        visitor.visit_seq(de::value::SeqDeserializer::new(std::iter::empty::<u8>()))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+        visitor.visit_map(de::value::MapDeserializer::new(
+            std::iter::empty::<(u8, u8)>(),
+        ))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Probe> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        tuple tuple_struct struct enum
+    }
+}
+
+/// Find the fields of `T` that are required, but neither in the header nor defaulted by `schema`
+///
+/// Each probe reports the first required field that was not given, so the fields are given one
+/// after the other until the struct is complete. Types that are not plain structs need nothing.
+fn missing_columns<'de, T: Deserialize<'de>>(header: &Header, schema: &Schema) -> Vec<String> {
+    let mut given = Vec::new();
+    let mut missing = Vec::new();
+    while let Err(Probe::Missing(field)) = T::deserialize(RequiredProbe(&given)) {
+        if given.contains(&field) {
+            break;
+        }
+        given.push(field);
+        if header.position(field).is_none() && schema.get_default_value(field).is_none() {
+            missing.push(field.to_string());
+        }
+    }
+    missing
+}
+
+/// Find the number of leading columns that contain all fields of `T`
+///
+/// Returns `None` if `T` is not a plain struct, e.g. a map, which may need every column.
+fn needed_columns<'de, T: Deserialize<'de>>(header: &Header) -> Option<usize> {
+    match T::deserialize(FieldsProbe) {
+        Err(Probe::Struct(fields)) => fields
+            .iter()
+            .filter_map(|field| header.position(field))
+            .max()
+            .map(|position| position + 1),
+        _ => None,
+    }
+}
+
+/// Deserializes a whole record as a struct, map, tuple or sequence
+struct RecordDeserializer<'de> {
+    record: &'de Record,
+    header: Option<&'de Header>,
+    schema: Option<&'de Schema>,
+}
+
+impl<'de> de::Deserializer<'de> for RecordDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.header {
+            Some(_) => self.deserialize_map(visitor),
+            None => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        match self.header {
+            Some(header) => visitor.visit_map(StructAccess {
+                record: self.record,
+                header,
+                schema: self.schema,
+                fields: fields.iter(),
+                current: None,
+            }),
+            None => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.header {
+            Some(header) => visitor.visit_map(HeaderAccess {
+                record: self.record,
+                schema: self.schema,
+                names: header.names().iter().enumerate(),
+                current: None,
+            }),
+            None => Err(DeError(
+                "a record can only be deserialized as a map with a header".to_string(),
+            )),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(FieldsAccess {
+            record: self.record,
+            position: 0,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Yields the fields of a struct that occur in the header
+struct StructAccess<'de> {
+    record: &'de Record,
+    header: &'de Header,
+    schema: Option<&'de Schema>,
+    fields: std::slice::Iter<'static, &'static str>,
+    current: Option<(&'static str, Option<usize>)>,
+}
+
+impl<'de> MapAccess<'de> for StructAccess<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        for field in &mut self.fields {
+            let position = self.header.position(field);
+            let has_default = self
+                .schema
+                .is_some_and(|schema| schema.get_default_value(field).is_some());
+            if position.is_some() || has_default {
+                self.current = Some((field, position));
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let (field, position) = self.current.take().expect("value requested before key");
+        seed.deserialize(FieldDeserializer::named(
+            self.record,
+            position,
+            field,
+            self.schema,
+        ))
+        .map_err(|e| DeError(format!("column `{}`: {}", field, e)))
+    }
+}
+
+/// Yields every column of the header with its value
+struct HeaderAccess<'de> {
+    record: &'de Record,
+    schema: Option<&'de Schema>,
+    names: std::iter::Enumerate<std::slice::Iter<'de, String>>,
+    current: Option<(&'de str, Option<usize>)>,
+}
+
+impl<'de> MapAccess<'de> for HeaderAccess<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        match self.names.next() {
+            Some((position, name)) => {
+                self.current = Some((name, Some(position)));
+                seed.deserialize(name.as_str().into_deserializer())
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let (name, position) = self.current.take().expect("value requested before key");
+        seed.deserialize(FieldDeserializer::named(
+            self.record,
+            position,
+            name,
+            self.schema,
+        ))
+        .map_err(|e| DeError(format!("column `{}`: {}", name, e)))
+    }
+}
+
+/// Yields the fields of a record in order
+struct FieldsAccess<'de> {
+    record: &'de Record,
+    position: usize,
+}
+
+impl<'de> SeqAccess<'de> for FieldsAccess<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        if self.position >= self.record.len() {
+            return Ok(None);
+        }
+        let position = self.position;
+        self.position += 1;
+        seed.deserialize(FieldDeserializer::new(self.record.get_opt(position)))
+            .map(Some)
+            .map_err(|e| DeError(format!("field {}: {}", position, e)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.record.len() - self.position)
+    }
+}
+
+/// Deserializes a single field, which is `None` if it is missing or null
+struct FieldDeserializer<'de> {
+    value: Option<&'de str>,
+    list_separator: Option<char>,
+}
+
+impl<'de> FieldDeserializer<'de> {
+    fn new(value: Option<&'de str>) -> FieldDeserializer<'de> {
+        FieldDeserializer {
+            value,
+            list_separator: None,
+        }
+    }
+
+    /// Deserialize the field at `position` with the rules for the column `name`
+    ///
+    /// The default value of the column is used if there is no such column or if the field is
+    /// missing or null.
+    fn named(
+        record: &'de Record,
+        position: Option<usize>,
+        name: &str,
+        schema: Option<&'de Schema>,
+    ) -> FieldDeserializer<'de> {
+        let value = position.and_then(|position| record.get_opt(position));
+        FieldDeserializer {
+            value: value.or_else(|| schema.and_then(|schema| schema.get_default_value(name))),
+            list_separator: schema.and_then(|schema| schema.get_list_separator(name)),
+        }
+    }
+
+    fn value(&self) -> Result<&'de str, DeError> {
+        self.value
+            .ok_or_else(|| DeError("missing value".to_string()))
+    }
+}
+
+/// Yields the parts of a field that holds several values
+struct ListAccess<'de> {
+    parts: Option<std::str::Split<'de, char>>,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        match self.parts.as_mut().and_then(Iterator::next) {
+            Some(part) => seed
+                .deserialize(FieldDeserializer::new(Some(part)))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                let value = self.value()?;
+                match value.parse::<$ty>() {
+                    Ok(parsed) => visitor.$visit(parsed),
+                    Err(_) => Err(DeError(format!(
+                        "cannot parse `{}` as {}",
+                        value,
+                        stringify!($ty)
+                    ))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FieldDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.value()?)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_borrowed_bytes(self.value()?.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value {
+            None | Some("") => visitor.visit_none(),
+            Some(_) => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value {
+            None | Some("") => visitor.visit_unit(),
+            Some(value) => Err(DeError(format!(
+                "expected an empty field, found `{}`",
+                value
+            ))),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(self.value()?.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let separator = self
+            .list_separator
+            .ok_or_else(|| DeError("no list separator is defined for the column".to_string()))?;
+        let parts = match self.value()? {
+            "" => None,
+            value => Some(value.split(separator)),
+        };
+        visitor.visit_seq(ListAccess { parts })
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string tuple tuple_struct map struct identifier
+    }
+}
+
+impl Record {
+    /// Deserialize the record into `T` with serde.
+    ///
+    /// With a header, structs and maps are filled by column name, so the order of the columns in
+    /// the file does not matter. Without a header the fields are taken in order, which also
+    /// works for tuples and sequences. Empty and [null](crate::Tabfile::null_tokens) fields
+    /// deserialize to `None` for `Option` fields. Errors are of kind `InvalidData` and name the
+    /// line and the offending column.
+    ///
+    /// This method is only available with the `serde` feature.
+    pub fn deserialize<'de, T: Deserialize<'de>>(
+        &'de self,
+        header: Option<&'de Header>,
+    ) -> Result<T, Error> {
+        self.deserialize_inner(header, None)
+    }
+
+    /// Deserialize the record into `T` following the rules of `schema`.
+    ///
+    /// This works like [deserialize](#method.deserialize), but the columns are interpreted
+    /// according to the schema, e.g. cells with several values can be split into a `Vec`.
+    /// Columns are identified by their names in `header`.
+    ///
+    /// This method is only available with the `serde` feature.
+    pub fn deserialize_with_schema<'de, T: Deserialize<'de>>(
+        &'de self,
+        header: &'de Header,
+        schema: &'de Schema,
+    ) -> Result<T, Error> {
+        self.deserialize_inner(Some(header), Some(schema))
+    }
+
+    fn deserialize_inner<'de, T: Deserialize<'de>>(
+        &'de self,
+        header: Option<&'de Header>,
+        schema: Option<&'de Schema>,
+    ) -> Result<T, Error> {
+        T::deserialize(RecordDeserializer {
+            record: self,
+            header,
+            schema,
+        })
+        .map_err(|e| {
+            code::invalid_data(
+                ErrorCode::InvalidField,
+                format!("line {}: {}", self.line_number(), e),
+            )
+        })
+    }
+}
+
+/// Iterator over the records of a tab file deserialized into `T`.
+///
+/// See [Tabfile::deserialize](struct.Tabfile.html#method.deserialize).
+pub struct DeserializeRecords<T> {
+    rows: RowIterator,
+    header: Option<Header>,
+    schema: Schema,
+    versions: Option<SchemaVersions>,
+    version: Option<String>,
+    finished: bool,
+    marker: PhantomData<T>,
+}
+
+/// The comment that declares the version of a file format
+const VERSION_MARKER: &str = "##version=";
+
+/// Get the value of the first version comment in `comments`
+fn version_marker(comments: &[String]) -> Option<&str> {
+    comments
+        .iter()
+        .find_map(|comment| comment.strip_prefix(VERSION_MARKER))
+        .map(str::trim)
+}
+
+impl<T> DeserializeRecords<T> {
+    /// Interpret the columns according to `schema`, e.g. to split cells with several values.
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Pick the schema from several versions that matches the file.
+    ///
+    /// The version is chosen when the header is read: first by a `##version=<marker>` comment
+    /// before the header, then by the exact column names of the header, see
+    /// [SchemaVersions](struct.SchemaVersions.html). This requires the default
+    /// [comment character](struct.Tabfile.html#method.comment_character) `#`. If no
+    /// version matches, the first item is an error of kind `InvalidData` and the iteration ends.
+    /// This replaces any schema set with [schema](#method.schema).
+    pub fn schema_versions(mut self, versions: SchemaVersions) -> Self {
+        self.versions = Some(versions);
+        self
+    }
+
+    /// Get the header once it has been read by the first call to `next`
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Get the marker of the version that was picked by `schema_versions`
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Read the header and choose the schema
+    fn read_header(&mut self) -> Option<Result<(), Error>> {
+        let header = match self.rows.next()? {
+            Ok(record) => Header::from_record(&record),
+            Err(e) => return Some(Err(e)),
+        };
+        if let Some(versions) = &self.versions {
+            let marker = version_marker(self.rows.leading_comments());
+            match versions.select(marker, &header) {
+                Some((version, schema)) => {
+                    self.version = Some(version.to_string());
+                    self.schema = schema.clone();
+                }
+                None => {
+                    let message = match marker {
+                        Some(marker) => format!("unknown format version `{}`", marker),
+                        None => format!(
+                            "line {}: the header does not match any known format version",
+                            self.rows.next_line_number
+                        ),
+                    };
+                    return Some(Err(code::invalid_data(
+                        ErrorCode::UnknownFormatVersion,
+                        message,
+                    )));
+                }
+            }
+        }
+        self.header = Some(header);
+        Some(Ok(()))
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for DeserializeRecords<T> {
+    type Item = Result<T, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self.header.is_none() {
+            if let Err(e) = self.read_header()? {
+                self.finished = true;
+                return Some(Err(e));
+            }
+            let missing = missing_columns::<T>(self.header.as_ref().unwrap(), &self.schema);
+            if !missing.is_empty() {
+                self.finished = true;
+                let missing = MissingColumns { columns: missing };
+                return Some(Err(Error::new(ErrorKind::InvalidData, missing)));
+            }
+            // the width of ragged rows is checked on all fields, so they must be split out
+            if self.rows.tabfile.dialect.ragged.is_none() {
+                self.rows.tabfile.dialect.field_limit =
+                    needed_columns::<T>(self.header.as_ref().unwrap());
+            }
+        }
+        Some(
+            self.rows.next()?.and_then(|record| {
+                record.deserialize_inner(self.header.as_ref(), Some(&self.schema))
+            }),
+        )
+    }
+}
+
+impl Tabfile {
+    /// Deserialize every record after the header into `T` with serde.
+    ///
+    /// The first record is taken as the header and struct fields are matched to columns by name,
+    /// so a provider may reorder or add columns without breaking the import. Columns that are
+    /// not needed by `T` are ignored: their values are never parsed and if `T` is a struct, lines
+    /// are only split up to the last column that `T` needs, which makes reading a few columns of
+    /// very wide files cheap. The columns are resolved once when the header is read: if the
+    /// columns of non-`Option` fields are missing from the header, the first item is an error
+    /// of kind `InvalidData` with a [MissingColumns](struct.MissingColumns.html) error inside
+    /// that names all of them, even if there are no records, and the iteration ends.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"qual\tchrom\tpos\n0.5\tchr1\t1234\n");
+    /// use serde::Deserialize;
+    /// use tabfile::Tabfile;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Variant {
+    ///     chrom: String,
+    ///     pos: u64,
+    ///     qual: Option<f32>,
+    /// }
+    ///
+    /// for variant in Tabfile::open(file.path()).unwrap().deserialize::<Variant>() {
+    ///     let variant = variant.unwrap();
+    ///     println!("{}:{}", variant.chrom, variant.pos);
+    /// }
+    /// ```
+    ///
+    /// This method is only available with the `serde` feature.
+    pub fn deserialize<T: DeserializeOwned>(self) -> DeserializeRecords<T> {
+        DeserializeRecords {
+            rows: self.into_iter(),
+            header: None,
+            schema: Schema::new(),
+            versions: None,
+            version: None,
+            finished: false,
+            marker: PhantomData,
+        }
+    }
+}
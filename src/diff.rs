@@ -0,0 +1,167 @@
+//! Comparing the records of two tab files
+
+use crate::code::{self, ErrorCode};
+use crate::collect::required_field;
+use crate::{DuplicatePolicy, Record, Tabfile};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Error;
+
+/// How [diff](fn.diff.html) pairs and compares records.
+///
+/// By default, records are paired by their position and all columns are compared.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    key_column: Option<usize>,
+    ignored_columns: Vec<usize>,
+}
+
+impl DiffOptions {
+    /// Create the default options
+    pub fn new() -> DiffOptions {
+        DiffOptions::default()
+    }
+
+    /// Pair the records by their field at `column` instead of their position, or by position
+    /// for `None`. The default is `None`.
+    ///
+    /// Every key must occur only once per file, otherwise an error of kind `InvalidData` is
+    /// returned.
+    pub fn key_column(mut self, column: Option<usize>) -> Self {
+        self.key_column = column;
+        self
+    }
+
+    /// Do not compare the fields at `columns`, e.g. timestamps or run times that change with
+    /// every run. The default is to compare all columns.
+    pub fn ignore_columns(mut self, columns: &[usize]) -> Self {
+        self.ignored_columns = columns.to_vec();
+        self
+    }
+
+    /// Get the columns at which `old` and `new` differ
+    fn changed_columns(&self, old: &Record, new: &Record) -> Vec<usize> {
+        (0..old.len().max(new.len()))
+            .filter(|column| !self.ignored_columns.contains(column))
+            .filter(|&column| old.iter().nth(column) != new.iter().nth(column))
+            .collect()
+    }
+
+    /// Compare a pair of records and push a difference if they are not equal
+    fn compare(&self, old: Record, new: Record, differences: &mut Vec<Difference>) {
+        let columns = self.changed_columns(&old, &new);
+        if !columns.is_empty() {
+            differences.push(Difference::Changed { old, new, columns });
+        }
+    }
+}
+
+/// A difference between two files found by [diff](fn.diff.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    /// The record only exists in the second file
+    Added(Record),
+    /// The record only exists in the first file
+    Removed(Record),
+    /// The record of the first file differs from its counterpart in the second file at
+    /// `columns`
+    Changed {
+        old: Record,
+        new: Record,
+        columns: Vec<usize>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Added(record) => write!(f, "added line {}", record.line_number()),
+            Difference::Removed(record) => write!(f, "removed line {}", record.line_number()),
+            Difference::Changed { old, new, columns } => {
+                let columns: Vec<String> = columns.iter().map(usize::to_string).collect();
+                write!(
+                    f,
+                    "changed line {} to line {} in columns {}",
+                    old.line_number(),
+                    new.line_number(),
+                    columns.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Find the records that were added, removed or changed between two files.
+///
+/// Without a [key column](struct.DiffOptions.html#method.key_column), the records are paired
+/// by their position like with [zip_longest](fn.zip_longest.html), so the differences keep
+/// the order of the files and the extra records of the longer file are added or removed. A
+/// record that is inserted in the middle changes all records after it, so files whose rows are
+/// not in a fixed order should be compared by a key. Then the removed and changed records
+/// come in the order of `a`, followed by the added records in the order of `b`, which is kept
+/// in memory.
+///
+/// The options of each `Tabfile`, like `skip_lines` or the separator, decide which fields are
+/// compared. Since fields are compared instead of lines, differences in line endings do not
+/// count, and neither do differences in quoting if a quote character is set.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut expected = NamedTempFile::new().unwrap();
+/// # expected.write_all(b"g1\t0.5\t12s\ng2\t0.7\t10s\ng3\t0.1\t11s\n");
+/// # let mut actual = NamedTempFile::new().unwrap();
+/// # actual.write_all(b"g3\t0.1\t9s\ng1\t0.6\t12s\ng4\t0.2\t8s\n");
+/// use tabfile::{diff, DiffOptions, Tabfile};
+///
+/// let options = DiffOptions::new().key_column(Some(0)).ignore_columns(&[2]);
+/// let expected = Tabfile::open(expected.path()).unwrap();
+/// let actual = Tabfile::open(actual.path()).unwrap();
+/// let differences: Vec<String> = diff(expected, actual, &options)
+///     .unwrap()
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+/// assert_eq!(
+///     differences,
+///     vec!["changed line 1 to line 2 in columns 1", "removed line 2", "added line 3"]
+/// );
+/// ```
+pub fn diff(a: Tabfile, b: Tabfile, options: &DiffOptions) -> Result<Vec<Difference>, Error> {
+    let mut differences = Vec::new();
+    let column = match options.key_column {
+        Some(column) => column,
+        None => {
+            let mut a = a.into_iter();
+            let mut b = b.into_iter();
+            loop {
+                match (a.next().transpose()?, b.next().transpose()?) {
+                    (None, None) => return Ok(differences),
+                    (Some(old), Some(new)) => options.compare(old, new, &mut differences),
+                    (Some(old), None) => differences.push(Difference::Removed(old)),
+                    (None, Some(new)) => differences.push(Difference::Added(new)),
+                }
+            }
+        }
+    };
+    let mut new_records = b.index_by(column, DuplicatePolicy::Error)?;
+    let mut old_keys = HashSet::new();
+    for old in a {
+        let old = old?;
+        let key = required_field(&old, column)?;
+        if !old_keys.insert(key.to_string()) {
+            return Err(code::invalid_data(
+                ErrorCode::DuplicateKey,
+                format!("line {}: the key `{}` occurs again", old.line_number(), key),
+            ));
+        }
+        match new_records.remove(key) {
+            Some(new) => options.compare(old, new, &mut differences),
+            None => differences.push(Difference::Removed(old)),
+        }
+    }
+    let mut added: Vec<Record> = new_records.into_values().collect();
+    added.sort_by_key(Record::line_number);
+    differences.extend(added.into_iter().map(Difference::Added));
+    Ok(differences)
+}
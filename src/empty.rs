@@ -0,0 +1,48 @@
+//! Detecting files without any data
+
+use crate::code::{self, ErrorCode};
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+/// What to do when a file has no records.
+///
+/// See [Tabfile::empty_policy](struct.Tabfile.html#method.empty_policy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmptyPolicy {
+    /// End the iteration without any record, which is the default
+    Allow,
+    /// Yield an error with an [EmptyInput](enum.EmptyInput.html) inside
+    Error,
+}
+
+/// The error for a file without any data.
+///
+/// This is the inner error of the `InvalidData` errors produced with
+/// [EmptyPolicy::Error](enum.EmptyPolicy.html#variant.Error) and can be retrieved with
+/// `error.get_ref().and_then(|e| e.downcast_ref::<EmptyInput>())`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmptyInput {
+    /// The file has no records at all, only comment, skipped or empty lines if any
+    EmptyFile,
+    /// The file has a header, but no records after it
+    HeaderOnly,
+}
+
+impl fmt::Display for EmptyInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            EmptyInput::EmptyFile => "the file has no records",
+            EmptyInput::HeaderOnly => "the file has a header but no records",
+        };
+        code::write_coded(f, ErrorCode::EmptyInput, message)
+    }
+}
+
+impl error::Error for EmptyInput {}
+
+impl From<EmptyInput> for Error {
+    fn from(empty: EmptyInput) -> Error {
+        Error::new(ErrorKind::InvalidData, empty)
+    }
+}
@@ -0,0 +1,161 @@
+//! Detecting and normalizing the line endings of records
+
+use crate::code::{self, ErrorCode};
+use crate::{Record, Tabfile};
+use std::fmt;
+use std::io::Error;
+
+/// The line break at the end of a record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `"\n"` as on Unix
+    Lf,
+    /// `"\r\n"` as on Windows
+    CrLf,
+    /// A lone `"\r"` as on classic Mac OS
+    Cr,
+    /// No line break, e.g. on the last line of a file without a final newline
+    None,
+}
+
+impl LineEnding {
+    /// Get the line ending of `line`
+    pub(crate) fn of(line: &str) -> LineEnding {
+        if line.ends_with("\r\n") {
+            LineEnding::CrLf
+        } else if line.ends_with('\n') {
+            LineEnding::Lf
+        } else if line.ends_with('\r') {
+            LineEnding::Cr
+        } else {
+            LineEnding::None
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+            LineEnding::Cr => write!(f, "CR"),
+            LineEnding::None => write!(f, "no line ending"),
+        }
+    }
+}
+
+/// What happens to the line endings of the records.
+///
+/// See [Tabfile::line_endings](struct.Tabfile.html#method.line_endings).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEndingPolicy {
+    /// Keep every line ending as it is, which is the default
+    Keep,
+    /// Replace `CRLF` and `CR` line endings with `LF`
+    Normalize,
+    /// Return an error of kind `InvalidData` for every record whose line ending differs from
+    /// the one of the first record
+    Error,
+}
+
+/// Applies a [LineEndingPolicy] to the records of an iteration
+pub(crate) struct LineEndings {
+    policy: LineEndingPolicy,
+    /// The first line ending and the line it was found on
+    first: Option<(LineEnding, usize)>,
+}
+
+impl LineEndings {
+    pub(crate) fn new(policy: LineEndingPolicy) -> LineEndings {
+        LineEndings {
+            policy,
+            first: None,
+        }
+    }
+
+    /// Forget the first line ending, e.g. after rewinding
+    pub(crate) fn reset(&mut self) {
+        self.first = None;
+    }
+
+    /// Normalize or check the line ending of the data line `line`
+    pub(crate) fn apply(&mut self, line: &mut String, line_number: usize) -> Result<(), Error> {
+        let ending = LineEnding::of(line);
+        match self.policy {
+            LineEndingPolicy::Keep => {}
+            LineEndingPolicy::Normalize => {
+                if let LineEnding::CrLf | LineEnding::Cr = ending {
+                    let content_length = line.trim_end_matches(['\r', '\n']).len();
+                    line.truncate(content_length);
+                    line.push('\n');
+                }
+            }
+            LineEndingPolicy::Error => match self.first {
+                // the last line of a file often lacks a line ending
+                _ if ending == LineEnding::None => {}
+                None => self.first = Some((ending, line_number)),
+                Some((first, first_line_number)) if first != ending => {
+                    return Err(code::invalid_data(
+                        ErrorCode::MixedLineEndings,
+                        format!(
+                            "line {}: {} line ending, but line {} ends with {}",
+                            line_number, ending, first_line_number, first
+                        ),
+                    ))
+                }
+                Some(_) => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+impl Record {
+    /// Get the line ending of the record.
+    ///
+    /// This is the line break that the record had in the file, unless it was changed by
+    /// [Tabfile::auto_fix](struct.Tabfile.html#method.auto_fix) or
+    /// [Tabfile::line_endings](struct.Tabfile.html#method.line_endings). Records that were
+    /// ended by another [record terminator](struct.Tabfile.html#method.record_terminator)
+    /// report [LineEnding::None] unless the terminator is `b'\r'`.
+    pub fn terminator(&self) -> LineEnding {
+        LineEnding::of(self.line())
+    }
+}
+
+impl Tabfile {
+    /// Choose what happens to the line endings of the records.
+    ///
+    /// Files that were edited on different operating systems often mix `LF` and `CRLF` line
+    /// endings, which breaks tools that expect one of them. With
+    /// [LineEndingPolicy::Normalize] every record ends with `LF` like with
+    /// [auto_fix](#method.auto_fix), but without recording the repairs. With
+    /// [LineEndingPolicy::Error] every record whose line ending differs from the one of the
+    /// first record is returned as an error, after which the iteration continues. A last record
+    /// without a line ending never counts as different. Comment and other skipped lines are not
+    /// checked. The default is [LineEndingPolicy::Keep].
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"a\t1\r\nb\t2\nc\t3");
+    /// use tabfile::{LineEnding, LineEndingPolicy, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .line_endings(LineEndingPolicy::Error)
+    ///     .into_iter();
+    /// assert_eq!(rows.next().unwrap().unwrap().terminator(), LineEnding::CrLf);
+    /// let error = rows.next().unwrap().unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "[TF016] line 2: LF line ending, but line 1 ends with CRLF"
+    /// );
+    /// assert_eq!(rows.next().unwrap().unwrap().terminator(), LineEnding::None);
+    /// ```
+    pub fn line_endings(mut self, policy: LineEndingPolicy) -> Self {
+        self.line_ending_policy = policy;
+        self
+    }
+}
@@ -0,0 +1,57 @@
+//! The error type of the typed row iterator
+
+use std::error;
+use std::fmt;
+use std::io::Error;
+
+/// The error for a row that could not be read or converted.
+///
+/// Returned by the iterator of [Tabfile::rows](struct.Tabfile.html#method.rows). It converts
+/// from and into `std::io::Error`, so `?` works in functions returning either type.
+#[derive(Debug)]
+pub enum TabfileError {
+    /// Reading the line from the file failed
+    Io(Error),
+    /// The record on `line_number` could not be converted into the row type
+    Convert { line_number: usize, error: Error },
+}
+
+impl TabfileError {
+    /// Get the line number of the record that could not be converted
+    pub fn line_number(&self) -> Option<usize> {
+        match self {
+            TabfileError::Io(_) => None,
+            TabfileError::Convert { line_number, .. } => Some(*line_number),
+        }
+    }
+}
+
+impl fmt::Display for TabfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TabfileError::Io(error) | TabfileError::Convert { error, .. } => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for TabfileError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TabfileError::Io(error) | TabfileError::Convert { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<Error> for TabfileError {
+    fn from(error: Error) -> TabfileError {
+        TabfileError::Io(error)
+    }
+}
+
+impl From<TabfileError> for Error {
+    fn from(error: TabfileError) -> Error {
+        match error {
+            TabfileError::Io(error) | TabfileError::Convert { error, .. } => error,
+        }
+    }
+}
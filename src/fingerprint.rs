@@ -0,0 +1,55 @@
+//! Hashing the logical content of a tab file
+
+use crate::sketch::StableHasher;
+use crate::Tabfile;
+use std::io::Error;
+
+/// Separates the fields of a record in the hashed data
+const FIELD_SEPARATOR: u8 = 0x1f;
+
+/// Terminates a record in the hashed data
+const RECORD_SEPARATOR: u8 = 0x1e;
+
+impl Tabfile {
+    /// Compute a 64-bit hash of the records of the file.
+    ///
+    /// Only the fields of the records are hashed, so comment, skipped and empty lines, line
+    /// endings and whitespace at the end of fields are ignored, as are empty fields at the end of
+    /// a record. Two files whose records are equal up to such cosmetic changes therefore have the
+    /// same fingerprint, while changing, adding or reordering records changes it. The hash is
+    /// stable across machines and versions of this crate, so fingerprints can be stored and
+    /// compared with the ones of later deliveries. It is not a cryptographic hash.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut old = NamedTempFile::new().unwrap();
+    /// # old.write_all(b"chr1\t1234\nchr2\t5678\n");
+    /// # let mut new = NamedTempFile::new().unwrap();
+    /// # new.write_all(b"# exported today\r\nchr1\t1234 \r\nchr2\t5678\t\r\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let old = Tabfile::open(old.path()).unwrap().fingerprint().unwrap();
+    /// let new = Tabfile::open(new.path()).unwrap().fingerprint().unwrap();
+    /// assert_eq!(old, new);
+    /// ```
+    pub fn fingerprint(self) -> Result<u64, Error> {
+        let mut hasher = StableHasher::new();
+        for record in self {
+            let record = record?;
+            let fields: Vec<&str> = record.iter().map(str::trim_end).collect();
+            let len = fields
+                .iter()
+                .rposition(|field| !field.is_empty())
+                .map_or(0, |i| i + 1);
+            for (i, field) in fields[..len].iter().enumerate() {
+                if i > 0 {
+                    hasher.write(&[FIELD_SEPARATOR]);
+                }
+                hasher.write(field.as_bytes());
+            }
+            hasher.write(&[RECORD_SEPARATOR]);
+        }
+        Ok(hasher.finish())
+    }
+}
@@ -0,0 +1,102 @@
+//! Repairing common formatting problems while reading
+
+use crate::parse::Separator;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+const NO_BREAK_SPACE: char = '\u{a0}';
+
+/// A kind of problem that is repaired by [Tabfile::auto_fix](struct.Tabfile.html#method.auto_fix).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FixKind {
+    /// A byte order mark at the start of the file was removed
+    ByteOrderMark,
+    /// A Windows line ending was replaced by `'\n'`
+    CarriageReturn,
+    /// Spaces in front of a separator were removed
+    SpaceBeforeSeparator,
+    /// Non-breaking spaces at the start or end of a field were removed
+    NonBreakingSpace,
+}
+
+/// A repair that was applied to one line of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fix {
+    line_number: usize,
+    kind: FixKind,
+}
+
+impl Fix {
+    /// Get the number of the line that was repaired
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the kind of the repair
+    pub fn kind(&self) -> FixKind {
+        self.kind
+    }
+}
+
+/// Remove a byte order mark from the first line of a file
+pub(crate) fn fix_byte_order_mark(line: &mut String, line_number: usize, fixes: &mut Vec<Fix>) {
+    if line.starts_with(BYTE_ORDER_MARK) {
+        line.drain(..BYTE_ORDER_MARK.len_utf8());
+        fixes.push(Fix {
+            line_number,
+            kind: FixKind::ByteOrderMark,
+        });
+    }
+}
+
+/// Repair the line ending and the padding of the fields of `line`
+///
+/// Every kind of repair is recorded at most once per line. The padding of fields is only
+/// repaired for single character separators.
+pub(crate) fn fix_line(
+    line: &mut String,
+    separator: &Separator,
+    line_number: usize,
+    fixes: &mut Vec<Fix>,
+) {
+    let mut fix = |kind| fixes.push(Fix { line_number, kind });
+    if line.ends_with("\r\n") {
+        line.truncate(line.len() - 2);
+        line.push('\n');
+        fix(FixKind::CarriageReturn);
+    }
+    let sep = match separator {
+        Separator::Char(sep) if *sep != ' ' && *sep != NO_BREAK_SPACE => *sep,
+        _ => return,
+    };
+    if !line.contains(NO_BREAK_SPACE) && !line.contains(&format!(" {}", sep)) {
+        return;
+    }
+    let content_length = line.trim_end_matches(['\r', '\n']).len();
+    let mut fixed = String::with_capacity(line.len());
+    let mut space_before_separator = false;
+    let mut no_break_space = false;
+    let mut fields = line[..content_length].split(sep).peekable();
+    while let Some(field) = fields.next() {
+        let is_last = fields.peek().is_none();
+        let trimmed = field.trim_start_matches(NO_BREAK_SPACE);
+        no_break_space |= trimmed.len() != field.len();
+        let kept = trimmed.trim_end_matches(|c| c == NO_BREAK_SPACE || (c == ' ' && !is_last));
+        let removed = &trimmed[kept.len()..];
+        no_break_space |= removed.contains(NO_BREAK_SPACE);
+        space_before_separator |= removed.contains(' ');
+        fixed.push_str(kept);
+        if !is_last {
+            fixed.push(sep);
+        }
+    }
+    if space_before_separator {
+        fix(FixKind::SpaceBeforeSeparator);
+    }
+    if no_break_space {
+        fix(FixKind::NonBreakingSpace);
+    }
+    if space_before_separator || no_break_space {
+        fixed.push_str(&line[content_length..]);
+        *line = fixed;
+    }
+}
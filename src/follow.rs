@@ -0,0 +1,135 @@
+//! Waiting for lines that are appended to a file
+
+use crate::code::{self, ErrorCode};
+use crate::Tabfile;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+
+/// The default of [Tabfile::poll_interval](struct.Tabfile.html#method.poll_interval)
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+impl Tabfile {
+    /// Wait for new lines at the end of the file instead of ending the iteration, like
+    /// `tail -f`.
+    ///
+    /// This suits logs and result files that another program appends records to while they are
+    /// read. At the end of the file, the iterator checks for new data every
+    /// [poll interval](#method.poll_interval) and only returns complete lines, so a record that
+    /// is written in several steps is returned once its line terminator arrives, even if a step
+    /// ends in the middle of a UTF-8 character. The iterator
+    /// never returns `None`, so stop the loop yourself, e.g. with [limit](#method.limit).
+    /// `skip_footer` has no effect when following, since a growing file has no last lines,
+    /// and a quoted field that is still open at the end of the file is an error. The default is
+    /// `false`.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"12:00\tstarted\n").unwrap();
+    /// use std::fs::OpenOptions;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut log = OpenOptions::new().append(true).open(file.path()).unwrap();
+    /// let writer = thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(50));
+    ///     log.write_all(b"12:01\tfin").unwrap();
+    ///     thread::sleep(Duration::from_millis(50));
+    ///     log.write_all(b"ished\n").unwrap();
+    /// });
+    /// let events: Vec<String> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .follow(true)
+    ///     .poll_interval(Duration::from_millis(10))
+    ///     .limit(2)
+    ///     .into_iter()
+    ///     .map(|record| record.unwrap()[1].to_string())
+    ///     .collect();
+    /// assert_eq!(events, vec!["started", "finished"]);
+    /// # writer.join().unwrap();
+    /// ```
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    /// Set how long to wait before checking the file for new data again when
+    /// [following](#method.follow) it. The default is
+    /// [DEFAULT_POLL_INTERVAL](constant.DEFAULT_POLL_INTERVAL.html).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Open the file again when it is rotated or truncated while [following](#method.follow)
+    /// it.
+    ///
+    /// Log rotation moves a file away and creates a new one at the same path, or truncates the
+    /// file in place. Without this option, the iterator keeps waiting for lines in the old
+    /// file. With it, the path is checked whenever the end of the file is reached: if it
+    /// refers to another file than the open one (on Unix) or the file is shorter than the
+    /// position of the iterator, the file is opened again and read from the start, including
+    /// `skip_lines` and `skip_lines_while`. The iterator returns an error with the code
+    /// [FileReopened](enum.ErrorCode.html#variant.FileReopened) as a marker before the
+    /// records of the new file, and the line numbers and byte offsets start over. Lines that
+    /// are written to the old file after its end has been reached are lost. The
+    /// [limit](#method.limit) counts the records of all files. The default is `false`.
+    ///
+    /// ```no_run
+    /// use tabfile::{ErrorCode, Tabfile};
+    ///
+    /// let log = Tabfile::open("/var/log/requests.tsv")
+    ///     .unwrap()
+    ///     .follow(true)
+    ///     .reopen_on_rotation(true);
+    /// for record in log {
+    ///     match record {
+    ///         Ok(record) => println!("{}", record.line()),
+    ///         Err(e) if ErrorCode::of(&e) == Some(ErrorCode::FileReopened) => {
+    ///             eprintln!("the log was rotated")
+    ///         }
+    ///         Err(e) => panic!("{}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn reopen_on_rotation(mut self, reopen: bool) -> Self {
+        self.reopen_on_rotation = reopen;
+        self
+    }
+
+    /// Check whether the path refers to another file than the open one or the file is shorter
+    /// than `offset`
+    pub(crate) fn is_rotated(&self, offset: u64) -> Result<bool, Error> {
+        let current = match fs::metadata(&self.path) {
+            Ok(current) => current,
+            // the file was moved away and the new one does not exist yet
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        if current.len() < offset {
+            return Ok(true);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let open = self.reader.get_ref().metadata()?;
+            if (open.dev(), open.ino()) != (current.dev(), current.ino()) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The marker that is returned after a rotated file has been opened again
+pub(crate) fn reopened(path: &Path) -> Error {
+    code::error(
+        ErrorKind::Other,
+        ErrorCode::FileReopened,
+        format!("{} was rotated and has been opened again", path.display()),
+    )
+}
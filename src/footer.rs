@@ -0,0 +1,60 @@
+//! Holding back the last lines of a file
+
+use crate::lines::{LineReader, Rejected};
+use std::collections::VecDeque;
+use std::io::{BufRead, Error};
+
+/// Reads lines ahead to recognize the last `size` lines of a file
+pub(crate) struct Lookahead {
+    size: usize,
+    pub(crate) reader: LineReader,
+    /// The lines read ahead with their length and why they were rejected
+    lines: VecDeque<(String, usize, Option<Rejected>)>,
+    at_end: bool,
+}
+
+impl Lookahead {
+    pub(crate) fn new(size: usize, reader: LineReader) -> Lookahead {
+        Lookahead {
+            size,
+            reader,
+            lines: VecDeque::with_capacity(size + 1),
+            at_end: false,
+        }
+    }
+
+    /// Forget the lines read ahead, e.g. after seeking
+    pub(crate) fn reset(&mut self) {
+        self.lines.clear();
+        self.at_end = false;
+    }
+
+    /// Read the next line into `line` like `BufRead::read_line`
+    ///
+    /// Returns the length of the line, whether it is one of the last `size` lines and why it
+    /// was rejected by the line reader, in which case `line` is left empty.
+    pub(crate) fn read_line<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        line: &mut String,
+    ) -> Result<(usize, bool, Option<Rejected>), Error> {
+        if self.size == 0 {
+            let (line_length, rejected) = self.reader.read_line(reader, line)?;
+            return Ok((line_length, false, rejected));
+        }
+        while !self.at_end && self.lines.len() <= self.size {
+            let mut next = String::new();
+            match self.reader.read_line(reader, &mut next)? {
+                (0, _) => self.at_end = true,
+                (line_length, rejected) => self.lines.push_back((next, line_length, rejected)),
+            }
+        }
+        match self.lines.pop_front() {
+            Some((next, line_length, rejected)) => {
+                line.push_str(&next);
+                Ok((line_length, self.at_end, rejected))
+            }
+            None => Ok((0, false, None)),
+        }
+    }
+}
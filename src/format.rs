@@ -0,0 +1,154 @@
+//! Rewriting tab files into a canonical form
+
+use crate::code::{self, ErrorCode};
+use crate::{Header, Record, Tabfile};
+use std::io::{Error, Write};
+
+/// How [format](fn.format.html) writes a file.
+///
+/// By default fields are separated by tabs, null fields are written as empty fields and the
+/// columns keep their order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatStyle {
+    separator: char,
+    null: String,
+    column_order: Option<Vec<String>>,
+}
+
+impl Default for FormatStyle {
+    fn default() -> FormatStyle {
+        FormatStyle {
+            separator: '\t',
+            null: String::new(),
+            column_order: None,
+        }
+    }
+}
+
+impl FormatStyle {
+    /// Create the default style
+    pub fn new() -> FormatStyle {
+        FormatStyle::default()
+    }
+
+    /// Set the separator between the fields of the output. The default is `'\t'`.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Write empty and [null](struct.Tabfile.html#method.null_tokens) fields as `token`
+    pub fn null(mut self, token: &str) -> Self {
+        self.null = token.to_string();
+        self
+    }
+
+    /// Order the columns like `columns`.
+    ///
+    /// The first record of the input is treated as its header. Columns of `columns` that the
+    /// input does not have are written as null fields and columns of the input that are not in
+    /// `columns` are written after them in their original order, so no data is lost.
+    pub fn column_order(mut self, columns: &[&str]) -> Self {
+        self.column_order = Some(columns.iter().map(|column| column.to_string()).collect());
+        self
+    }
+}
+
+/// A column of the output
+enum Column {
+    Input(usize),
+    Missing(String),
+}
+
+/// Find the input column for every output column
+fn output_columns(header: &Record, names: &[String]) -> Vec<Column> {
+    let header = Header::new(header.iter().map(str::trim));
+    let mut columns: Vec<Column> = names
+        .iter()
+        .map(|name| match header.position(name) {
+            Some(position) => Column::Input(position),
+            None => Column::Missing(name.clone()),
+        })
+        .collect();
+    for position in 0..header.len() {
+        if !names.contains(&header.names()[position]) {
+            columns.push(Column::Input(position));
+        }
+    }
+    columns
+}
+
+/// Rewrite the records of `input` into `output` in canonical form.
+///
+/// Every field is trimmed, empty and null fields are replaced by the null token of `style` and
+/// the fields are joined with its separator. Lines always end with `\n` and comment, skipped
+/// and empty lines of the input are dropped. The input is read with the options of the
+/// `Tabfile`, e.g. to split lines on any whitespace. Two files with the same content in
+/// different formatting therefore end up identical, which makes diffs between them
+/// meaningful.
+///
+/// An error of kind `InvalidData` is returned if a field contains the output separator.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"pos  chrom\r\n1234 chr1\r\nNA   chr2\r\n");
+/// use tabfile::{format, FormatStyle, Tabfile};
+///
+/// let input = Tabfile::open(file.path())
+///     .unwrap()
+///     .split_whitespace(true)
+///     .null_tokens(&["NA"]);
+/// let style = FormatStyle::new().null(".").column_order(&["chrom", "pos"]);
+/// let mut output = Vec::new();
+/// format(input, &mut output, &style).unwrap();
+/// assert_eq!(output, b"chrom\tpos\nchr1\t1234\nchr2\t.\n");
+/// ```
+pub fn format<W: Write>(input: Tabfile, mut output: W, style: &FormatStyle) -> Result<(), Error> {
+    let mut columns = None;
+    let mut line = String::new();
+    for record in input {
+        let record = record?;
+        // missing columns are named in the header and null in all other records
+        let is_header = columns.is_none() && style.column_order.is_some();
+        let (columns, width) = columns.get_or_insert_with(|| match &style.column_order {
+            Some(names) => (output_columns(&record, names), record.len()),
+            None => ((0..record.len()).map(Column::Input).collect(), record.len()),
+        });
+        // a row may have more fields than the first one
+        let extra: Vec<Column> = (*width..record.len()).map(Column::Input).collect();
+        line.clear();
+        for (i, column) in columns.iter().chain(&extra).enumerate() {
+            if i > 0 {
+                line.push(style.separator);
+            }
+            let field = match column {
+                Column::Input(position) => record
+                    .get_opt(*position)
+                    .map(str::trim)
+                    .filter(|field| !field.is_empty()),
+                Column::Missing(name) if is_header => Some(name.as_str()),
+                Column::Missing(_) => None,
+            };
+            match field {
+                Some(field) if field.contains(style.separator) => {
+                    return Err(code::invalid_data(
+                        ErrorCode::SeparatorInField,
+                        format!(
+                            "line {}: field `{}` contains the separator {:?}",
+                            record.line_number(),
+                            field,
+                            style.separator
+                        ),
+                    ))
+                }
+                Some(field) => line.push_str(field),
+                None => line.push_str(&style.null),
+            }
+        }
+        line.push('\n');
+        output.write_all(line.as_bytes())?;
+    }
+    output.flush()
+}
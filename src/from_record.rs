@@ -0,0 +1,224 @@
+//! Building structs from records
+
+use crate::{Header, HeaderedRecord, HeaderedTabfile, Record, Tabfile, TabfileError};
+use std::io::Error;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Types that can be built from a [Record](struct.Record.html).
+///
+/// With the `derive` feature this trait can be derived for structs with named fields. Every
+/// field is parsed with `FromStr` from the column with the same name if a header is given or
+/// from the field at the same position otherwise. Fields of type `Option<T>` are `None` if the
+/// column is missing or the field is empty or [null](struct.Tabfile.html#method.null_tokens).
+/// Use `#[tabfile(rename = "...")]` if the column has a different name than the field.
+///
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"QUAL\tchrom\tpos\n0.5\tchr1\t1234\n\tchr2\tx\n");
+/// use tabfile::{FromRecord, HeaderedTabfile};
+///
+/// #[derive(FromRecord)]
+/// struct Variant {
+///     chrom: String,
+///     pos: u64,
+///     #[tabfile(rename = "QUAL")]
+///     qual: Option<f32>,
+/// }
+///
+/// let mut records = HeaderedTabfile::open(file.path()).unwrap();
+/// let variant: Variant = records.next().unwrap().unwrap().parse().unwrap();
+/// assert_eq!((variant.chrom.as_str(), variant.pos, variant.qual), ("chr1", 1234, Some(0.5)));
+/// let error = records.next().unwrap().unwrap().parse::<Variant>().err().unwrap();
+/// assert_eq!(
+///     error.to_string(),
+///     "[TF004] line 3: field `pos`: cannot parse `x`: invalid digit found in string"
+/// );
+/// # }
+/// ```
+pub trait FromRecord: Sized {
+    /// Build a value from `record`, looking up columns in `header` if one is given
+    fn from_record(record: &Record, header: Option<&Header>) -> Result<Self, Error>;
+}
+
+impl HeaderedRecord {
+    /// Build a `T` from the record with its columns matched by name
+    pub fn parse<T: FromRecord>(&self) -> Result<T, Error> {
+        T::from_record(self, Some(self.header()))
+    }
+}
+
+/// Iterator over the records of a tab file converted into `T`.
+///
+/// See [Tabfile::rows](struct.Tabfile.html#method.rows) and
+/// [HeaderedTabfile::rows](struct.HeaderedTabfile.html#method.rows).
+pub struct Rows<T> {
+    records: Box<dyn Iterator<Item = Result<Record, Error>> + Send>,
+    header: Option<Arc<Header>>,
+    row_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Rows<T> {
+    /// Get the header that columns are looked up in, if there is one
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_deref()
+    }
+}
+
+impl<T: FromRecord> Iterator for Rows<T> {
+    type Item = Result<T, TabfileError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(TabfileError::Io(e))),
+        };
+        Some(
+            T::from_record(&record, self.header.as_deref()).map_err(|error| {
+                TabfileError::Convert {
+                    line_number: record.line_number(),
+                    error,
+                }
+            }),
+        )
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the records converted into `T`, matching fields by position.
+    ///
+    /// Use [HeaderedTabfile::rows](struct.HeaderedTabfile.html#method.rows) to match them by
+    /// column name instead. The iteration continues after a record that cannot be converted.
+    ///
+    /// ```
+    /// # #[cfg(feature = "derive")] {
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"chr1\t1234\nchr2\t5678\n");
+    /// use tabfile::{FromRecord, Tabfile};
+    ///
+    /// #[derive(FromRecord)]
+    /// struct Position {
+    ///     chrom: String,
+    ///     pos: u64,
+    /// }
+    ///
+    /// for position in Tabfile::open(file.path()).unwrap().rows::<Position>() {
+    ///     let position = position.unwrap();
+    ///     println!("{}:{}", position.chrom, position.pos);
+    /// }
+    /// # }
+    /// ```
+    pub fn rows<T: FromRecord>(self) -> Rows<T> {
+        Rows {
+            records: Box::new(self.into_iter()),
+            header: None,
+            row_type: PhantomData,
+        }
+    }
+}
+
+impl HeaderedTabfile {
+    /// Iterate over the records converted into `T`, matching fields by column name
+    pub fn rows<T: FromRecord>(self) -> Rows<T> {
+        let header = self.header.clone();
+        Rows {
+            records: Box::new(self.map(|record| record.map(HeaderedRecord::into_record))),
+            header: Some(header),
+            row_type: PhantomData,
+        }
+    }
+}
+
+/// Helpers for the code generated by `#[derive(FromRecord)]` and `#[derive(IntoRecord)]`
+#[doc(hidden)]
+pub mod __private {
+    use crate::code::{self, ErrorCode};
+    use crate::{Header, Record};
+    use std::fmt::Display;
+    use std::io::Error;
+    use std::str::FromStr;
+
+    fn field_error(record: &Record, field: &str, message: String) -> Error {
+        code::invalid_data(
+            ErrorCode::InvalidField,
+            format!(
+                "line {}: field `{}`: {}",
+                record.line_number(),
+                field,
+                message
+            ),
+        )
+    }
+
+    fn lookup<'a>(
+        record: &'a Record,
+        header: Option<&Header>,
+        position: usize,
+        column: &str,
+    ) -> Option<&'a str> {
+        let position = match header {
+            Some(header) => header.position(column)?,
+            None => position,
+        };
+        record.get_opt(position)
+    }
+
+    fn parse<T>(record: &Record, value: &str, field: &str) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        value
+            .parse()
+            .map_err(|e| field_error(record, field, format!("cannot parse `{}`: {}", value, e)))
+    }
+
+    pub fn parse_required<T>(
+        record: &Record,
+        header: Option<&Header>,
+        position: usize,
+        field: &str,
+        column: &str,
+    ) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match lookup(record, header, position, column) {
+            Some(value) => parse(record, value, field),
+            None if header.is_some_and(|header| header.position(column).is_none()) => Err(
+                field_error(record, field, format!("missing column `{}`", column)),
+            ),
+            None => Err(field_error(record, field, "missing value".to_string())),
+        }
+    }
+
+    pub fn parse_optional<T>(
+        record: &Record,
+        header: Option<&Header>,
+        position: usize,
+        field: &str,
+        column: &str,
+    ) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match lookup(record, header, position, column) {
+            None | Some("") => Ok(None),
+            Some(value) => parse(record, value, field).map(Some),
+        }
+    }
+
+    pub fn format_required<T: Display>(value: &T) -> String {
+        value.to_string()
+    }
+
+    pub fn format_optional<T: Display>(value: &Option<T>) -> String {
+        value.as_ref().map_or_else(String::new, ToString::to_string)
+    }
+}
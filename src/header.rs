@@ -0,0 +1,55 @@
+//! Column names from the first line of a file
+
+use crate::Record;
+use std::collections::HashMap;
+
+/// The names of the columns of a tab file.
+///
+/// A header maps column names to the positions of the columns, so columns can be looked up by
+/// name regardless of the order in which a file stores them. If a name occurs more than once,
+/// [position](#method.position) returns the first occurrence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    names: Vec<String>,
+    positions: HashMap<String, usize>,
+}
+
+impl Header {
+    /// Create a header from a list of column names
+    pub fn new<I, S>(names: I) -> Header
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        let mut positions = HashMap::with_capacity(names.len());
+        for (position, name) in names.iter().enumerate() {
+            positions.entry(name.clone()).or_insert(position);
+        }
+        Header { names, positions }
+    }
+
+    /// Use the fields of a record as column names
+    pub fn from_record(record: &Record) -> Header {
+        Header::new(record.iter())
+    }
+
+    /// Get the column names in the order of the file
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Get the position of the column called `name`
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.positions.get(name).copied()
+    }
+
+    /// Get the number of columns
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
@@ -0,0 +1,314 @@
+//! Reading files whose first line names the columns
+
+use crate::code::{self, ErrorCode};
+use crate::{EmptyInput, Header, Record, RowIterator, Tabfile};
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::ops::{Deref, Index};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The error for columns that a file is required to have but does not.
+///
+/// This is the inner error of the `InvalidData` error returned by
+/// [HeaderedTabfile::require_columns](struct.HeaderedTabfile.html#method.require_columns) and can
+/// be retrieved with `error.get_ref().and_then(|e| e.downcast_ref::<MissingColumns>())`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingColumns {
+    pub(crate) columns: Vec<String>,
+}
+
+impl MissingColumns {
+    /// Get the names of the missing columns
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+impl fmt::Display for MissingColumns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = format!("missing columns: {}", self.columns.join(", "));
+        code::write_coded(f, ErrorCode::MissingColumns, message)
+    }
+}
+
+impl error::Error for MissingColumns {}
+
+/// A tab file whose first record is a header with the names of the columns.
+///
+/// The header is read when the `HeaderedTabfile` is created, so it can be checked before any
+/// data is processed. Iterating yields [HeaderedRecord](struct.HeaderedRecord.html)s, which allow
+/// looking up fields by column name.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"chrom\tpos\nchr1\t1234\n");
+/// use tabfile::HeaderedTabfile;
+///
+/// let tsv_file = HeaderedTabfile::open(file.path())
+///     .unwrap()
+///     .require_columns(&["chrom", "pos"])
+///     .unwrap();
+/// for record in tsv_file {
+///     let record = record.unwrap();
+///     println!("{}:{}", &record["chrom"], &record["pos"]);
+/// }
+/// ```
+pub struct HeaderedTabfile {
+    rows: RowIterator,
+    pub(crate) header: Arc<Header>,
+    recodings: Vec<(usize, HashMap<String, String>)>,
+}
+
+impl HeaderedTabfile {
+    /// Open a file with the default options and read its header
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<HeaderedTabfile, Error> {
+        HeaderedTabfile::new(Tabfile::open(path)?)
+    }
+
+    /// Read the header of a configured `Tabfile`
+    ///
+    /// The header is the first record after skipped, comment and empty lines. A file without any
+    /// records results in an error of kind `InvalidData` with
+    /// [EmptyInput::EmptyFile](enum.EmptyInput.html#variant.EmptyFile) inside. With
+    /// [EmptyPolicy::Error](enum.EmptyPolicy.html#variant.Error) the iteration yields an error
+    /// with [EmptyInput::HeaderOnly](enum.EmptyInput.html#variant.HeaderOnly) inside if there
+    /// are no records after the header.
+    pub fn new(tabfile: Tabfile) -> Result<HeaderedTabfile, Error> {
+        let mut rows = tabfile.into_iter();
+        rows.has_header = true;
+        let header = match rows.next() {
+            Some(record) => Header::from_record(&record?),
+            None => return Err(EmptyInput::EmptyFile.into()),
+        };
+        Ok(HeaderedTabfile {
+            rows,
+            header: Arc::new(header),
+            recodings: Vec::new(),
+        })
+    }
+
+    /// Check that the header contains all of `columns`
+    ///
+    /// Otherwise an error of kind `InvalidData` with a
+    /// [MissingColumns](struct.MissingColumns.html) error inside is returned.
+    pub fn require_columns(self, columns: &[&str]) -> Result<Self, Error> {
+        let missing: Vec<String> = columns
+            .iter()
+            .filter(|column| self.header.position(column).is_none())
+            .map(|column| column.to_string())
+            .collect();
+        if missing.is_empty() {
+            Ok(self)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                MissingColumns { columns: missing },
+            ))
+        }
+    }
+
+    /// Translate the values of `column` while reading.
+    ///
+    /// Every field of the column that is equal to the first element of one of the `mapping`
+    /// pairs is replaced by the second element, so coded values like `0` and `1` can be turned
+    /// into readable ones in a single place. Other values are kept as they are. The replacement
+    /// is also visible in [Record::line](struct.Record.html#method.line). Calling this again for
+    /// the same column adds to the mapping. A column that is not in the header is ignored, use
+    /// [require_columns](#method.require_columns) to make sure that it exists.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"id\tstatus\n7\t1\n8\t2\n");
+    /// use tabfile::HeaderedTabfile;
+    ///
+    /// let statuses: Vec<String> = HeaderedTabfile::open(file.path())
+    ///     .unwrap()
+    ///     .recode_column("status", &[("0", "inactive"), ("1", "active")])
+    ///     .map(|record| record.unwrap()["status"].to_string())
+    ///     .collect();
+    /// assert_eq!(statuses, vec!["active", "2"]);
+    /// ```
+    pub fn recode_column(mut self, column: &str, mapping: &[(&str, &str)]) -> Self {
+        let position = match self.header.position(column) {
+            Some(position) => position,
+            None => return self,
+        };
+        let index = match self.recodings.iter().position(|(p, _)| *p == position) {
+            Some(index) => index,
+            None => {
+                self.recodings.push((position, HashMap::new()));
+                self.recodings.len() - 1
+            }
+        };
+        let values = &mut self.recodings[index].1;
+        for (from, to) in mapping {
+            values.insert(from.to_string(), to.to_string());
+        }
+        self
+    }
+
+    /// Only read the columns called `columns`, in that order.
+    ///
+    /// The records and the header then contain exactly these fields in the order of `columns`,
+    /// regardless of the order in the file, so `record[0]` is always the field of `columns[0]`.
+    /// Like with [Tabfile::select_columns](struct.Tabfile.html#method.select_columns), the other
+    /// fields are not split out at all. An error like the one of
+    /// [require_columns](#method.require_columns) is returned if the header does not contain all
+    /// of `columns`.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"tpm\tlength\tgene_id\n12.5\t2000\tENSG01\n");
+    /// use tabfile::HeaderedTabfile;
+    ///
+    /// let mut tsv_file = HeaderedTabfile::open(file.path())
+    ///     .unwrap()
+    ///     .select(&["gene_id", "tpm"])
+    ///     .unwrap();
+    /// assert_eq!(tsv_file.header().names(), &["gene_id", "tpm"]);
+    /// assert_eq!(tsv_file.next().unwrap().unwrap().fields(), vec!["ENSG01", "12.5"]);
+    /// ```
+    pub fn select(self, columns: &[&str]) -> Result<Self, Error> {
+        let mut selected = self.require_columns(columns)?;
+        let positions: Vec<usize> = columns
+            .iter()
+            .map(|column| selected.header.position(column).unwrap())
+            .collect();
+        let dialect = &mut selected.rows.tabfile.dialect;
+        // the header may already be a selection of the columns of the file
+        let projection: Vec<usize> = match &dialect.projection {
+            Some(previous) => positions.iter().map(|&p| previous[p]).collect(),
+            None => positions.clone(),
+        };
+        dialect.projection = Some(projection.into());
+        selected.recodings = selected
+            .recodings
+            .into_iter()
+            .filter_map(|(position, values)| {
+                let position = positions.iter().position(|&p| p == position)?;
+                Some((position, values))
+            })
+            .collect();
+        selected.header = Arc::new(Header::new(columns.iter().copied()));
+        Ok(selected)
+    }
+
+    /// Get the header of the file
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Get the comment lines that were read before the header.
+    ///
+    /// See [RowIterator::leading_comments](crate::RowIterator::leading_comments).
+    pub fn leading_comments(&self) -> &[String] {
+        self.rows.leading_comments()
+    }
+}
+
+impl Iterator for HeaderedTabfile {
+    type Item = Result<HeaderedRecord, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = match self.rows.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        for (position, values) in &self.recodings {
+            let value = record
+                .iter()
+                .nth(*position)
+                .and_then(|field| values.get(field));
+            if let Some(value) = value {
+                let value = value.clone();
+                record.replace_field(*position, &value);
+            }
+        }
+        Some(Ok(HeaderedRecord {
+            record,
+            header: self.header.clone(),
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+/// A record together with the header of its file.
+///
+/// Fields can be looked up by column name with [get](#method.get) or by indexing, e.g.
+/// `&record["chrom"]`. All methods of [Record](struct.Record.html) are available as well.
+#[derive(Clone, Debug)]
+pub struct HeaderedRecord {
+    record: Record,
+    header: Arc<Header>,
+}
+
+impl HeaderedRecord {
+    /// Get the field in the column called `name`
+    ///
+    /// Returns `None` if there is no such column or if the row is too short or the field is
+    /// null, see [Record::get_opt](struct.Record.html#method.get_opt).
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.record.get_opt(self.header.position(name)?)
+    }
+
+    /// Get the header of the file that the record comes from
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Get the record without the header
+    pub fn into_record(self) -> Record {
+        self.record
+    }
+
+    /// Deserialize the record into `T` with its columns matched by name.
+    ///
+    /// See [Record::deserialize](struct.Record.html#method.deserialize).
+    ///
+    /// This method is only available with the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, T: serde::Deserialize<'de>>(&'de self) -> Result<T, Error> {
+        self.record.deserialize(Some(&self.header))
+    }
+}
+
+impl Deref for HeaderedRecord {
+    type Target = Record;
+    fn deref(&self) -> &Record {
+        &self.record
+    }
+}
+
+impl Index<usize> for HeaderedRecord {
+    type Output = str;
+    fn index(&self, index: usize) -> &str {
+        &self.record[index]
+    }
+}
+
+impl Index<&str> for HeaderedRecord {
+    type Output = str;
+
+    /// Get the field in the column called `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such column or the row is too short to have it.
+    fn index(&self, name: &str) -> &str {
+        match self.header.position(name) {
+            Some(position) => &self.record[position],
+            None => panic!("no column named `{}`", name),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Exchanging configurations with Python and Frictionless Data tooling
+
+use crate::code::{self, ErrorCode};
+use crate::parse::Separator;
+use crate::{ColumnContract, ColumnType, Contract, Dialect, TrimMode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::io::Error;
+
+/// The value of `csv.QUOTE_MINIMAL` in Python
+const QUOTE_MINIMAL: u8 = 0;
+
+/// The value of `csv.QUOTE_NONE` in Python
+const QUOTE_NONE: u8 = 3;
+
+/// The keyword arguments of a Python `csv.Dialect`
+const PYTHON_CSV_PARAMETERS: &[&str] = &[
+    "delimiter",
+    "doublequote",
+    "escapechar",
+    "lineterminator",
+    "quotechar",
+    "quoting",
+    "skipinitialspace",
+    "strict",
+];
+
+fn unsupported<M: Into<String>>(message: M) -> Error {
+    code::invalid_data(ErrorCode::UnsupportedDialect, message)
+}
+
+impl Dialect {
+    /// Describe the dialect as the keyword arguments of a Python `csv` dialect.
+    ///
+    /// The result is a JSON object that can be passed to Python with
+    /// `csv.reader(f, **json.loads(params))`. Without a
+    /// [quote character](struct.Dialect.html#method.quote_character) the dialect uses
+    /// `csv.QUOTE_NONE`, otherwise `csv.QUOTE_MINIMAL` with doubled quotes. Python has no
+    /// comment lines, null tokens or trimming at the end of fields, so these options are not
+    /// part of the result, and trimming at the start becomes `skipinitialspace`. An error of kind `InvalidData` is returned if the separator is
+    /// not a single character, which Python does not support.
+    ///
+    /// This method is only available with the `contract` feature.
+    ///
+    /// ```
+    /// use tabfile::Dialect;
+    ///
+    /// let params = Dialect::new().separator(';').to_python_csv().unwrap();
+    /// assert!(params.contains(r#""delimiter":";""#));
+    /// let dialect = Dialect::from_python_csv(&params).unwrap();
+    /// assert_eq!(dialect.parse_line("a;b", 1).fields(), vec!["a", "b"]);
+    /// ```
+    pub fn to_python_csv(&self) -> Result<String, Error> {
+        let delimiter = match &self.separator {
+            Separator::Char(separator) => separator.to_string(),
+            _ => {
+                return Err(unsupported(
+                    "Python csv dialects only support a single character as delimiter",
+                ))
+            }
+        };
+        let skip_initial_space = matches!(self.trim_mode, TrimMode::Start | TrimMode::Both);
+        let quoting = match self.quote {
+            Some(_) => QUOTE_MINIMAL,
+            None => QUOTE_NONE,
+        };
+        let params = json!({
+            "delimiter": delimiter,
+            "doublequote": self.quote.is_some(),
+            "escapechar": null,
+            "lineterminator": "\n",
+            "quotechar": self.quote.map(String::from),
+            "quoting": quoting,
+            "skipinitialspace": skip_initial_space,
+            "strict": false,
+        });
+        Ok(params.to_string())
+    }
+
+    /// Create a dialect from the keyword arguments of a Python `csv` dialect.
+    ///
+    /// `json` is an object with the attributes of a dialect like `csv.excel`, e.g. from
+    /// `json.dumps({k: getattr(d, k) for k in ("delimiter", "quotechar", ...)})`. The delimiter
+    /// defaults to `","` like in Python. Unless `quoting` is `csv.QUOTE_NONE`, the `quotechar`
+    /// becomes the [quote character](struct.Dialect.html#method.quote_character) of the dialect.
+    /// Since Python has no comment lines, the dialect does not have any
+    /// comment prefix. An error of kind `InvalidData` is returned for unknown parameters or a
+    /// delimiter that is not a single character.
+    ///
+    /// This method is only available with the `contract` feature.
+    pub fn from_python_csv(json: &str) -> Result<Dialect, Error> {
+        let params: Map<String, Value> =
+            serde_json::from_str(json).map_err(|e| unsupported(e.to_string()))?;
+        if let Some(unknown) = params
+            .keys()
+            .find(|key| !PYTHON_CSV_PARAMETERS.contains(&key.as_str()))
+        {
+            return Err(unsupported(format!(
+                "unknown csv dialect parameter `{}`",
+                unknown
+            )));
+        }
+        let delimiter = match params.get("delimiter") {
+            None => ',',
+            Some(Value::String(delimiter)) if delimiter.chars().count() == 1 => {
+                delimiter.chars().next().unwrap()
+            }
+            Some(delimiter) => {
+                return Err(unsupported(format!(
+                    "the delimiter {} is not a single character",
+                    delimiter
+                )))
+            }
+        };
+        let skip_initial_space = match params.get("skipinitialspace") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(skip)) => *skip,
+            Some(skip) => return Err(unsupported(format!("invalid skipinitialspace {}", skip))),
+        };
+        let trim_mode = if skip_initial_space {
+            TrimMode::Start
+        } else {
+            TrimMode::None
+        };
+        let quote = match params.get("quotechar") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(quote)) if quote.chars().count() == 1 => quote.chars().next(),
+            Some(quote) => {
+                return Err(unsupported(format!(
+                    "the quotechar {} is not a single character",
+                    quote
+                )))
+            }
+        };
+        let dialect = Dialect::new()
+            .separator(delimiter)
+            .comment_prefixes(&[])
+            .trim_fields(trim_mode);
+        let quote_none = params.get("quoting") == Some(&Value::from(QUOTE_NONE));
+        Ok(match quote {
+            Some(quote) if !quote_none => dialect.quote_character(quote),
+            _ => dialect,
+        })
+    }
+}
+
+/// A Frictionless Data Table Schema
+#[derive(Deserialize)]
+struct TableSchema {
+    fields: Vec<TableField>,
+}
+
+/// A field descriptor of a Table Schema
+#[derive(Deserialize)]
+struct TableField {
+    name: String,
+    #[serde(rename = "type", default)]
+    field_type: Option<String>,
+    #[serde(default)]
+    constraints: Constraints,
+}
+
+/// The constraints of a field descriptor
+#[derive(Default, Deserialize)]
+struct Constraints {
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    unique: bool,
+    minimum: Option<Value>,
+    maximum: Option<Value>,
+    #[serde(rename = "enum")]
+    allowed: Option<Vec<Value>>,
+}
+
+/// Get a number from a constraint, which may also be a string like `"10"`
+fn number(value: &Option<Value>) -> Option<f64> {
+    match value.as_ref()? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Get the text of an `enum` value like it appears in a file
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl Contract {
+    /// Convert a Frictionless Data Table Schema into a contract.
+    ///
+    /// The types `string`, `integer`, `number` and `boolean` become the corresponding
+    /// [ColumnType](enum.ColumnType.html)s and all other types, e.g. `date`, are checked as
+    /// [ColumnType::Text](enum.ColumnType.html#variant.Text). The constraints `required`,
+    /// `unique`, `minimum`, `maximum` and `enum` are taken over, other constraints and keys like
+    /// `primaryKey` are ignored. The file is expected to have a header with the field names.
+    ///
+    /// This method is only available with the `contract` feature.
+    ///
+    /// ```
+    /// use tabfile::Contract;
+    ///
+    /// let schema = r#"{"fields": [
+    ///     {"name": "id", "type": "integer", "constraints": {"required": true}},
+    ///     {"name": "score", "type": "number", "constraints": {"minimum": 0, "maximum": 1}}
+    /// ]}"#;
+    /// let contract = Contract::from_table_schema(schema).unwrap();
+    /// let round_trip = Contract::from_table_schema(&contract.to_table_schema()).unwrap();
+    /// assert_eq!(round_trip.to_table_schema(), contract.to_table_schema());
+    /// ```
+    pub fn from_table_schema(json: &str) -> Result<Contract, Error> {
+        let schema: TableSchema = serde_json::from_str(json)
+            .map_err(|e| code::invalid_data(ErrorCode::InvalidContract, e.to_string()))?;
+        let columns = schema
+            .fields
+            .into_iter()
+            .map(|field| ColumnContract {
+                name: Some(field.name),
+                column_type: match field.field_type.as_deref() {
+                    Some("integer") => ColumnType::Integer,
+                    Some("number") => ColumnType::Float,
+                    Some("boolean") => ColumnType::Boolean,
+                    _ => ColumnType::Text,
+                },
+                required: field.constraints.required,
+                unique: field.constraints.unique,
+                min: number(&field.constraints.minimum),
+                max: number(&field.constraints.maximum),
+                allowed: field
+                    .constraints
+                    .allowed
+                    .map(|allowed| allowed.iter().map(text).collect()),
+            })
+            .collect();
+        Ok(Contract {
+            separator: None,
+            header: true,
+            columns,
+        })
+    }
+
+    /// Convert the contract into a Frictionless Data Table Schema.
+    ///
+    /// This is the inverse of [from_table_schema](#method.from_table_schema). A Table Schema
+    /// does not describe the separator or whether the file has a header, so these parts of the
+    /// contract are lost. Columns without a name are named after their 1-based position.
+    ///
+    /// This method is only available with the `contract` feature.
+    pub fn to_table_schema(&self) -> String {
+        let fields: Vec<Value> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let mut constraints = Map::new();
+                if column.required {
+                    constraints.insert("required".to_string(), Value::Bool(true));
+                }
+                if column.unique {
+                    constraints.insert("unique".to_string(), Value::Bool(true));
+                }
+                if let Some(min) = column.min {
+                    constraints.insert("minimum".to_string(), json!(min));
+                }
+                if let Some(max) = column.max {
+                    constraints.insert("maximum".to_string(), json!(max));
+                }
+                if let Some(allowed) = &column.allowed {
+                    constraints.insert("enum".to_string(), json!(allowed));
+                }
+                let field_type = match column.column_type {
+                    ColumnType::Text => "string",
+                    ColumnType::Integer => "integer",
+                    ColumnType::Float => "number",
+                    ColumnType::Boolean => "boolean",
+                };
+                let mut field = json!({
+                    "name": column.name.clone().unwrap_or_else(|| (i + 1).to_string()),
+                    "type": field_type,
+                });
+                if !constraints.is_empty() {
+                    field["constraints"] = Value::Object(constraints);
+                }
+                field
+            })
+            .collect();
+        json!({ "fields": fields }).to_string()
+    }
+}
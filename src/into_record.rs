@@ -0,0 +1,45 @@
+//! Turning structs into records
+
+use crate::Record;
+
+/// Types that can be written as a record, see
+/// [Tabwriter::write_row](struct.Tabwriter.html#method.write_row).
+///
+/// This is the counterpart of [FromRecord](trait.FromRecord.html). With the `derive` feature
+/// this trait can be derived for structs with named fields. The columns are in the order of
+/// the fields and are named after them, unless `#[tabfile(rename = "...")]` gives another
+/// name. Every field is formatted with `Display`, and fields of type `Option<T>` are empty if
+/// they are `None`.
+///
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// use tabfile::{IntoRecord, Tabwriter};
+///
+/// #[derive(IntoRecord)]
+/// struct Variant {
+///     chrom: String,
+///     pos: u64,
+///     #[tabfile(rename = "QUAL")]
+///     qual: Option<f32>,
+/// }
+///
+/// let mut writer = Tabwriter::new(Vec::new()).header(true);
+/// writer.write_row(&Variant { chrom: "chr1".to_string(), pos: 1234, qual: Some(0.5) }).unwrap();
+/// writer.write_row(&Variant { chrom: "chr2".to_string(), pos: 5678, qual: None }).unwrap();
+/// let output = writer.into_inner().unwrap();
+/// assert_eq!(output, b"chrom\tpos\tQUAL\nchr1\t1234\t0.5\nchr2\t5678\t\n");
+/// # }
+/// ```
+pub trait IntoRecord {
+    /// Get the names of the columns in the order of the fields, e.g. for a header
+    fn column_names() -> Vec<&'static str>;
+
+    /// Get the fields of the value in the order of the columns
+    fn fields(&self) -> Vec<String>;
+
+    /// Build a record with the fields joined by `separator`, see
+    /// [Record::from_fields](struct.Record.html#method.from_fields)
+    fn to_record(&self, separator: &str) -> Record {
+        Record::from_fields(self.fields(), separator)
+    }
+}
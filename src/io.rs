@@ -0,0 +1,661 @@
+//! Opening tab files and configuring how they are read
+
+use crate::iter::{IterItem, RowIterator};
+use crate::lines::LineReader;
+use crate::parse::{Dialect, EscapeStyle, RaggedPolicy, TrimMode, Whitespace};
+use crate::pool::RecordPool;
+use crate::progress::ProgressCallback;
+use crate::DEFAULT_POLL_INTERVAL;
+use crate::{EmptyPolicy, LineEndingPolicy};
+use std::fs::File;
+use std::io::{BufReader, Error, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A condition on a line, see [Tabfile::skip_lines_while]
+pub(crate) type LinePredicate = Box<dyn Fn(&str) -> bool + Send>;
+
+/// A read-only open handle for a tab-separated file.
+///
+/// To make use of this struct, put it into a for-loop:
+///
+/// ```
+/// extern crate tabfile;
+/// use tabfile::Tabfile;
+///
+/// # use std::fs::File;
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write(b"first_header\tsecond_header\nfirst_field\tsecond_field");
+/// // setup for `file` ellided
+///
+/// let tabfile = Tabfile::open(file.path()).unwrap();
+/// for line_result in tabfile {
+///     match line_result {
+///         Ok(line) => { // line is a tabfile::Record object
+///             let fields = line.fields();
+///             println!("{}", fields[0]); // print values in first column
+///             let line_string = line.line(); //get the original line
+///             println!("complete line={}", line_string);
+///         },
+///         Err(io_error) => eprintln!("{}", io_error) // std::io::Error
+///     }
+/// }
+/// ```
+///
+/// `Tabfile` supports the builder pattern. You can configure the `Tabfile` before using it in a
+/// for loop like so:
+///
+/// ```
+/// # extern crate tabfile;
+/// # use tabfile::Tabfile;
+/// #
+/// # use std::fs::File;
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write(b"first_header,second_header\nfirst_field,second_field");
+/// // setup for `file` ellided
+///
+/// let tabfile = Tabfile::open(file.path())
+///     .unwrap() // trust your hard disk and file system
+///     .separator(',') // if you have comma-separated values
+///     .comment_character('#') // if you want to ignore lines starting with #
+///     .skip_lines(2); // if you know that the first 2 lines are not relevant to you
+/// ```
+///
+/// Once you have gotten familiar with `Tabfile`, you probably want to check out
+/// [Record](crate::Record).
+///
+pub struct Tabfile {
+    pub(crate) path: PathBuf,
+    pub(crate) reader: BufReader<File>,
+    pub(crate) dialect: Dialect,
+    pub(crate) skip_lines: usize,
+    pub(crate) skip_footer: usize,
+    pub(crate) limit: Option<usize>,
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) skip_while: Option<LinePredicate>,
+    pub(crate) start_offset: u64,
+    /// Number of lines before `start_offset` that count towards the line numbers
+    pub(crate) start_line_number: usize,
+    pub(crate) record_pool: Option<Arc<RecordPool>>,
+    pub(crate) auto_fix: bool,
+    pub(crate) empty_policy: EmptyPolicy,
+    pub(crate) line_ending_policy: LineEndingPolicy,
+    pub(crate) metadata: Vec<String>,
+    pub(crate) progress_callback: Option<ProgressCallback>,
+    pub(crate) follow: bool,
+    pub(crate) poll_interval: Duration,
+    pub(crate) reopen_on_rotation: bool,
+}
+
+impl Tabfile {
+    /// Open an existing tab file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Tabfile, Error> {
+        let fd = File::open(path.as_ref())?;
+        Ok(Tabfile {
+            path: path.as_ref().to_path_buf(),
+            reader: BufReader::new(fd),
+            dialect: Dialect::default(),
+            skip_lines: 0,
+            skip_footer: 0,
+            limit: None,
+            max_line_length: None,
+            skip_while: None,
+            start_offset: 0,
+            start_line_number: 0,
+            record_pool: None,
+            auto_fix: false,
+            empty_policy: EmptyPolicy::Allow,
+            line_ending_policy: LineEndingPolicy::Keep,
+            metadata: Vec::new(),
+            progress_callback: None,
+            follow: false,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            reopen_on_rotation: false,
+        })
+    }
+
+    /// Replace all splitting and filtering options with the ones of `dialect`.
+    ///
+    /// Use this to share one configuration between several files or with
+    /// [Dialect::parse_line](crate::parse::Dialect::parse_line).
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Set the separator of the tab file reader.
+    ///
+    /// The default is `'\t'`
+    pub fn separator(mut self, sep: char) -> Self {
+        self.dialect = self.dialect.separator(sep);
+        self
+    }
+
+    /// Set a separator that consists of several characters, like `"||"`.
+    ///
+    /// This replaces any separator that was set with `separator`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    pub fn separator_str(mut self, sep: &str) -> Self {
+        self.dialect = self.dialect.separator_str(sep);
+        self
+    }
+
+    /// Split the fields on runs of spaces and tabs, like awk does by default.
+    ///
+    /// Leading and trailing spaces and tabs are ignored, so `"  1   2\t 3 "` has the three fields
+    /// `"1"`, `"2"` and `"3"` and a line that consists of whitespace only has no fields at all.
+    /// Use [whitespace](#method.whitespace) to split on Unicode whitespace as well.
+    /// Enabling this replaces any separator that was set before. Disabling it again restores the
+    /// default separator `'\t'`.
+    pub fn split_whitespace(mut self, split: bool) -> Self {
+        self.dialect = self.dialect.split_whitespace(split);
+        self
+    }
+
+    /// Split the fields on every match of a regular expression, like `\t+` or `\s*;\s*`.
+    ///
+    /// This replaces any separator that was set with `separator` or `separator_str`. The regular
+    /// expression should not match the empty string, otherwise every character becomes a field
+    /// of its own.
+    ///
+    /// This method is only available with the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn separator_regex(mut self, sep: regex::Regex) -> Self {
+        self.dialect = self.dialect.separator_regex(sep);
+        self
+    }
+
+    /// Allow fields to be quoted with `quote`, usually `'"'`.
+    ///
+    /// A field that starts with the quote character ends at the next single quote character and
+    /// may contain the separator and line breaks, so one record can span several lines. Two
+    /// quote characters in a row inside a quoted field stand for one. The fields of a record and
+    /// [Record::line](crate::Record::line) contain the text without the quotes, and the line
+    /// number and byte offset of the record are those of its first line. By default there is no
+    /// quote character. Quoting only applies to `separator` and `separator_str`.
+    ///
+    /// An error of kind `InvalidData` is returned if a quoted field is not closed before the
+    /// end of the file.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1,\"Smith, John\"\n2,\"first line\nsecond line\"\n3,\"5\"\" tall\"\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let records: Vec<_> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .separator(',')
+    ///     .quote_character('"')
+    ///     .into_iter()
+    ///     .map(|record| record.unwrap())
+    ///     .collect();
+    /// assert_eq!(records[0].fields(), vec!["1", "Smith, John"]);
+    /// assert_eq!(records[1].fields(), vec!["2", "first line\nsecond line"]);
+    /// assert_eq!(records[2].fields(), vec!["3", "5\" tall"]);
+    /// assert_eq!(records[2].line_number(), 4);
+    /// ```
+    pub fn quote_character(mut self, quote: char) -> Self {
+        self.dialect = self.dialect.quote_character(quote);
+        self
+    }
+
+    /// Decode escape sequences in the fields, like the ones written by PostgreSQL `COPY`.
+    ///
+    /// With [EscapeStyle::Backslash] a `\t` in a field becomes a tab, `\n` a
+    /// line break and `\\` a single backslash, so fields can contain the separator without
+    /// quoting. Any other character after a backslash stands for itself. A field that is
+    /// exactly `\N` is kept as it is, because it is the null token of PostgreSQL that can be
+    /// set with [null_tokens](#method.null_tokens). Lines without a backslash are not copied.
+    /// The default is `EscapeStyle::None`.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfirst\\tsecond\tC:\\\\temp\n2\t\\N\tline\\nbreak\n");
+    /// use tabfile::{EscapeStyle, Tabfile};
+    ///
+    /// let records: Vec<_> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .unescape(EscapeStyle::Backslash)
+    ///     .null_tokens(&["\\N"])
+    ///     .into_iter()
+    ///     .map(|record| record.unwrap())
+    ///     .collect();
+    /// assert_eq!(records[0].fields(), vec!["1", "first\tsecond", "C:\\temp"]);
+    /// assert_eq!(records[1].get_opt(1), None);
+    /// assert_eq!(&records[1][2], "line\nbreak");
+    /// ```
+    pub fn unescape(mut self, style: EscapeStyle) -> Self {
+        self.dialect = self.dialect.unescape(style);
+        self
+    }
+
+    /// Strip the `="..."` wrapper that Excel puts around values to keep them as text.
+    ///
+    /// Excel exports values like IDs or ZIP codes with leading zeros as the formula `="0123"`.
+    /// With this option such a field becomes `0123` without copying the line, and all other
+    /// fields are kept as they are. The wrapper is removed before the fields are trimmed. The
+    /// default is `false`.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"=\"00123\"\tAlice\t=1+1\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut records = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .unwrap_excel_text(true)
+    ///     .into_iter();
+    /// let record = records.next().unwrap().unwrap();
+    /// assert_eq!(record.fields(), vec!["00123", "Alice", "=1+1"]);
+    /// ```
+    pub fn unwrap_excel_text(mut self, unwrap: bool) -> Self {
+        self.dialect = self.dialect.unwrap_excel_text(unwrap);
+        self
+    }
+
+    /// Set the number of lines that should be skipped when reading the tab file.
+    ///
+    /// The default is `0`.
+    pub fn skip_lines(mut self, num_lines: usize) -> Self {
+        self.skip_lines = num_lines;
+        self
+    }
+
+    /// Skip lines at the beginning of the file as long as `predicate` returns `true` for them.
+    ///
+    /// This skips a preamble of varying length, e.g. everything before the header with
+    /// `.skip_lines_while(|line| !line.starts_with("ID\t"))`. The predicate gets the lines
+    /// without their line terminator and is called for the lines after the ones removed by
+    /// `skip_lines`, including comment and empty lines, until it returns `false` for the first
+    /// time. Skipped lines are still counted for the line numbers.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"Export from LIMS\nDate: today\nID\tvalue\n1\t2\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .skip_lines_while(|line| !line.starts_with("ID\t"))
+    ///     .into_iter();
+    /// let header = rows.next().unwrap().unwrap();
+    /// assert_eq!((header.fields()[0], header.line_number()), ("ID", 3));
+    /// ```
+    pub fn skip_lines_while<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + 'static,
+    {
+        self.skip_while = Some(Box::new(predicate));
+        self
+    }
+
+    /// Create a reader for the lines of this file
+    pub(crate) fn line_reader(&self) -> LineReader {
+        LineReader::new(self.dialect.terminator, self.max_line_length)
+    }
+
+    /// Check whether `line` belongs to the preamble removed by `skip_lines_while`
+    pub(crate) fn in_preamble(&self, line: &str) -> bool {
+        self.skip_while
+            .as_ref()
+            .is_some_and(|predicate| predicate(self.dialect.content(line)))
+    }
+
+    /// Set the number of lines at the end of the file that should be ignored.
+    ///
+    /// This drops summary or footer rows that spreadsheet programs and report generators append
+    /// to the data. The lines are counted like with `skip_lines`, i.e. comment and empty lines
+    /// count as well. To recognize the last lines, `num_lines` lines are read ahead. The default
+    /// is `0`.
+    pub fn skip_footer(mut self, num_lines: usize) -> Self {
+        self.skip_footer = num_lines;
+        self
+    }
+
+    /// Stop after `records` records, e.g. to preview a file or to test a pipeline on its first
+    /// rows.
+    ///
+    /// Unlike `take` on the iterator, this only counts records, so skipped, comment and empty
+    /// lines do not use up the limit, and it is part of the configuration of the `Tabfile`. The
+    /// header of a [HeaderedTabfile](crate::HeaderedTabfile) does not count either. No lines are
+    /// read after the last record. The default is no limit.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# sample data\n1\tfoo\n\n2\tbar\n3\tbaz\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let records: Vec<_> = Tabfile::open(file.path()).unwrap().limit(2).into_iter().collect();
+    /// assert_eq!(records.len(), 2);
+    /// assert_eq!(&records[1].as_ref().unwrap()[1], "bar");
+    /// ```
+    pub fn limit(mut self, records: usize) -> Self {
+        self.limit = Some(records);
+        self
+    }
+
+    /// Refuse to read lines that are longer than `bytes` bytes including the line terminator.
+    ///
+    /// Lines are normally read into memory completely, so a binary file or a file without line
+    /// breaks is buffered as a whole. With a maximum length, the rest of a longer line is
+    /// discarded without buffering it and the line is returned as an error of kind `InvalidData`
+    /// that names the line, after which the iteration continues with the next line. The default
+    /// is no limit.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\tbaz\tquux\n3\tbaz\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().max_line_length(8).into_iter();
+    /// assert!(rows.next().unwrap().is_ok());
+    /// let error = rows.next().unwrap().unwrap_err();
+    /// assert_eq!(error.to_string(), "[TF007] line 2: longer than 8 bytes");
+    /// assert_eq!(rows.next().unwrap().unwrap().fields(), vec!["3", "baz"]);
+    /// ```
+    pub fn max_line_length(mut self, bytes: usize) -> Self {
+        self.max_line_length = Some(bytes);
+        self
+    }
+
+    /// Silently repair common formatting problems and record every repair.
+    ///
+    /// The following problems are fixed before a line is split into fields:
+    ///
+    /// * a byte order mark at the start of the file
+    /// * Windows line endings, which are replaced by `'\n'`
+    /// * spaces in front of a separator
+    /// * non-breaking spaces (U+00A0) at the start or end of a field
+    ///
+    /// Spaces and non-breaking spaces are only removed if the separator is a single character
+    /// other than a space. [Record::line](crate::Record::line) returns the repaired line while
+    /// [Record::byte_offset](crate::Record::byte_offset) still refers to the original file. The
+    /// repairs are recorded per line and can be retrieved with
+    /// [RowIterator::fixes](crate::RowIterator::fixes) to report data quality issues. The default
+    /// is `false`.
+    pub fn auto_fix(mut self, auto_fix: bool) -> Self {
+        self.auto_fix = auto_fix;
+        self
+    }
+
+    /// Choose what happens if the file has no records.
+    ///
+    /// By default an empty file simply yields no records, which cannot be told apart from a
+    /// file whose upstream job produced no output by mistake. With
+    /// [EmptyPolicy::Error] the iteration yields an error of kind
+    /// `InvalidData` with [EmptyInput::EmptyFile](crate::EmptyInput::EmptyFile) inside instead.
+    /// A [HeaderedTabfile](crate::HeaderedTabfile) also reports a file with a header but no
+    /// records as [EmptyInput::HeaderOnly](crate::EmptyInput::HeaderOnly).
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# no data today\n");
+    /// use tabfile::{EmptyInput, EmptyPolicy, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .empty_policy(EmptyPolicy::Error)
+    ///     .into_iter();
+    /// let error = rows.next().unwrap().unwrap_err();
+    /// let empty = error.get_ref().and_then(|e| e.downcast_ref::<EmptyInput>());
+    /// assert_eq!(empty, Some(&EmptyInput::EmptyFile));
+    /// assert!(rows.next().is_none());
+    /// ```
+    pub fn empty_policy(mut self, policy: EmptyPolicy) -> Self {
+        self.empty_policy = policy;
+        self
+    }
+
+    /// Set the byte that ends a record instead of `b'\n'`.
+    ///
+    /// This reads records that are separated by another character, e.g. the output of
+    /// `find -print0` with `b'\0'` or a single line of records separated by `b';'`. The
+    /// terminator is not part of the last field and a `'\n'` or `"\r\n"` after the last record
+    /// is ignored. Line numbers then count records and `skip_lines` skips records. A `'\n'`
+    /// or `'\r'` still ends the fields of a record, so everything after it up to the terminator
+    /// is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `terminator` is not an ASCII character.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"./a.txt\0./my notes.txt\0");
+    /// use tabfile::Tabfile;
+    ///
+    /// let paths: Vec<String> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .record_terminator(b'\0')
+    ///     .into_iter()
+    ///     .map(|record| record.unwrap()[0].to_string())
+    ///     .collect();
+    /// assert_eq!(paths, vec!["./a.txt", "./my notes.txt"]);
+    /// ```
+    pub fn record_terminator(mut self, terminator: u8) -> Self {
+        self.dialect = self.dialect.record_terminator(terminator);
+        self
+    }
+
+    /// Set a comment character for lines that should be ignored.
+    ///
+    /// All lines starting with the comment character will be ignored.
+    /// By default there is no comment character. If you use this method in combination with
+    /// `skip_lines` note that `skip_lines` is always checked first and the first `n` lines will be
+    /// dropped regardless of whether they have a comment character at the beginning or not.
+    pub fn comment_character(mut self, comment_character: char) -> Self {
+        self.dialect = self.dialect.comment_character(comment_character);
+        self
+    }
+
+    /// Set a comment prefix of one or more characters for lines that should be ignored.
+    ///
+    /// This is like `comment_character`, but for markers like `//` or `--`, and replaces the
+    /// comment character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is empty.
+    pub fn comment_prefix(mut self, prefix: &str) -> Self {
+        self.dialect = self.dialect.comment_prefix(prefix);
+        self
+    }
+
+    /// Ignore lines starting with any of `prefixes`.
+    ///
+    /// This is useful for files from different sources that use different comment markers,
+    /// e.g. `.comment_prefixes(&["#", ";"])`. The prefixes replace the comment character and
+    /// an empty list disables comments altogether.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of `prefixes` is empty.
+    pub fn comment_prefixes(mut self, prefixes: &[&str]) -> Self {
+        self.dialect = self.dialect.comment_prefixes(prefixes);
+        self
+    }
+
+    /// Skip empty lines.
+    ///
+    /// If set to `true` (which is the default) then the iterator will only yield non-empty
+    /// vectors. If you combine this with the `skip` method, then the first `n` lines will always
+    /// be skipped regardless of whether or not they are empty.
+    pub fn skip_empty_lines(mut self, skip: bool) -> Self {
+        self.dialect = self.dialect.skip_empty_lines(skip);
+        self
+    }
+
+    /// Remove whitespace from the start and/or end of every field.
+    ///
+    /// With `TrimMode::Both` a field like `" value "` is returned as `"value"` by
+    /// [Record::fields](crate::Record::fields). Only the positions of the fields are
+    /// adjusted, the original line stays unchanged. By default only ASCII whitespace like spaces
+    /// and tabs is removed, see [whitespace](#method.whitespace). The default is `TrimMode::None`.
+    pub fn trim_fields(mut self, mode: TrimMode) -> Self {
+        self.dialect = self.dialect.trim_fields(mode);
+        self
+    }
+
+    /// Choose which characters are removed by `trim_fields` and split on by `split_whitespace`.
+    ///
+    /// Data that was copied from spreadsheets is often padded with non-breaking spaces (U+00A0),
+    /// which are not ASCII whitespace and therefore survive trimming and break the parsing of
+    /// numbers. With `Whitespace::Unicode` every character with the Unicode `White_Space`
+    /// property is treated as whitespace, which includes non-breaking spaces. Non-breaking
+    /// spaces inside a field are kept when trimming, but they do separate fields when splitting
+    /// on whitespace. The default is `Whitespace::Ascii`.
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.dialect = self.dialect.whitespace(whitespace);
+        self
+    }
+
+    /// Recycle the buffers of dropped records.
+    ///
+    /// Normally every record allocates a new `String` for its line and a new `Vec` for the
+    /// positions of its fields. With a record pool, the buffers of dropped records are kept (up to
+    /// `capacity` of them) and reused for the following records. This helps if you keep
+    /// ownership of records for a short while, e.g. by sending them to another thread, and your
+    /// program spends a noticeable amount of time in the allocator. The pool is shared between
+    /// threads, so records can be dropped anywhere.
+    pub fn record_pool(mut self, capacity: usize) -> Self {
+        self.record_pool = Some(Arc::new(RecordPool::new(capacity)));
+        self
+    }
+
+    /// Treat fields that are equal to one of `tokens` as null values.
+    ///
+    /// Files exported from R or bioinformatics tools often use sentinel values like `NA` or `.`
+    /// for missing data. With this option [Record::get_opt](crate::Record::get_opt)
+    /// returns `None` for these fields, e.g. after `.null_tokens(&["NA", ".", ""])`. The tokens are
+    /// compared after trimming with `trim_fields`. By default no field is null.
+    pub fn null_tokens(mut self, tokens: &[&str]) -> Self {
+        self.dialect = self.dialect.null_tokens(tokens);
+        self
+    }
+
+    /// Choose what happens to rows that do not have exactly `width` fields.
+    ///
+    /// With [RaggedPolicy::Pad] short rows are filled up with empty
+    /// fields like with `pad_short_rows`, with
+    /// [RaggedPolicy::Truncate] the extra fields of long rows are
+    /// dropped and with
+    /// [RaggedPolicy::Error] every row with a different width is
+    /// returned as an error of kind `InvalidData`, after which the iteration continues. The
+    /// width is checked after comment and empty lines are removed, so a header counts as a row.
+    /// `Record::line` still returns the complete line. The default is
+    /// [RaggedPolicy::Keep].
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"a\tb\tc\nd\te\n");
+    /// use tabfile::{RaggedPolicy, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .on_ragged(3, RaggedPolicy::Error)
+    ///     .into_iter();
+    /// assert!(rows.next().unwrap().is_ok());
+    /// let error = rows.next().unwrap().unwrap_err();
+    /// assert_eq!(error.to_string(), "[TF002] line 2: expected 3 fields but found 2");
+    /// ```
+    pub fn on_ragged(mut self, width: usize, policy: RaggedPolicy) -> Self {
+        self.dialect = self.dialect.on_ragged(width, policy);
+        self
+    }
+
+    /// Declare that every row has exactly `num_columns` fields.
+    ///
+    /// Every record with a different number of fields is returned as an error of kind
+    /// `InvalidData` that names the line and the actual count, so corrupt or truncated files are
+    /// noticed as soon as they are read. This is a shorthand for
+    /// `.on_ragged(num_columns, RaggedPolicy::Error)`.
+    pub fn columns(self, num_columns: usize) -> Self {
+        self.on_ragged(num_columns, RaggedPolicy::Error)
+    }
+
+    /// Only split out the columns at the given positions.
+    ///
+    /// Records then contain just the selected fields in the order of `columns`, so
+    /// [Record::fields](crate::Record::fields) returns one field per selected column and
+    /// `get_opt(0)` is the field of `columns[0]`. The line is only split as far as the last
+    /// selected column and the other fields are never trimmed or stored, which makes reading a
+    /// few columns of a very wide file much faster. Columns that a row does not have are left
+    /// out, so short rows produce shorter records. Options that work on the width of a record
+    /// like `pad_short_rows` and `on_ragged` count the selected fields. `Record::line` still
+    /// returns the complete line.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"gene\ts1\ts2\ts3\ts4\nBRCA2\t0.1\t0.2\t0.3\t0.4\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().select_columns(&[0, 3]).into_iter();
+    /// assert_eq!(rows.next().unwrap().unwrap().fields(), vec!["gene", "s3"]);
+    /// assert_eq!(rows.next().unwrap().unwrap().fields(), vec!["BRCA2", "0.3"]);
+    /// ```
+    pub fn select_columns(mut self, columns: &[usize]) -> Self {
+        self.dialect = self.dialect.select_columns(columns);
+        self
+    }
+
+    /// Pad rows with fewer than `width` fields.
+    ///
+    /// Short rows are filled up with empty fields until they have `width` fields, which means
+    /// that [Record::get_opt](crate::Record::get_opt) returns `Some("")` for the padded
+    /// fields. By default short rows are not padded and `get_opt` returns `None` for fields that
+    /// are missing from a row.
+    pub fn pad_short_rows(mut self, width: usize) -> Self {
+        self.dialect = self.dialect.pad_short_rows(width);
+        self
+    }
+
+    /// Continue reading at the given byte offset.
+    ///
+    /// The offset should point to the beginning of a line, for example one obtained from
+    /// [Record::byte_offset](crate::Record::byte_offset) before a long-running job was
+    /// interrupted. Since the lines before the offset are not read, line numbers are counted
+    /// relative to the offset, i.e. the line at `offset` has the line number one. Options like
+    /// `skip_lines` apply to the lines after the offset.
+    pub fn seek_to(mut self, offset: u64) -> Result<Self, Error> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.start_offset = offset;
+        self.start_line_number = 0;
+        Ok(self)
+    }
+}
+
+impl IntoIterator for Tabfile {
+    type Item = IterItem;
+    type IntoIter = RowIterator;
+    fn into_iter(self) -> Self::IntoIter {
+        RowIterator::new(self)
+    }
+}
@@ -0,0 +1,541 @@
+//! Iterating over the records of a tab file
+
+use crate::code::{self, ErrorCode};
+use crate::ending::LineEndings;
+use crate::fix::{fix_byte_order_mark, fix_line, Fix};
+use crate::follow::reopened;
+use crate::footer::Lookahead;
+use crate::lines::Rejected;
+use crate::{EmptyInput, EmptyPolicy, Record, Tabfile};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, Error, Seek, SeekFrom};
+use std::thread;
+
+pub(crate) type IterItem = Result<Record, Error>;
+
+/// Iterator over the lines of a tab file.
+///
+/// ```
+/// extern crate tabfile;
+/// use tabfile::Tabfile;
+///
+/// # use std::fs::File;
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write(b"first_header\tsecond_header\nfirst_field\tsecond_field");
+/// // setup for `file` ellided
+///
+/// let tsv_file = Tabfile::open(file.path()).unwrap();
+/// let iter = tsv_file.into_iter();
+/// // or alternatively
+/// # let tsv_file = Tabfile::open(file.path()).unwrap();
+/// for record_result in tsv_file { // this creates a RowIterator
+///     // do stuff
+/// }
+/// ```
+pub struct RowIterator {
+    pub(crate) tabfile: Tabfile,
+    lines_to_skip: usize,
+    in_preamble: bool,
+    footer: Lookahead,
+    pub(crate) next_line_number: usize,
+    /// Lines of the last record after its first one, which belong to a quoted field
+    continuation_lines: usize,
+    next_byte_offset: u64,
+    fixes: Vec<Fix>,
+    leading_comments: Vec<String>,
+    seen_data: bool,
+    data_lines: usize,
+    /// Whether the first data line is a header, which does not count as a record
+    pub(crate) has_header: bool,
+    reported_empty: bool,
+    line_endings: LineEndings,
+    /// The size of the file when the iteration began
+    pub(crate) file_size: u64,
+}
+
+/// Get the number of lines to hold back at the end of the file
+fn footer_size(tabfile: &Tabfile) -> usize {
+    if tabfile.follow {
+        0
+    } else {
+        tabfile.skip_footer
+    }
+}
+
+impl RowIterator {
+    pub(crate) fn new(tabfile: Tabfile) -> RowIterator {
+        RowIterator {
+            lines_to_skip: tabfile.skip_lines,
+            in_preamble: tabfile.skip_while.is_some(),
+            footer: Lookahead::new(footer_size(&tabfile), tabfile.line_reader()),
+            next_line_number: tabfile.start_line_number,
+            continuation_lines: 0,
+            next_byte_offset: tabfile.start_offset,
+            fixes: Vec::new(),
+            leading_comments: tabfile.metadata.clone(),
+            seen_data: false,
+            data_lines: 0,
+            has_header: false,
+            reported_empty: false,
+            line_endings: LineEndings::new(tabfile.line_ending_policy),
+            file_size: tabfile
+                .reader
+                .get_ref()
+                .metadata()
+                .map_or(0, |metadata| metadata.len()),
+            tabfile,
+        }
+    }
+
+    /// Start over from the position where the iteration began, e.g. for a second pass.
+    ///
+    /// All settings of the `Tabfile` still apply, so an algorithm that first infers a schema or
+    /// counts the records and then processes them does not need to open and configure the file
+    /// again. Lines are skipped again and the records are counted from zero, while the
+    /// [leading comments](#method.leading_comments) are kept and the [fixes](#method.fixes)
+    /// are collected anew. The file is not opened again, but its size is looked up again for
+    /// the [progress](#method.progress).
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n3\tbaz\n");
+    /// use tabfile::{Record, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().into_iter();
+    /// let value = |record: Result<Record, _>| record.unwrap()[0].parse::<f64>().unwrap();
+    /// let total: f64 = rows.by_ref().map(value).sum();
+    /// rows.rewind().unwrap();
+    /// let shares: Vec<f64> = rows.map(|record| value(record) / total).collect();
+    /// assert_eq!(shares, [1.0 / 6.0, 2.0 / 6.0, 3.0 / 6.0]);
+    /// ```
+    pub fn rewind(&mut self) -> Result<(), Error> {
+        self.restart()?;
+        self.fixes.clear();
+        self.file_size = self.tabfile.reader.get_ref().metadata()?.len();
+        Ok(())
+    }
+
+    /// Stop iterating and get the `Tabfile` back, positioned where the iteration began.
+    ///
+    /// All settings are kept, so the `Tabfile` can be iterated again or used for another
+    /// pass with a method that takes it by value.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"generated by a tool\n1\tfoo\n2\tbar\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().skip_lines(1).into_iter();
+    /// let first = rows.next().unwrap().unwrap();
+    /// let tsv_file = rows.into_tabfile().unwrap();
+    /// assert_eq!(tsv_file.last_records(2).unwrap()[0].line(), first.line());
+    /// ```
+    pub fn into_tabfile(mut self) -> Result<Tabfile, Error> {
+        self.tabfile
+            .reader
+            .seek(SeekFrom::Start(self.tabfile.start_offset))?;
+        Ok(self.tabfile)
+    }
+
+    /// Reset the state to the position where the iteration began
+    fn restart(&mut self) -> Result<(), Error> {
+        self.tabfile
+            .reader
+            .seek(SeekFrom::Start(self.tabfile.start_offset))?;
+        self.lines_to_skip = self.tabfile.skip_lines;
+        self.in_preamble = self.tabfile.skip_while.is_some();
+        self.footer.reset();
+        self.next_line_number = self.tabfile.start_line_number;
+        self.continuation_lines = 0;
+        self.next_byte_offset = self.tabfile.start_offset;
+        self.data_lines = 0;
+        self.reported_empty = false;
+        self.line_endings.reset();
+        Ok(())
+    }
+
+    /// Open the file at the path again and start over, but keep counting the records
+    fn reopen(&mut self) -> Result<(), Error> {
+        let file = File::open(&self.tabfile.path)?;
+        self.file_size = file.metadata()?.len();
+        self.tabfile.reader = BufReader::new(file);
+        self.tabfile.start_offset = 0;
+        self.tabfile.start_line_number = 0;
+        let records_read = self.data_lines;
+        self.restart()?;
+        self.data_lines = records_read;
+        Ok(())
+    }
+
+    /// Continue with the data line at `byte_offset`, which has the 1-based `line_number`
+    ///
+    /// `records_before` is the number of records before the line. Skipping lines and checking
+    /// for empty input do not apply afterwards.
+    pub(crate) fn seek_data_line(
+        &mut self,
+        byte_offset: u64,
+        line_number: usize,
+        records_before: usize,
+    ) -> Result<(), Error> {
+        self.tabfile.reader.seek(SeekFrom::Start(byte_offset))?;
+        self.lines_to_skip = 0;
+        self.in_preamble = false;
+        self.footer.reset();
+        self.next_line_number = line_number - 1;
+        self.continuation_lines = 0;
+        self.next_byte_offset = byte_offset;
+        self.seen_data = true;
+        self.data_lines = records_before;
+        self.reported_empty = true;
+        Ok(())
+    }
+
+    /// Get the repairs that were applied to the lines read so far.
+    ///
+    /// This is always empty unless [Tabfile::auto_fix](crate::Tabfile::auto_fix) is enabled.
+    pub fn fixes(&self) -> &[Fix] {
+        &self.fixes
+    }
+
+    /// Get the comment lines that were read before the first record.
+    ///
+    /// Files often start with comments that carry metadata like a format version or the
+    /// program that created the file. The comments are collected until the first record has
+    /// been read and keep their comment character, but not their line terminator. Lines removed
+    /// by [Tabfile::skip_lines](crate::Tabfile::skip_lines) are not included.
+    pub fn leading_comments(&self) -> &[String] {
+        &self.leading_comments
+    }
+
+    /// Get the byte offset of the next line that will be read.
+    ///
+    /// After a record was returned this is the position right behind it, so it can be stored as
+    /// a continuation token and passed to [Tabfile::seek_to](crate::Tabfile::seek_to) to resume
+    /// with the following record.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n3\tbaz\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().into_iter();
+    /// let page: Vec<_> = rows.by_ref().take(2).collect();
+    /// let token = rows.byte_offset();
+    /// assert_eq!((rows.line_number(), rows.records_read()), (2, 2));
+    ///
+    /// // later, e.g. in the next request for this file
+    /// let mut rows = Tabfile::open(file.path()).unwrap().seek_to(token).unwrap().into_iter();
+    /// assert_eq!(rows.next().unwrap().unwrap().fields(), vec!["3", "baz"]);
+    /// ```
+    pub fn byte_offset(&self) -> u64 {
+        self.next_byte_offset
+    }
+
+    /// Get the fraction of the file that has been read, from `0.0` to `1.0`.
+    ///
+    /// This is the [byte offset](#method.byte_offset) divided by the size of the file when the
+    /// iterator was created, so it grows evenly with the bytes of the records, unlike the
+    /// number of records. An empty file counts as read completely.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().into_iter();
+    /// assert_eq!(rows.progress(), 0.0);
+    /// rows.next();
+    /// assert_eq!(rows.progress(), 0.5);
+    /// ```
+    pub fn progress(&self) -> f64 {
+        if self.file_size == 0 {
+            return 1.0;
+        }
+        (self.next_byte_offset as f64 / self.file_size as f64).min(1.0)
+    }
+
+    /// Get the line number of the line that was read last, or zero before the first line
+    ///
+    /// This counts all lines including skipped, comment and empty ones, just like
+    /// [Record::line_number](crate::Record::line_number).
+    pub fn line_number(&self) -> usize {
+        self.next_line_number + self.continuation_lines
+    }
+
+    /// Get the number of records read so far
+    ///
+    /// Iterators built on top of this one, like the one of
+    /// [Tabfile::into_parallel_iter](crate::Tabfile::into_parallel_iter), may read ahead, so the
+    /// count can be higher than the number of records they returned.
+    pub fn records_read(&self) -> usize {
+        self.data_lines
+    }
+
+    /// Estimate the number of remaining records from the bytes left in the file, e.g. for a
+    /// progress bar.
+    ///
+    /// The estimate is the remaining bytes divided by the average number of bytes per record
+    /// so far, so it is zero before the first record and only a guess afterwards. It is never
+    /// more than the upper bound of `size_hint`.
+    pub fn estimated_remaining(&self) -> usize {
+        let upper = match self.size_hint() {
+            (_, Some(upper)) => upper,
+            (_, None) => return 0,
+        };
+        let remaining = self.file_size.saturating_sub(self.next_byte_offset);
+        // a resumed iteration can start in front of the start offset
+        let read = self
+            .next_byte_offset
+            .saturating_sub(self.tabfile.start_offset);
+        if self.data_lines == 0 || read == 0 {
+            return 0;
+        }
+        let estimate = remaining as u128 * self.data_lines as u128 / read as u128;
+        usize::try_from(estimate).unwrap_or(usize::MAX).min(upper)
+    }
+
+    /// Read the next record into an existing `Record` instead of allocating a new one.
+    ///
+    /// The buffers of `record` are cleared and reused, so a loop over a huge file only allocates
+    /// when a line is longer or has more fields than all lines before. Returns `false` once the
+    /// end of the file is reached, in which case `record` is left without any fields.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n");
+    /// use tabfile::{Record, Tabfile};
+    ///
+    /// let mut rows = Tabfile::open(file.path()).unwrap().into_iter();
+    /// let mut record = Record::default();
+    /// while rows.read_record_into(&mut record).unwrap() {
+    ///     println!("{}", record.fields()[1]);
+    /// }
+    /// ```
+    pub fn read_record_into(&mut self, record: &mut Record) -> Result<bool, Error> {
+        let mut line = record.take_line();
+        match self.next_data_line(&mut line)? {
+            Some(byte_offset) => {
+                self.tabfile
+                    .dialect
+                    .refill(record, line, self.next_line_number, byte_offset);
+                self.tabfile.dialect.check_width(record)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Call the progress callback if another `every` records have been read
+    fn report_progress(&mut self) {
+        let progress = self.progress();
+        if let Some(reporter) = &mut self.tabfile.progress_callback {
+            if self.data_lines.is_multiple_of(reporter.every) {
+                (reporter.callback)(self.data_lines, progress);
+            }
+        }
+    }
+
+    /// Check whether the end of the file should be reported as an error
+    fn empty_input(&mut self) -> Option<EmptyInput> {
+        if self.reported_empty || self.tabfile.empty_policy != EmptyPolicy::Error {
+            return None;
+        }
+        let empty = match (self.data_lines, self.has_header) {
+            (0, _) => EmptyInput::EmptyFile,
+            (1, true) => EmptyInput::HeaderOnly,
+            _ => return None,
+        };
+        self.reported_empty = true;
+        Some(empty)
+    }
+
+    /// Read the next line that is neither skipped nor filtered out into `line`
+    ///
+    /// Returns the byte offset of the line or `None` at the end of the file.
+    pub(crate) fn next_data_line(&mut self, line: &mut String) -> Result<Option<u64>, Error> {
+        Ok(self
+            .next_line(line, false)?
+            .map(|(byte_offset, _)| byte_offset))
+    }
+
+    /// Append the following lines to `line` as long as it ends inside a quoted field
+    fn read_continuation(&mut self, line: &mut String) -> Result<(), Error> {
+        while self.tabfile.dialect.has_open_quote(line) {
+            let length = line.len();
+            let (line_length, in_footer, rejected) =
+                self.footer.read_line(&mut self.tabfile.reader, line)?;
+            if line_length == 0 || in_footer {
+                line.truncate(length);
+                return Err(code::invalid_data(
+                    ErrorCode::UnclosedQuote,
+                    format!("line {}: unclosed quote", self.next_line_number),
+                ));
+            }
+            self.next_byte_offset += line_length as u64;
+            self.continuation_lines += 1;
+            if let Some(rejected) = rejected {
+                let line_number = self.next_line_number + self.continuation_lines;
+                return Err(self.footer.reader.rejected(rejected, line_number));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `next_data_line`, but also return comment lines if `comments` is `true`
+    ///
+    /// Returns the byte offset of the line and whether it is a comment.
+    pub(crate) fn next_line(
+        &mut self,
+        line: &mut String,
+        comments: bool,
+    ) -> Result<Option<(u64, bool)>, Error> {
+        self.next_line_number += std::mem::take(&mut self.continuation_lines);
+        let limit = self
+            .tabfile
+            .limit
+            .map(|limit| limit + self.has_header as usize);
+        if limit.is_some_and(|limit| self.data_lines >= limit) {
+            return Ok(None);
+        }
+        loop {
+            line.clear();
+            let (mut line_length, in_footer, rejected) =
+                self.footer.read_line(&mut self.tabfile.reader, line)?;
+            let unterminated = match rejected {
+                None => !line.ends_with(self.tabfile.dialect.terminator as char),
+                Some(rejected) => rejected == Rejected::UnterminatedInvalidUtf8,
+            };
+            if self.tabfile.follow && unterminated {
+                // wait until the line is complete, which may also complete a UTF-8 character
+                self.tabfile
+                    .reader
+                    .seek(SeekFrom::Start(self.next_byte_offset))?;
+                if self.tabfile.reopen_on_rotation
+                    && self.tabfile.is_rotated(self.next_byte_offset)?
+                {
+                    self.reopen()?;
+                    return Err(reopened(&self.tabfile.path));
+                }
+                thread::sleep(self.tabfile.poll_interval);
+                continue;
+            }
+            if in_footer {
+                // the rest of the file is ignored
+                line.clear();
+                line_length = 0;
+            }
+            let byte_offset = self.next_byte_offset;
+            self.next_byte_offset += line_length as u64;
+            if self.lines_to_skip > 0 {
+                self.lines_to_skip -= 1;
+                self.next_line_number += 1;
+                continue;
+            }
+            if let Some(rejected) = rejected.filter(|_| !in_footer) {
+                self.next_line_number += 1;
+                return Err(self.footer.reader.rejected(rejected, self.next_line_number));
+            }
+            if self.in_preamble && line_length > 0 {
+                if self.tabfile.in_preamble(line) {
+                    self.next_line_number += 1;
+                    continue;
+                }
+                self.in_preamble = false;
+            }
+            if line_length == 0 {
+                return match self.empty_input() {
+                    Some(empty) => Err(empty.into()),
+                    None => Ok(None),
+                };
+            }
+            self.next_line_number += 1; //line numbers are 1-based
+            if self.tabfile.auto_fix && byte_offset == 0 {
+                fix_byte_order_mark(line, self.next_line_number, &mut self.fixes);
+            }
+            if self.tabfile.dialect.is_data_line(line) {
+                let continued = self.read_continuation(line);
+                let checked = self.line_endings.apply(line, self.next_line_number);
+                if self.tabfile.auto_fix {
+                    fix_line(
+                        line,
+                        &self.tabfile.dialect.separator,
+                        self.next_line_number,
+                        &mut self.fixes,
+                    );
+                }
+                self.seen_data = true;
+                self.data_lines += 1;
+                self.report_progress();
+                continued?;
+                checked?;
+                return Ok(Some((byte_offset, false)));
+            }
+            if self.tabfile.dialect.is_comment(line) {
+                if !self.seen_data {
+                    let content = self.tabfile.dialect.content(line);
+                    self.leading_comments.push(content.to_string());
+                }
+                if comments {
+                    return Ok(Some((byte_offset, true)));
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for RowIterator {
+    type Item = IterItem;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (mut line, ranges) = match &self.tabfile.record_pool {
+            Some(pool) => pool.take(),
+            None => (String::new(), Vec::new()),
+        };
+        match self.next_data_line(&mut line) {
+            Ok(Some(byte_offset)) => {
+                let dialect = &self.tabfile.dialect;
+                let record = dialect.record(
+                    line,
+                    ranges,
+                    self.next_line_number,
+                    byte_offset,
+                    self.tabfile.record_pool.clone(),
+                );
+                Some(dialect.check_width(&record).map(|_| record))
+            }
+            Ok(None) => None, // iterator exhausted
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Get the bounds of the number of remaining records.
+    ///
+    /// The lower bound is zero, since the rest of the file may be comments or blank lines.
+    /// Every record has at least one byte, so the remaining bytes are the upper bound, unless
+    /// the file is followed. See [estimated_remaining](#method.estimated_remaining) for a
+    /// better guess.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.tabfile.follow {
+            return (0, None);
+        }
+        let remaining = self.file_size.saturating_sub(self.next_byte_offset);
+        let mut upper = usize::try_from(remaining).unwrap_or(usize::MAX);
+        if let Some(limit) = self.tabfile.limit {
+            let records = limit + self.has_header as usize;
+            upper = upper.min(records.saturating_sub(self.data_lines));
+        }
+        (0, Some(upper))
+    }
+}
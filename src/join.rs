@@ -0,0 +1,270 @@
+//! Joining the records of two tab files on a key column
+
+use crate::code::{self, ErrorCode};
+use crate::collect::required_field;
+use crate::{Record, RowIterator, Tabfile};
+use std::collections::{HashMap, VecDeque};
+use std::io::Error;
+
+/// Which records of the left file are kept by a join.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Keep only the left records with at least one matching right record
+    Inner,
+    /// Keep every left record, those without a match are paired with `None`
+    Left,
+}
+
+/// Pair `left` with every record of `matches`, or with `None` for a left join without matches
+fn pair_up(
+    pending: &mut VecDeque<(Record, Option<Record>)>,
+    left: Record,
+    matches: &[Record],
+    kind: JoinKind,
+) {
+    match matches.split_last() {
+        Some((last, others)) => {
+            for right in others {
+                pending.push_back((left.clone(), Some(right.clone())));
+            }
+            pending.push_back((left, Some(last.clone())));
+        }
+        None if kind == JoinKind::Left => pending.push_back((left, None)),
+        None => {}
+    }
+}
+
+/// Iterator over the joined records of two files. See [hash_join](fn.hash_join.html).
+pub struct HashJoin {
+    left: RowIterator,
+    left_column: usize,
+    right: HashMap<String, Vec<Record>>,
+    kind: JoinKind,
+    pending: VecDeque<(Record, Option<Record>)>,
+}
+
+impl Iterator for HashJoin {
+    type Item = Result<(Record, Option<Record>), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.pending.pop_front() {
+                return Some(Ok(pair));
+            }
+            let left = match self.left.next()? {
+                Ok(left) => left,
+                Err(e) => return Some(Err(e)),
+            };
+            let matches = match required_field(&left, self.left_column) {
+                Ok(key) => self.right.get(key).map_or(&[][..], Vec::as_slice),
+                Err(e) => return Some(Err(e)),
+            };
+            pair_up(&mut self.pending, left, matches, self.kind);
+        }
+    }
+}
+
+/// Join two files on a key column by loading the right file into memory.
+///
+/// The records of `right` are grouped by their field at `right_column`. Then `left` is read
+/// record by record and every left record is paired with the right records whose key equals
+/// its field at `left_column`, in the order of the right file. The pairs keep the order of the
+/// left file. With [JoinKind::Left](enum.JoinKind.html#variant.Left), left records without a
+/// match are paired with `None`. This suits a small right file, e.g. sample metadata that is
+/// merged into a large results table. Use [merge_join](fn.merge_join.html) if both files are
+/// sorted by their keys or the right file does not fit into memory.
+///
+/// Every record must have a field at its key column, otherwise an error of kind `InvalidData`
+/// is returned, for the right file already by this function.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut results = NamedTempFile::new().unwrap();
+/// # results.write_all(b"s1\t0.5\ns2\t0.7\ns3\t0.1\n");
+/// # let mut samples = NamedTempFile::new().unwrap();
+/// # samples.write_all(b"s2\tliver\ns1\tbrain\n");
+/// use tabfile::{hash_join, JoinKind, Tabfile};
+///
+/// let results = Tabfile::open(results.path()).unwrap();
+/// let samples = Tabfile::open(samples.path()).unwrap();
+/// let joined: Vec<_> = hash_join(results, 0, samples, 0, JoinKind::Left)
+///     .unwrap()
+///     .map(|pair| pair.unwrap())
+///     .collect();
+/// assert_eq!(joined.len(), 3);
+/// let (result, sample) = &joined[1];
+/// assert_eq!((&result[1], &sample.as_ref().unwrap()[1]), ("0.7", "liver"));
+/// assert!(joined[2].1.is_none());
+/// ```
+pub fn hash_join(
+    left: Tabfile,
+    left_column: usize,
+    right: Tabfile,
+    right_column: usize,
+    kind: JoinKind,
+) -> Result<HashJoin, Error> {
+    Ok(HashJoin {
+        left: left.into_iter(),
+        left_column,
+        right: right.group_by_key(right_column)?,
+        kind,
+        pending: VecDeque::new(),
+    })
+}
+
+/// Iterator over the joined records of two sorted files. See [merge_join](fn.merge_join.html).
+pub struct MergeJoin {
+    left: RowIterator,
+    left_column: usize,
+    right: RowIterator,
+    right_column: usize,
+    kind: JoinKind,
+    /// The key of the last left record
+    left_key: Option<String>,
+    /// The key of the last right record that was read
+    right_key: Option<String>,
+    /// The right records whose key is `right_key`, if that is also the key of the last left
+    /// record
+    group: Vec<Record>,
+    /// The first right record with a key greater than the last left key
+    lookahead: Option<Record>,
+    pending: VecDeque<(Record, Option<Record>)>,
+}
+
+/// Check that `key` is not smaller than the `previous` key of the same file
+fn check_order(
+    previous: &mut Option<String>,
+    key: &str,
+    record: &Record,
+    side: &str,
+) -> Result<(), Error> {
+    match previous {
+        Some(previous) if key < previous.as_str() => Err(code::invalid_data(
+            ErrorCode::UnsortedInput,
+            format!(
+                "line {} of the {} file: the key `{}` comes after `{}`",
+                record.line_number(),
+                side,
+                key,
+                previous
+            ),
+        )),
+        Some(previous) if key == previous.as_str() => Ok(()),
+        _ => {
+            *previous = Some(key.to_string());
+            Ok(())
+        }
+    }
+}
+
+impl MergeJoin {
+    /// Read the next right record and check the order of its key
+    fn next_right(&mut self) -> Result<Option<Record>, Error> {
+        let right = match self.lookahead.take() {
+            Some(right) => return Ok(Some(right)),
+            None => match self.right.next() {
+                Some(right) => right?,
+                None => return Ok(None),
+            },
+        };
+        let key = required_field(&right, self.right_column)?;
+        check_order(&mut self.right_key, key, &right, "right")?;
+        Ok(Some(right))
+    }
+
+    /// Collect the right records with the key `key` into the group
+    fn advance_right(&mut self, key: &str) -> Result<(), Error> {
+        self.group.clear();
+        while let Some(right) = self.next_right()? {
+            let right_key = required_field(&right, self.right_column)?;
+            if right_key > key {
+                self.lookahead = Some(right);
+                break;
+            } else if right_key == key {
+                self.group.push(right);
+            }
+        }
+        Ok(())
+    }
+
+    /// Join the next left record
+    fn join_next(&mut self) -> Result<Option<()>, Error> {
+        let left = match self.left.next() {
+            Some(left) => left?,
+            None => return Ok(None),
+        };
+        let key = required_field(&left, self.left_column)?;
+        let same_key = self.left_key.as_deref() == Some(key);
+        check_order(&mut self.left_key, key, &left, "left")?;
+        if !same_key {
+            let key = key.to_string();
+            self.advance_right(&key)?;
+        }
+        pair_up(&mut self.pending, left, &self.group, self.kind);
+        Ok(Some(()))
+    }
+}
+
+impl Iterator for MergeJoin {
+    type Item = Result<(Record, Option<Record>), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pair) = self.pending.pop_front() {
+                return Some(Ok(pair));
+            }
+            match self.join_next() {
+                Ok(Some(())) => {}
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Join two files that are sorted by their key columns while reading them side by side.
+///
+/// The result is the same as with [hash_join](fn.hash_join.html), but only the right records
+/// of the current key are kept in memory. Both files must be sorted by their key column in
+/// byte order, like `LC_ALL=C sort -t$'\t' -k1,1` does for the first column. If a key is
+/// smaller than the key before it, an error of kind `InvalidData` is returned.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut genes = NamedTempFile::new().unwrap();
+/// # genes.write_all(b"BRCA1\tchr17\nEGFR\tchr7\nTP53\tchr17\n");
+/// # let mut variants = NamedTempFile::new().unwrap();
+/// # variants.write_all(b"BRCA1\trs1\nBRCA1\trs2\nTP53\trs3\n");
+/// use tabfile::{merge_join, JoinKind, Tabfile};
+///
+/// let genes = Tabfile::open(genes.path()).unwrap();
+/// let variants = Tabfile::open(variants.path()).unwrap();
+/// let pairs: Vec<(String, String)> = merge_join(genes, 0, variants, 0, JoinKind::Inner)
+///     .map(|pair| {
+///         let (gene, variant) = pair.unwrap();
+///         (gene[0].to_string(), variant.unwrap()[1].to_string())
+///     })
+///     .collect();
+/// assert_eq!(pairs.len(), 3);
+/// assert_eq!(&pairs[2], &("TP53".to_string(), "rs3".to_string()));
+/// ```
+pub fn merge_join(
+    left: Tabfile,
+    left_column: usize,
+    right: Tabfile,
+    right_column: usize,
+    kind: JoinKind,
+) -> MergeJoin {
+    MergeJoin {
+        left: left.into_iter(),
+        left_column,
+        right: right.into_iter(),
+        right_column,
+        kind,
+        left_key: None,
+        right_key: None,
+        group: Vec::new(),
+        lookahead: None,
+        pending: VecDeque::new(),
+    }
+}
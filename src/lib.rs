@@ -6,281 +6,145 @@
 //!
 //! See [Tabfile's](struct.Tabfile.html) documentation to see how to use this crate.
 
-use std::fs::File;
-use std::io::{BufRead, BufReader, Error};
-use std::ops::Range;
-use std::path::Path;
-
-type IterItem = Result<Record, Error>;
-
-/// A read-only open handle for a tab-separated file.
-///
-/// To make use of this struct, put it into a for-loop:
-///
-/// ```
-/// extern crate tabfile;
-/// use tabfile::Tabfile;
-///
-/// # use std::fs::File;
-/// # use std::io::Write;
-/// # use tempfile::NamedTempFile;
-/// # let mut file = NamedTempFile::new().unwrap();
-/// # file.write(b"first_header\tsecond_header\nfirst_field\tsecond_field");
-/// // setup for `file` ellided
-///
-/// let tabfile = Tabfile::open(file.path()).unwrap();
-/// for line_result in tabfile {
-///     match line_result {
-///         Ok(line) => { // line is a tabfile::Record object
-///             let fields = line.fields();
-///             println!("{}", fields[0]); // print values in first column
-///             let line_string = line.line(); //get the original line
-///             println!("complete line={}", line_string);
-///         },
-///         Err(io_error) => eprintln!("{}", io_error) // std::io::Error
-///     }
-/// }
-/// ```
-///
-/// `Tabfile` supports the builder pattern. You can configure the `Tabfile` before using it in a
-/// for loop like so:
-///
-/// ```
-/// # extern crate tabfile;
-/// # use tabfile::Tabfile;
-/// #
-/// # use std::fs::File;
-/// # use std::io::Write;
-/// # use tempfile::NamedTempFile;
-/// # let mut file = NamedTempFile::new().unwrap();
-/// # file.write(b"first_header,second_header\nfirst_field,second_field");
-/// // setup for `file` ellided
-///
-/// let tabfile = Tabfile::open(file.path())
-///     .unwrap() // trust your hard disk and file system
-///     .separator(',') // if you have comma-separated values
-///     .comment_character('#') // if you want to ignore lines starting with #
-///     .skip_lines(2); // if you know that the first 2 lines are not relevant to you
-/// ```
-///
-/// Once you have gotten familiar with `Tabfile`, you probably want to check out
-/// [Record](struct.Record.html).
-///
-pub struct Tabfile {
-    reader: BufReader<File>,
-    separator: char,
-    comment_character: Option<char>,
-    skip_lines: usize,
-    skip_empty_lines: bool,
-}
-
-impl Tabfile {
-    /// Open an existing tab file
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Tabfile, Error> {
-        let fd = File::open(path)?;
-        Ok(Tabfile {
-            reader: BufReader::new(fd),
-            separator: '\t',
-            comment_character: Some('#'),
-            skip_lines: 0,
-            skip_empty_lines: true,
-        })
-    }
-
-    /// Set the separator of the tab file reader.
-    ///
-    /// The default is `'\t'`
-    pub fn separator(mut self, sep: char) -> Self {
-        self.separator = sep;
-        self
-    }
-
-    /// Set the number of lines that should be skipped when reading the tab file.
-    ///
-    /// The default is `0`.
-    pub fn skip_lines(mut self, num_lines: usize) -> Self {
-        self.skip_lines = num_lines;
-        self
-    }
-
-    /// Set a comment character for lines that should be ignored.
-    ///
-    /// All lines starting with the comment character will be ignored.
-    /// By default there is no comment character. If you use this method in combination with
-    /// `skip_lines` note that `skip_lines` is always checked first and the first `n` lines will be
-    /// dropped regardless of whether they have a comment character at the beginning or not.
-    pub fn comment_character(mut self, comment_character: char) -> Self {
-        self.comment_character = Some(comment_character);
-        self
-    }
-
-    /// Skip empty lines.
-    ///
-    /// If set to `true` (which is the default) then the iterator will only yield non-empty
-    /// vectors. If you combine this with the `skip` method, then the first `n` lines will always
-    /// be skipped regardless of whether or not they are empty.
-    pub fn skip_empty_lines(mut self, skip: bool) -> Self {
-        self.skip_empty_lines = skip;
-        self
-    }
-}
-
-impl IntoIterator for Tabfile {
-    type Item = IterItem;
-    type IntoIter = RowIterator;
-    fn into_iter(self) -> Self::IntoIter {
-        RowIterator::new(self)
-    }
-}
-
-/// Iterator over the lines of a tab file.
-///
-/// ```
-/// extern crate tabfile;
-/// use tabfile::Tabfile;
-///
-/// # use std::fs::File;
-/// # use std::io::Write;
-/// # use tempfile::NamedTempFile;
-/// # let mut file = NamedTempFile::new().unwrap();
-/// # file.write(b"first_header\tsecond_header\nfirst_field\tsecond_field");
-/// // setup for `file` ellided
-///
-/// let tsv_file = Tabfile::open(file.path()).unwrap();
-/// let iter = tsv_file.into_iter();
-/// // or alternatively
-/// # let tsv_file = Tabfile::open(file.path()).unwrap();
-/// for record_result in tsv_file { // this creates a RowIterator
-///     // do stuff
-/// }
-/// ```
-pub struct RowIterator {
-    tabfile: Tabfile,
-    next_line_number: usize,
-}
-
-impl RowIterator {
-    fn new(tabfile: Tabfile) -> RowIterator {
-        RowIterator {
-            tabfile,
-            next_line_number: 0,
-        }
-    }
-}
-
-impl Iterator for RowIterator {
-    type Item = IterItem;
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let mut line = String::new();
-            match self.tabfile.reader.read_line(&mut line) {
-                Ok(line_length) => {
-                    if self.tabfile.skip_lines > 0 {
-                        self.tabfile.skip_lines -= 1;
-                        self.next_line_number += 1;
-                        continue;
-                    }
-                    if line_length == 0 {
-                        return None; // iterator exhausted
-                    } else {
-                        if let Some(comment_char) = self.tabfile.comment_character {
-                            if line.starts_with(comment_char) {
-                                self.next_line_number += 1;
-                                continue; // fetch next line
-                            }
-                        }
-                        if line.trim() == "" && self.tabfile.skip_empty_lines {
-                            self.next_line_number += 1;
-                            continue;
-                        }
-                        self.next_line_number += 1; //line numbers are 1-based
-                        return Some(Ok(Record::new(
-                            line,
-                            self.next_line_number,
-                            self.tabfile.separator,
-                        )));
-                    }
-                }
-                Err(e) => return Some(Err(e)),
-            }
-        }
-    }
-}
-
-/// One line from a tab-separated file.
-///
-/// A `Record` gives you access to the original line as well as the individual fields of the
-/// line. You can keep ownership of the `Record` even if you continue looping over the
-/// [Tabfile](struct.Tabfile.html).
-pub struct Record {
-    line_number: usize,
-    line: String,
-    ranges: Vec<Range<usize>>,
-}
+pub mod io;
+pub mod iter;
+pub mod parse;
 
-impl Record {
-    fn new(line: String, line_number: usize, separator: char) -> Record {
-        let mut slice_start = 0;
-        let mut slice_end = 0;
-        let mut seen_newline = false;
-        let mut ranges = Vec::new();
-        for c in line.chars() {
-            if c == separator {
-                ranges.push(slice_start..slice_end);
-                slice_start = slice_end + c.len_utf8();
-            } else if c == '\n' || c == '\r' {
-                seen_newline = true;
-                ranges.push(slice_start..slice_end);
-                break; // no tolerance for multiline strings
-            }
-            slice_end += c.len_utf8();
-        }
-        if !seen_newline {
-            ranges.push(slice_start..line.len())
-        }
-        Record {
-            line,
-            line_number,
-            ranges,
-        }
-    }
-
-    /// Get the individual (tab-)separated fields of a line
-    ///
-    /// This method generates string slices on the fly from precomputed positions.
-    pub fn fields(&self) -> Vec<&str> {
-        let mut result = Vec::new();
-        for range in &self.ranges {
-            result.push(&self.line[range.clone()])
-        }
-        result
-    }
+mod atomic;
+mod batch;
+#[cfg(feature = "bio")]
+mod bio;
+mod code;
+mod collect;
+mod commented;
+mod concat;
+#[cfg(feature = "contract")]
+mod contract;
+mod count;
+mod cursor;
+#[cfg(feature = "serde")]
+mod de;
+mod diff;
+mod empty;
+mod ending;
+mod error;
+mod fingerprint;
+mod fix;
+mod follow;
+mod footer;
+mod format;
+mod from_record;
+mod header;
+mod headered;
+#[cfg(feature = "contract")]
+mod interop;
+mod into_record;
+mod join;
+mod lines;
+mod lint;
+mod metadata;
+mod offsets;
+mod page;
+#[cfg(feature = "rayon")]
+mod par_iter;
+mod parallel;
+mod partition;
+mod percent;
+mod physical;
+mod pool;
+mod progress;
+#[cfg(feature = "indicatif")]
+mod progress_bar;
+mod pseudonym;
+mod raw;
+mod reverse;
+mod sam;
+mod schema;
+mod search;
+mod shared;
+mod sketch;
+mod smart;
+mod sniff;
+mod split;
+#[cfg(feature = "duckdb")]
+mod sql;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "tabix")]
+mod tabix;
+mod types;
+mod vcf;
+mod writer;
+mod zip;
 
-    /// Get the original line unchanged
-    pub fn line(&self) -> &str {
-        &self.line
-    }
-
-    /// Get the original line number
-    ///
-    /// Please note that the line number counts start from one.
-    pub fn line_number(&self) -> usize {
-        self.line_number
-    }
-
-    /// Get the number of fields
-    pub fn len(&self) -> usize {
-        self.ranges.len()
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.ranges.is_empty()
-    }
-}
+pub use atomic::AtomicFile;
+pub use batch::{BatchesBy, Chunks, GroupByColumn};
+#[cfg(feature = "bio")]
+pub use bio::{parse_attributes, Attributes, BedRecord, BedRecords};
+pub use code::ErrorCode;
+pub use collect::DuplicatePolicy;
+pub use commented::{Line, WithComments};
+pub use concat::ConcatTabfile;
+#[cfg(feature = "contract")]
+pub use contract::{ColumnContract, Contract, ContractReport, Violation, ViolationKind};
+pub use cursor::Cursor;
+#[cfg(feature = "serde")]
+pub use de::DeserializeRecords;
+pub use diff::{diff, DiffOptions, Difference};
+pub use empty::{EmptyInput, EmptyPolicy};
+pub use ending::{LineEnding, LineEndingPolicy};
+pub use error::TabfileError;
+pub use fix::{Fix, FixKind};
+pub use follow::DEFAULT_POLL_INTERVAL;
+pub use format::{format, FormatStyle};
+#[doc(hidden)]
+pub use from_record::__private;
+pub use from_record::{FromRecord, Rows};
+pub use header::Header;
+pub use headered::{HeaderedRecord, HeaderedTabfile, MissingColumns};
+pub use into_record::IntoRecord;
+#[doc(inline)]
+pub use io::Tabfile;
+#[doc(inline)]
+pub use iter::RowIterator;
+pub use join::{hash_join, merge_join, HashJoin, JoinKind, MergeJoin};
+pub use lint::{lint, LintKind, LintRules, LintWarning};
+pub use offsets::RecordIndex;
+#[cfg(feature = "rayon")]
+pub use par_iter::{ParRecords, DEFAULT_PAR_CHUNK_SIZE};
+pub use parallel::ParallelRowIterator;
+#[doc(inline)]
+pub use parse::{
+    split_line, Dialect, EscapeStyle, FieldSlices, Fields, RaggedPolicy, Record, TrimMode,
+    Whitespace,
+};
+pub use partition::{Partition, PartitionRecords};
+pub use physical::{LineKind, PhysicalLine, PhysicalLines};
+#[cfg(feature = "indicatif")]
+pub use progress_bar::ProgressBarRows;
+pub use pseudonym::Pseudonymizer;
+pub use raw::RawLines;
+pub use reverse::ReverseRows;
+pub use schema::{Schema, SchemaVersions};
+pub use shared::SharedTabfile;
+pub use sketch::{BloomFilter, HyperLogLog, DEFAULT_HLL_PRECISION};
+pub use sniff::{Sniff, SNIFF_CANDIDATES};
+pub use split::{split_by, SplitOptions, DEFAULT_MAX_OPEN_FILES};
+#[cfg(feature = "stream")]
+pub use stream::RecordStream;
+#[cfg(feature = "derive")]
+pub use tabfile_derive::{FromRecord, IntoRecord};
+#[cfg(feature = "tabix")]
+pub use tabix::{IndexedTabfile, RegionRecords};
+pub use types::{CoercionFailure, CoercionReport, ColumnReport, ColumnType};
+pub use writer::{QuotePolicy, Tabwriter};
+pub use zip::{zip, zip_longest, Zip, ZipLongest};
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::borrow::Cow;
+    use std::fs::File;
     use std::io::Write;
     use std::path::PathBuf;
 
@@ -292,6 +156,7 @@ mod tests {
             .as_bytes();
     const EMPTY: &[u8] = b"\t\t\tleft\t\t\tright\t\t\t";
 
+    #[allow(clippy::unused_io_amount)]
     fn setup(test_file_conents: &[u8]) -> (TempDir, PathBuf) {
         let test_dir = tempdir().unwrap();
         let test_file_path = test_dir.path().join("four_column.tsv");
@@ -300,6 +165,7 @@ mod tests {
         (test_dir, test_file_path) // return test_dir so that it is not destroyed
     }
 
+    #[allow(clippy::assertions_on_constants)]
     #[test]
     fn four_column() {
         let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
@@ -354,6 +220,7 @@ mod tests {
         assert_eq!(iterations, 4);
     }
 
+    #[allow(clippy::assertions_on_constants)]
     #[test]
     fn unicode() {
         let (_test_dir, test_file_path) = setup(UNICODE);
@@ -382,6 +249,7 @@ mod tests {
         assert_eq!(iterations, 2);
     }
 
+    #[allow(clippy::assertions_on_constants)]
     #[test]
     fn empty() {
         let (_test_dir, test_file_path) = setup(EMPTY);
@@ -413,4 +281,3110 @@ mod tests {
         }
         assert_eq!(iterations, 1);
     }
+
+    #[test]
+    fn split_whitespace() {
+        let (_test_dir, test_file_path) = setup(b"  1   2\t 3 \nchr1 \t\t 100\r\n \t \n");
+        let records: Vec<Record> = Tabfile::open(test_file_path)
+            .unwrap()
+            .split_whitespace(true)
+            .skip_empty_lines(false)
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records[0].fields(), vec!["1", "2", "3"]);
+        assert_eq!(records[1].fields(), vec!["chr1", "100"]);
+        assert!(records[2].is_empty());
+    }
+
+    #[test]
+    fn struct_of_slices() {
+        let (_test_dir, test_file_path) = setup(UNICODE);
+        let record = Tabfile::open(test_file_path)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        let slices = record.as_struct_of_slices();
+        assert_eq!(slices.ptrs.len(), 2);
+        assert_eq!(slices.lens.len(), 2);
+        for (i, field) in record.fields().iter().enumerate() {
+            let bytes = unsafe { std::slice::from_raw_parts(slices.ptrs[i], slices.lens[i]) };
+            assert_eq!(bytes, field.as_bytes());
+        }
+    }
+
+    #[test]
+    fn seek_to() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let records: Vec<Record> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records[0].byte_offset(), 0);
+        assert_eq!(records[1].byte_offset(), 12);
+        assert_eq!(records[1].line(), "foo\tbar\tbaz\tquux\n");
+
+        let offset = records[3].byte_offset();
+        let mut resumed = Tabfile::open(&test_file_path)
+            .unwrap()
+            .seek_to(offset)
+            .unwrap()
+            .into_iter();
+        let record = resumed.next().unwrap().unwrap();
+        assert_eq!(record.line(), records[3].line());
+        assert_eq!(record.byte_offset(), offset);
+        assert_eq!(record.line_number(), 1);
+    }
+
+    #[test]
+    fn empty_vs_missing() {
+        let (_test_dir, test_file_path) = setup(b"a\t\tb\nc\n");
+        let records: Vec<Record> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records[0].get_opt(1), Some(""));
+        assert_eq!(records[0].get_opt(2), Some("b"));
+        assert_eq!(records[0].get_opt(3), None);
+        assert_eq!(records[1].get_opt(0), Some("c"));
+        assert_eq!(records[1].get_opt(1), None);
+
+        let records: Vec<Record> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .pad_short_rows(3)
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records[0].len(), 3);
+        assert_eq!(records[1].fields(), vec!["c", "", ""]);
+        assert_eq!(records[1].get_opt(2), Some(""));
+        assert_eq!(records[1].get_opt(3), None);
+    }
+
+    #[test]
+    fn coercion_report() {
+        let (_test_dir, test_file_path) =
+            setup(b"1\t0.5\ttrue\tx\ntwo\t1e3\tyes\ty\n3\tNaN\tno\n4.0\t.\tfalse\n");
+        let report = Tabfile::open(test_file_path)
+            .unwrap()
+            .coercion_report(
+                &[
+                    ColumnType::Integer,
+                    ColumnType::Float,
+                    ColumnType::Boolean,
+                    ColumnType::Integer,
+                ],
+                1,
+            )
+            .unwrap();
+        assert!(!report.is_clean());
+        let columns = report.columns();
+        assert_eq!(columns[0].failures(), 2);
+        assert_eq!(columns[0].examples().len(), 1);
+        assert_eq!(columns[0].examples()[0].line_number(), 2);
+        assert_eq!(columns[0].examples()[0].value(), "two");
+        assert_eq!(columns[1].failures(), 1);
+        assert_eq!(columns[1].examples()[0].value(), ".");
+        assert_eq!(columns[2].failures(), 2);
+        assert_eq!(columns[3].failures(), 2); // missing fields are not counted
+        assert_eq!(columns[3].column_type(), ColumnType::Integer);
+    }
+
+    #[test]
+    fn sniff() {
+        let (_test_dir, test_file_path) =
+            setup(b"# generated by a tool, with comments\nid,name;alias\n1,foo;f\n\n2,bar\n");
+        let sniff = Tabfile::sniff(&test_file_path, 10).unwrap();
+        assert_eq!(sniff.separator(), ',');
+        assert_eq!(sniff.confidence(), 1.0);
+
+        let sniff = Tabfile::sniff(&test_file_path, 2).unwrap();
+        assert_eq!(sniff.separator(), ',');
+
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let sniff = Tabfile::sniff(&test_file_path, 100).unwrap();
+        assert_eq!(sniff.separator(), '\t');
+        assert!(sniff.confidence() > 0.5 && sniff.confidence() < 1.0);
+
+        let (_test_dir, test_file_path) = setup(b"a;b;c,d\ne;f;g,h\n");
+        let sniff = Tabfile::sniff(&test_file_path, 10).unwrap();
+        assert_eq!(sniff.separator(), ';');
+
+        let (_test_dir, test_file_path) = setup(b"% a;b\n% c;d\n% e;f\n1,2\n3,4\n");
+        assert_eq!(
+            Tabfile::sniff(&test_file_path, 10).unwrap().separator(),
+            ';'
+        );
+        let dialect = Dialect::new().comment_character('%');
+        assert_eq!(dialect.sniff(&test_file_path, 10).unwrap().separator(), ',');
+
+        let (_test_dir, test_file_path) = setup(b"no separators here\n");
+        let sniff = Tabfile::sniff(&test_file_path, 10).unwrap();
+        assert_eq!(sniff.separator(), '\t');
+        assert_eq!(sniff.confidence(), 0.0);
+    }
+
+    #[test]
+    fn batches_by() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\na\t2\nb\t3\nc\t4\nc\t5\nc\t6\n");
+        let sizes: Vec<usize> = Tabfile::open(test_file_path)
+            .unwrap()
+            .batches_by(0)
+            .map(|batch| batch.unwrap().len())
+            .collect();
+        assert_eq!(sizes, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn batches_by_null_token_keys() {
+        let (_test_dir, test_file_path) = setup(b"NA\t1\nNA\t2\n.\t3\n.\t4\n\t5\nshort\n");
+        let mut batches = Tabfile::open(&test_file_path)
+            .unwrap()
+            .null_tokens(&["NA", ".", ""])
+            .batches_by(0);
+        for expected in [vec!["1", "2"], vec!["3", "4"], vec!["5"]] {
+            let batch = batches.next().unwrap().unwrap();
+            let values: Vec<&str> = batch.iter().map(|record| &record[1]).collect();
+            assert_eq!(values, expected);
+        }
+        let mut batches = Tabfile::open(&test_file_path).unwrap().batches_by(1);
+        assert_eq!(batches.next().unwrap().unwrap().len(), 1);
+        for _ in 0..4 {
+            batches.next().unwrap().unwrap();
+        }
+        let error = batches.next().unwrap().unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+        assert!(batches.next().is_none());
+    }
+
+    #[test]
+    fn batches_by_keeps_records_before_error() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\na\t2\n\xff\na\t3\nb\t4\n");
+        let items: Vec<Result<Vec<String>, std::io::ErrorKind>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .batches_by(0)
+            .map(|batch| {
+                batch
+                    .map(|records| records.iter().map(|r| r[1].to_string()).collect())
+                    .map_err(|e| e.kind())
+            })
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                Ok(vec!["1".to_string(), "2".to_string()]),
+                Err(std::io::ErrorKind::InvalidData),
+                Ok(vec!["3".to_string()]),
+                Ok(vec!["4".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn separator_str() {
+        let (_test_dir, test_file_path) = setup(b"a||b|c||\n||d\r\n");
+        let records: Vec<Record> = Tabfile::open(test_file_path)
+            .unwrap()
+            .separator_str("||")
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records[0].fields(), vec!["a", "b|c", ""]);
+        assert_eq!(records[1].fields(), vec!["", "d"]);
+    }
+
+    #[test]
+    fn approx_distinct() {
+        let mut contents = String::new();
+        for i in 0..20000 {
+            contents.push_str(&format!("{}\tkey{}\n", i, i % 5000));
+        }
+        let (_test_dir, test_file_path) = setup(contents.as_bytes());
+        let hll = Tabfile::open(&test_file_path)
+            .unwrap()
+            .approx_distinct(1)
+            .unwrap();
+        let estimate = hll.estimate();
+        assert!((estimate - 5000.0).abs() < 250.0, "estimate={}", estimate);
+
+        let mut merged = HyperLogLog::from_bytes(&hll.to_bytes()).unwrap();
+        assert_eq!(merged, hll);
+        let mut other = HyperLogLog::new(DEFAULT_HLL_PRECISION);
+        for i in 0..5000 {
+            other.insert(&format!("other{}", i));
+        }
+        merged.merge(&other);
+        let estimate = merged.estimate();
+        assert!((estimate - 10000.0).abs() < 500.0, "estimate={}", estimate);
+        assert!(HyperLogLog::from_bytes(&[14, 0, 0]).is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn separator_regex() {
+        let (_test_dir, test_file_path) = setup(b"a \t; b;c  ;\t\td\n");
+        let record = Tabfile::open(test_file_path)
+            .unwrap()
+            .separator_regex(regex::Regex::new(r"\s*;\s*").unwrap())
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.fields(), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn build_bloom() {
+        let mut contents = String::new();
+        for i in 0..10000 {
+            contents.push_str(&format!("key{}\t{}\n", i % 2000, i));
+        }
+        let (_test_dir, test_file_path) = setup(contents.as_bytes());
+        let bloom = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .build_bloom(0, 0.01)
+            .unwrap();
+        assert!((0..2000).all(|i| bloom.contains(&format!("key{}", i))));
+        let false_positives = (0..10000)
+            .filter(|i| bloom.contains(&format!("other{}", i)))
+            .count();
+        assert!(false_positives < 200, "false_positives={}", false_positives);
+
+        let copy = BloomFilter::from_bytes(&bloom.to_bytes()).unwrap();
+        assert_eq!(copy, bloom);
+        assert!(BloomFilter::from_bytes(b"garbage").is_err());
+    }
+
+    #[test]
+    fn trim_fields() {
+        let (_test_dir, test_file_path) = setup(b" a \t\t  b\t \n");
+        let fields = |mode| {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .trim_fields(mode)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap()
+                .fields()
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(fields(TrimMode::None), vec![" a ", "", "  b", " "]);
+        assert_eq!(fields(TrimMode::Start), vec!["a ", "", "b", ""]);
+        assert_eq!(fields(TrimMode::End), vec![" a", "", "  b", ""]);
+        assert_eq!(fields(TrimMode::Both), vec!["a", "", "b", ""]);
+    }
+
+    #[test]
+    fn unicode_whitespace() {
+        let (_test_dir, test_file_path) =
+            setup("\u{a0}1.5\u{a0}\t New\u{a0}York\u{2003}\n".as_bytes());
+        let fields = |whitespace, split| {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .trim_fields(TrimMode::Both)
+                .split_whitespace(split)
+                .whitespace(whitespace)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap()
+                .fields()
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            fields(Whitespace::Ascii, false),
+            vec!["\u{a0}1.5\u{a0}", "New\u{a0}York\u{2003}"]
+        );
+        assert_eq!(
+            fields(Whitespace::Unicode, false),
+            vec!["1.5", "New\u{a0}York"]
+        );
+        assert_eq!(
+            fields(Whitespace::Ascii, true),
+            vec!["\u{a0}1.5\u{a0}", "New\u{a0}York\u{2003}"]
+        );
+        assert_eq!(
+            fields(Whitespace::Unicode, true),
+            vec!["1.5", "New", "York"]
+        );
+    }
+
+    #[test]
+    fn record_traits() {
+        let dialect = Dialect::new();
+        let record = dialect.parse_line("foo\tbar\tbaz\n", 1);
+        assert_eq!(&record[1], "bar");
+        assert_eq!(record.iter().len(), 3);
+        assert_eq!(record.iter().nth(2), Some("baz"));
+        assert_eq!(
+            (&record).into_iter().rev().collect::<Vec<&str>>(),
+            vec!["baz", "bar", "foo"]
+        );
+        let copy = record.clone();
+        assert_eq!(copy, record);
+        assert_eq!(copy.line_number(), 1);
+        assert_eq!(record, dialect.parse_line("foo\tbar\tbaz", 7));
+        assert_ne!(record, dialect.parse_line("foo\tbar", 1));
+        // the record pool does not take part in hashing
+        #[allow(clippy::mutable_key_type)]
+        let set: std::collections::HashSet<Record> = vec![copy, record].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        assert_eq!(
+            format!("{:?}", dialect.parse_line("a\tb", 3)),
+            r#"Record { line_number: 3, byte_offset: 0, fields: ["a", "b"] }"#
+        );
+    }
+
+    #[test]
+    fn record_pool() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let mut records = Tabfile::open(test_file_path)
+            .unwrap()
+            .record_pool(1)
+            .into_iter();
+        let first = records.next().unwrap().unwrap();
+        let first_buffer = first.line().as_ptr();
+        drop(first);
+        let second = records.next().unwrap().unwrap();
+        assert_eq!(second.line().as_ptr(), first_buffer);
+        assert_eq!(second.fields(), vec!["foo", "bar", "baz", "quux"]);
+        let third = records.next().unwrap().unwrap();
+        assert_ne!(third.line().as_ptr(), second.line().as_ptr());
+        assert_eq!(third.line(), "alpha\tbeta\tgamma\tdelta\n");
+    }
+
+    #[test]
+    fn null_tokens() {
+        let (_test_dir, test_file_path) = setup(b"1\tNA\t.\t\tNAN\n");
+        let record = Tabfile::open(&test_file_path)
+            .unwrap()
+            .null_tokens(&["NA", ".", ""])
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.get_opt(0), Some("1"));
+        assert_eq!(record.get_opt(1), None);
+        assert_eq!(record.get_opt(2), None);
+        assert_eq!(record.get_opt(3), None);
+        assert_eq!(record.get_opt(4), Some("NAN"));
+        assert_eq!(record.fields()[1], "NA");
+
+        let report = Tabfile::open(&test_file_path)
+            .unwrap()
+            .null_tokens(&["NA", ".", ""])
+            .coercion_report(&[ColumnType::Integer; 5], 0)
+            .unwrap();
+        let failures: Vec<usize> = report.columns().iter().map(|c| c.failures()).collect();
+        assert_eq!(failures, vec![0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn push_parser() {
+        let mut parser = parse::Parser::new(Dialect::new());
+        let mut records = Vec::new();
+        for chunk in FOUR_COLUMN.chunks(5) {
+            records.extend(parser.feed(chunk).into_iter().map(Result::unwrap));
+        }
+        records.extend(parser.finish().into_iter().map(Result::unwrap));
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let expected: Vec<Record> = Tabfile::open(test_file_path)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records.len(), expected.len());
+        for (record, expected) in records.iter().zip(&expected) {
+            assert_eq!(record.line(), expected.line());
+            assert_eq!(record.fields(), expected.fields());
+            assert_eq!(record.line_number(), expected.line_number());
+            assert_eq!(record.byte_offset(), expected.byte_offset());
+        }
+
+        let mut parser = parse::Parser::new(Dialect::new());
+        let records = parser.feed(b"ok\nnot \xff ok\n");
+        assert_eq!(records[0].as_ref().unwrap().fields(), vec!["ok"]);
+        let error = records[1].as_ref().unwrap_err();
+        assert_eq!(error.to_string(), "[TF017] line 2 is not valid UTF-8");
+        let records = parser.feed(b"fine\n");
+        assert_eq!(records[0].as_ref().unwrap().line_number(), 3);
+        assert_eq!(records[0].as_ref().unwrap().byte_offset(), 12);
+
+        let mut parser = parse::Parser::new(Dialect::new().on_ragged(2, RaggedPolicy::Error));
+        let lines: Vec<Result<usize, Option<ErrorCode>>> = parser
+            .feed(b"a\t1\nb\nc\t3\n")
+            .into_iter()
+            .map(|record| {
+                record
+                    .map(|record| record.line_number())
+                    .map_err(|e| ErrorCode::of(&e))
+            })
+            .collect();
+        assert_eq!(lines, vec![Ok(1), Err(Some(ErrorCode::ColumnCount)), Ok(3)]);
+    }
+
+    #[test]
+    fn separators() {
+        let dialect = Dialect::new();
+        let record = dialect.parse_line("a\tb\t\tc\rd\te\n", 1);
+        assert_eq!(record.fields(), vec!["a", "b", "", "c"]);
+        let record = dialect.parse_line("ä\t💣\t", 1);
+        assert_eq!(record.fields(), vec!["ä", "💣", ""]);
+
+        let dialect = Dialect::new().separator('§');
+        let record = dialect.parse_line("ä§§💣§b\r\n", 1);
+        assert_eq!(record.fields(), vec!["ä", "", "💣", "b"]);
+    }
+
+    #[test]
+    fn read_record_into() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let mut rows = Tabfile::open(&test_file_path).unwrap().into_iter();
+        let mut record = Record::default();
+        assert!(record.is_empty());
+        let mut lines = Vec::new();
+        while rows.read_record_into(&mut record).unwrap() {
+            lines.push((record.line_number(), record.fields().join(",")));
+        }
+        assert!(record.is_empty());
+        assert_eq!(
+            lines,
+            vec![
+                (1, "line noise".to_string()),
+                (3, "foo,bar,baz,quux".to_string()),
+                (4, "alpha,beta,gamma,delta".to_string()),
+                (6, "Leonardo,Michelangelo,Donatello,Raphael".to_string()),
+                (8, "red,yellow,green".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn zip() {
+        let (_dir_a, path_a) = setup(b"a\t1\nb\t2\nc\t3\n");
+        let (_dir_b, path_b) = setup(b"#comment\na\t1\nb\t4\n");
+        let pairs: Vec<(String, String)> = super::zip(
+            Tabfile::open(&path_a).unwrap(),
+            Tabfile::open(&path_b).unwrap(),
+        )
+        .take(2)
+        .map(|pair| {
+            let (a, b) = pair.unwrap();
+            (a.line().to_string(), b.line().to_string())
+        })
+        .collect();
+        assert_eq!(pairs[1], ("b\t2\n".to_string(), "b\t4\n".to_string()));
+
+        let mut zipped = super::zip(
+            Tabfile::open(&path_a).unwrap(),
+            Tabfile::open(&path_b).unwrap(),
+        );
+        let error = zipped.nth(2).unwrap().err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(zipped.next().is_none());
+
+        let sides: Vec<(bool, bool)> = zip_longest(
+            Tabfile::open(&path_b).unwrap(),
+            Tabfile::open(&path_a).unwrap(),
+        )
+        .map(|pair| {
+            let (a, b) = pair.unwrap();
+            (a.is_some(), b.is_some())
+        })
+        .collect();
+        assert_eq!(sides, vec![(true, true), (true, true), (false, true)]);
+    }
+
+    #[test]
+    fn parallel_iter() {
+        let mut content = String::from("header\tline\n");
+        for i in 0..5000 {
+            if i % 7 == 0 {
+                content.push_str("# comment\n\n");
+            }
+            content.push_str(&format!("{}\t{}\t{}\n", i, i * 2, i % 3));
+        }
+        let (_dir, path) = setup(content.as_bytes());
+        let open = || Tabfile::open(&path).unwrap().skip_lines(1);
+        let sequential: Vec<(usize, u64, Vec<String>)> = open()
+            .into_iter()
+            .map(|r| {
+                let r = r.unwrap();
+                (
+                    r.line_number(),
+                    r.byte_offset(),
+                    r.fields().iter().map(|f| f.to_string()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(sequential.len(), 5000);
+        for num_threads in &[0, 1, 3] {
+            let parallel: Vec<(usize, u64, Vec<String>)> = open()
+                .record_pool(16)
+                .into_parallel_iter(*num_threads)
+                .map(|r| {
+                    let r = r.unwrap();
+                    (
+                        r.line_number(),
+                        r.byte_offset(),
+                        r.fields().iter().map(|f| f.to_string()).collect(),
+                    )
+                })
+                .collect();
+            assert_eq!(parallel, sequential);
+        }
+        let mut early_stop = open().into_parallel_iter(2);
+        assert_eq!(early_stop.next().unwrap().unwrap().fields()[0], "0");
+    }
+
+    #[test]
+    fn auto_fix() {
+        let (_dir, path) = setup(
+            "\u{feff}# comment \t\r\nid \tvalue\r\n1  \t\u{a0}2.5\u{a0}\nNew\u{a0}York\tx \n"
+                .as_bytes(),
+        );
+        let mut rows = Tabfile::open(&path).unwrap().auto_fix(true).into_iter();
+        let fields: Vec<Vec<String>> = (&mut rows)
+            .map(|r| r.unwrap().fields().iter().map(|f| f.to_string()).collect())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                vec!["id", "value"],
+                vec!["1", "2.5"],
+                vec!["New\u{a0}York", "x "]
+            ]
+        );
+        let fixes: Vec<(usize, FixKind)> = rows
+            .fixes()
+            .iter()
+            .map(|f| (f.line_number(), f.kind()))
+            .collect();
+        assert_eq!(
+            fixes,
+            vec![
+                (1, FixKind::ByteOrderMark),
+                (2, FixKind::CarriageReturn),
+                (2, FixKind::SpaceBeforeSeparator),
+                (3, FixKind::SpaceBeforeSeparator),
+                (3, FixKind::NonBreakingSpace),
+            ]
+        );
+        let mut rows = Tabfile::open(&path).unwrap().into_iter();
+        assert_eq!(
+            rows.next().unwrap().unwrap().fields()[0],
+            "\u{feff}# comment "
+        );
+        assert!(rows.fixes().is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_par_iter() {
+        use rayon::prelude::*;
+        let mut content = String::from("# comment\n");
+        for i in 0..10000 {
+            content.push_str(&format!("{}\t{}\n", i, i % 10));
+        }
+        let (_dir, path) = setup(content.as_bytes());
+        let line_numbers: Vec<usize> = Tabfile::open(&path)
+            .unwrap()
+            .into_par_iter()
+            .chunk_size(999)
+            .map(|r| r.unwrap().line_number())
+            .collect();
+        assert_eq!(line_numbers, (2..10002).collect::<Vec<usize>>());
+        let sum: usize = Tabfile::open(&path)
+            .unwrap()
+            .into_par_iter()
+            .map(|r| r.unwrap().fields()[1].parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(sum, 45000);
+        let first_big = Tabfile::open(&path)
+            .unwrap()
+            .into_par_iter()
+            .find_first(|r| r.as_ref().unwrap().fields()[0].len() == 4)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first_big.fields()[0], "1000");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_checks_columns() {
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        struct Chrom {
+            chrom: String,
+        }
+
+        let (_test_dir, test_file_path) = setup(b"chrom\tpos\tqual\nchr1\t12\t0.5\nchr2\t34\n");
+        let chroms: Vec<Result<Chrom, std::io::Error>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .columns(3)
+            .deserialize()
+            .collect();
+        assert_eq!(chroms[0].as_ref().unwrap().chrom, "chr1");
+        assert_eq!(
+            chroms[1].as_ref().err().unwrap().to_string(),
+            "[TF002] line 3: expected 3 fields but found 2"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Variant {
+            chrom: String,
+            pos: u64,
+            qual: Option<f32>,
+        }
+
+        let (_test_dir, test_file_path) =
+            setup(b"qual\tid\tpos\tchrom\n0.5\tx\t12\tchr1\n\ty\t34\tchr2\n1\tz\tfoo\tchr3\n");
+        let variants: Vec<Result<Variant, std::io::Error>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize()
+            .collect();
+        assert_eq!(
+            variants[0].as_ref().unwrap(),
+            &Variant {
+                chrom: "chr1".to_string(),
+                pos: 12,
+                qual: Some(0.5)
+            }
+        );
+        assert_eq!(variants[1].as_ref().unwrap().qual, None);
+        let error = variants[2].as_ref().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "[TF004] line 4: column `pos`: cannot parse `foo` as u64"
+        );
+
+        let (_test_dir, test_file_path) = setup(b"chrom\tqual\nchr1\t0.5\n");
+        let error = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize::<Variant>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "[TF005] missing columns: pos");
+
+        let (_empty_dir, empty_path) = setup(b"qual\n");
+        let mut rows = Tabfile::open(&empty_path).unwrap().deserialize::<Variant>();
+        let error = rows.next().unwrap().unwrap_err();
+        let missing = error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<MissingColumns>())
+            .unwrap();
+        assert_eq!(missing.columns(), ["chrom", "pos"]);
+        assert!(rows.next().is_none());
+
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize::<std::collections::HashMap<String, String>>();
+        assert_eq!(rows.next().unwrap().unwrap()["qual"], "0.5");
+
+        #[derive(Deserialize)]
+        struct Chrom {
+            chrom: String,
+        }
+        let (_test_dir, test_file_path) = setup(b"chrom\tpos\tqual\n1\tfoo\tbar\n2\n");
+        let chroms: Vec<String> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize::<Chrom>()
+            .map(|chrom| chrom.unwrap().chrom)
+            .collect();
+        assert_eq!(chroms, vec!["1", "2"]);
+
+        #[derive(Deserialize)]
+        struct Tagged {
+            tags: Vec<String>,
+            scores: Option<Vec<u8>>,
+        }
+        let (_test_dir, test_file_path) = setup(b"tags\tscores\na;b;c\t1,2\n\t7\nd\t\ne\t3,x\n");
+        let mut tagged = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize::<Tagged>()
+            .schema(
+                Schema::new()
+                    .list_separator("tags", ';')
+                    .list_separator("scores", ','),
+            );
+        let first = tagged.next().unwrap().unwrap();
+        assert_eq!(first.tags, vec!["a", "b", "c"]);
+        assert_eq!(first.scores, Some(vec![1, 2]));
+        let second = tagged.next().unwrap().unwrap();
+        assert!(second.tags.is_empty());
+        assert_eq!(second.scores, Some(vec![7]));
+        assert_eq!(tagged.next().unwrap().unwrap().scores, None);
+        let error = tagged.next().unwrap().err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "[TF004] line 5: column `scores`: cannot parse `x` as u8"
+        );
+
+        #[derive(Deserialize)]
+        struct Versioned {
+            id: u32,
+            weight: f64,
+            unit: String,
+        }
+        let (_test_dir, test_file_path) = setup(b"id\tweight\n1\t2.5\n2\tNA\n3\n");
+        let weights: Vec<(u32, f64, String)> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .null_tokens(&["NA"])
+            .deserialize::<Versioned>()
+            .schema(
+                Schema::new()
+                    .default_value("weight", "0")
+                    .default_value("unit", "kg"),
+            )
+            .map(|v| {
+                let v = v.unwrap();
+                (v.id, v.weight, v.unit)
+            })
+            .collect();
+        assert_eq!(
+            weights,
+            vec![
+                (1, 2.5, "kg".to_string()),
+                (2, 0.0, "kg".to_string()),
+                (3, 0.0, "kg".to_string())
+            ]
+        );
+
+        let versions = || {
+            SchemaVersions::new()
+                .version_with_header(
+                    "1",
+                    &["id", "weight"],
+                    Schema::new().default_value("unit", "lb"),
+                )
+                .version("2", Schema::new())
+        };
+        for (content, expected_version, unit) in &[
+            (&b"id\tweight\n1\t2\n"[..], "1", "lb"),
+            (
+                &b"##version=2\n# exported today\nunit\tweight\tid\nkg\t2\t1\n"[..],
+                "2",
+                "kg",
+            ),
+        ] {
+            let (_test_dir, test_file_path) = setup(content);
+            let mut rows = Tabfile::open(&test_file_path)
+                .unwrap()
+                .deserialize::<Versioned>()
+                .schema_versions(versions());
+            assert_eq!(rows.next().unwrap().unwrap().unit, *unit);
+            assert_eq!(rows.version(), Some(*expected_version));
+        }
+        let (_test_dir, test_file_path) = setup(b"##version=3\nid\tweight\tunit\n1\t2\tkg\n");
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .deserialize::<Versioned>()
+            .schema_versions(versions());
+        assert_eq!(
+            rows.next().unwrap().err().unwrap().to_string(),
+            "[TF010] unknown format version `3`"
+        );
+        assert!(rows.next().is_none());
+
+        let record = Dialect::new().parse_line("chr1\t12\t", 1);
+        let (chrom, pos, qual): (&str, u64, Option<f32>) = record.deserialize(None).unwrap();
+        assert_eq!((chrom, pos, qual), ("chr1", 12, None));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn into_stream() {
+        use futures::StreamExt;
+        let mut content = String::new();
+        for i in 0..1000 {
+            content.push_str(&format!("{}\t{}\n", i, i * i));
+        }
+        let (_dir, path) = setup(content.as_bytes());
+        let stream = Tabfile::open(&path).unwrap().into_stream(8);
+        let sum: u64 = futures::executor::block_on(
+            stream
+                .map(|r| r.unwrap().fields()[0].parse::<u64>().unwrap())
+                .fold(0, |sum, n| async move { sum + n }),
+        );
+        assert_eq!(sum, 499500);
+        let mut stream = Tabfile::open(&path).unwrap().into_stream(1);
+        let first = futures::executor::block_on(stream.next()).unwrap().unwrap();
+        assert_eq!(first.fields(), vec!["0", "0"]);
+    }
+
+    #[test]
+    fn collect_helpers() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\nb\t2\na\t3\n");
+        let open = || Tabfile::open(&test_file_path).unwrap();
+        let records = open().collect_records().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].fields(), vec!["a", "3"]);
+        assert_eq!(open().read_column(1).unwrap(), vec!["1", "2", "3"]);
+        let map = open().read_map(0, 1).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], "3");
+        let error = open().read_column(2).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "[TF003] line 1: expected a field at column 2 but the line has 2 fields"
+        );
+    }
+
+    #[test]
+    fn headered_tabfile() {
+        let (_test_dir, test_file_path) = setup(b"# comment\nchrom\tpos\nchr1\t12\nchr2\n");
+        let tsv_file = HeaderedTabfile::open(&test_file_path)
+            .unwrap()
+            .require_columns(&["pos"])
+            .unwrap();
+        assert_eq!(tsv_file.header().names(), &["chrom", "pos"]);
+        let records: Vec<HeaderedRecord> = tsv_file.map(|r| r.unwrap()).collect();
+        assert_eq!(&records[0]["chrom"], "chr1");
+        assert_eq!(&records[0][1], "12");
+        assert_eq!(records[0].line_number(), 3);
+        assert_eq!(records[1].get("pos"), None);
+        assert_eq!(records[1].get("qual"), None);
+
+        let error = HeaderedTabfile::open(&test_file_path)
+            .unwrap()
+            .require_columns(&["chrom", "ref", "alt"])
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let missing = error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<MissingColumns>())
+            .unwrap();
+        assert_eq!(missing.columns(), &["ref", "alt"]);
+
+        let (_test_dir, test_file_path) = setup(b"# only a comment\n");
+        assert!(HeaderedTabfile::open(&test_file_path).is_err());
+    }
+
+    #[test]
+    fn physical_lines() {
+        let content: &[u8] = b"preamble\n# comment\r\n\na\tb\r\nc";
+        let (_test_dir, test_file_path) = setup(content);
+        let lines: Vec<PhysicalLine> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .physical_lines()
+            .map(|line| line.unwrap())
+            .collect();
+        let kinds: Vec<LineKind> = lines.iter().map(|line| line.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LineKind::Skipped,
+                LineKind::Comment,
+                LineKind::Empty,
+                LineKind::Data,
+                LineKind::Data
+            ]
+        );
+        assert_eq!(lines[3].content(), "a\tb");
+        assert_eq!(lines[3].terminator(), "\r\n");
+        assert_eq!(lines[3].line_number(), 4);
+        assert_eq!(lines[3].byte_offset(), 21);
+        assert_eq!(lines[4].terminator(), "");
+        let text: String = lines.iter().map(|line| line.text()).collect();
+        assert_eq!(text.as_bytes(), content);
+    }
+
+    #[test]
+    fn lint_file() {
+        let (_test_dir, test_file_path) =
+            setup(b"# header\nb\t1\na\t2 \nc,3\n\nd\t\"4\t5\n\xe9t\xe9\t6\ne\t7\r\n");
+        let warnings: Vec<String> = lint(&test_file_path, &LintRules::new().sorted_by(0))
+            .unwrap()
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "line 3: trailing whitespace",
+                "line 3: not sorted by column 0",
+                "line 4: the separator is missing, but ',' is used",
+                "line 4: expected 2 fields, found 1",
+                "line 6: expected 2 fields, found 3",
+                "line 6: unbalanced quotes in column 1",
+                "line 7: invalid UTF-8",
+            ]
+        );
+        let rules = LintRules::new()
+            .check_trailing_whitespace(false)
+            .check_column_count(false)
+            .check_mixed_separators(false)
+            .check_quoting(false)
+            .check_encoding(false);
+        assert!(lint(&test_file_path, &rules).unwrap().is_empty());
+    }
+
+    #[test]
+    fn typed_rows() {
+        use std::io::{Error, ErrorKind};
+
+        struct Position {
+            chrom: String,
+            pos: u64,
+        }
+
+        impl FromRecord for Position {
+            fn from_record(record: &Record, header: Option<&Header>) -> Result<Self, Error> {
+                let column = |name, default| header.map_or(Some(default), |h| h.position(name));
+                let field = |name, default| {
+                    column(name, default)
+                        .and_then(|position| record.get_opt(position))
+                        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing field"))
+                };
+                Ok(Position {
+                    chrom: field("chrom", 0)?.to_string(),
+                    pos: field("pos", 1)?
+                        .parse()
+                        .map_err(|_| Error::new(ErrorKind::InvalidData, "bad position"))?,
+                })
+            }
+        }
+
+        let (_test_dir, test_file_path) = setup(b"chrom\tpos\nchr1\t12\nchr2\tx\nchr3\t34\n");
+        let rows = Tabfile::open(&test_file_path).unwrap().rows::<Position>();
+        assert!(rows.header().is_none());
+        let errors: Vec<usize> = rows.filter_map(|row| row.err()?.line_number()).collect();
+        assert_eq!(errors, vec![1, 3]);
+
+        let rows = HeaderedTabfile::open(&test_file_path)
+            .unwrap()
+            .rows::<Position>();
+        assert_eq!(rows.header().unwrap().names(), &["chrom", "pos"]);
+        let rows: Vec<Result<Position, TabfileError>> = rows.collect();
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!((first.chrom.as_str(), first.pos), ("chr1", 12));
+        match &rows[1] {
+            Err(TabfileError::Convert { line_number, error }) => {
+                assert_eq!(*line_number, 3);
+                assert_eq!(error.to_string(), "bad position");
+            }
+            _ => panic!("expected a conversion error"),
+        }
+        let source = rows[1].as_ref().err().and_then(std::error::Error::source);
+        assert_eq!(source.unwrap().to_string(), "bad position");
+        let error: Error = rows.into_iter().nth(1).unwrap().err().unwrap().into();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn format_file() {
+        let (_test_dir, test_file_path) =
+            setup(b"# comment\nc\t a\tb\r\n3\t1 \t\n\n6\t4\t5\t7\n9\n");
+        let mut output = Vec::new();
+        let style = FormatStyle::new().null("NA").column_order(&["a", "b", "d"]);
+        format(Tabfile::open(&test_file_path).unwrap(), &mut output, &style).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a\tb\td\tc\n1\tNA\tNA\t3\n4\t5\tNA\t6\t7\nNA\tNA\tNA\t9\n"
+        );
+
+        let (_test_dir, test_file_path) = setup(b"name\tcity\nJane Doe\tBerlin\n");
+        let mut output = Vec::new();
+        let style = FormatStyle::new().separator(' ');
+        let error = format(Tabfile::open(&test_file_path).unwrap(), &mut output, &style)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn comment_prefix() {
+        let (_test_dir, test_file_path) = setup(b"// comment\n-- not a comment\n/ data\n#data\n");
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .comment_prefix("//")
+            .into_iter();
+        let lines: Vec<String> = rows
+            .by_ref()
+            .map(|record| record.unwrap()[0].to_string())
+            .collect();
+        assert_eq!(lines, vec!["-- not a comment", "/ data", "#data"]);
+        assert_eq!(rows.leading_comments(), &["// comment"]);
+
+        let lines: Vec<String> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .comment_prefixes(&["//", "--", "#"])
+            .into_iter()
+            .map(|record| record.unwrap()[0].to_string())
+            .collect();
+        assert_eq!(lines, vec!["/ data"]);
+        let lines = Tabfile::open(&test_file_path)
+            .unwrap()
+            .comment_prefixes(&[])
+            .into_iter()
+            .count();
+        assert_eq!(lines, 4);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let fingerprint = |content: &[u8]| {
+            let (_test_dir, test_file_path) = setup(content);
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .fingerprint()
+                .unwrap()
+        };
+        let original = fingerprint(b"a\tb\nc\td\n");
+        assert_eq!(original, fingerprint(b"# comment\r\na\tb \t\r\n\nc\td"));
+        assert_ne!(original, fingerprint(b"c\td\na\tb\n"));
+        assert_ne!(original, fingerprint(b"a\tb\tc\td\n"));
+        assert_ne!(original, fingerprint(b"a\t\tb\nc\td\n"));
+        assert_ne!(original, fingerprint(b"a\tb\n"));
+    }
+
+    #[test]
+    fn empty_policy() {
+        let empty_input = |error: std::io::Error| {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+            *error
+                .get_ref()
+                .unwrap()
+                .downcast_ref::<EmptyInput>()
+                .unwrap()
+        };
+        let (_test_dir, test_file_path) = setup(b"# comment\n\n");
+        assert_eq!(
+            Tabfile::open(&test_file_path).unwrap().into_iter().count(),
+            0
+        );
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .empty_policy(EmptyPolicy::Error)
+            .into_iter();
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(empty_input(error), EmptyInput::EmptyFile);
+        assert!(rows.next().is_none());
+        let error = HeaderedTabfile::open(&test_file_path).err().unwrap();
+        assert_eq!(empty_input(error), EmptyInput::EmptyFile);
+
+        let (_test_dir, test_file_path) = setup(b"chrom\tpos\n");
+        assert_eq!(HeaderedTabfile::open(&test_file_path).unwrap().count(), 0);
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .empty_policy(EmptyPolicy::Error);
+        let mut rows = HeaderedTabfile::new(tabfile).unwrap();
+        let error = rows.next().unwrap().err().unwrap();
+        assert_eq!(empty_input(error), EmptyInput::HeaderOnly);
+        assert!(rows.next().is_none());
+
+        let (_test_dir, test_file_path) = setup(b"chrom\tpos\nchr1\t12\n");
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .empty_policy(EmptyPolicy::Error);
+        let records = HeaderedTabfile::new(tabfile).unwrap();
+        let records: Vec<HeaderedRecord> = records.map(|record| record.unwrap()).collect();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn with_comments() {
+        let (_test_dir, test_file_path) =
+            setup(b"skipped\n## meta\r\na\tb\n\n# between\nc\td\n#last");
+        let lines: Vec<Line> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .with_comments()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], Line::Comment("## meta".to_string()));
+        match &lines[1] {
+            Line::Record(record) => {
+                assert_eq!(record.fields(), vec!["a", "b"]);
+                assert_eq!(record.line_number(), 3);
+            }
+            line => panic!("expected a record, got {:?}", line),
+        }
+        assert_eq!(lines[2], Line::Comment("# between".to_string()));
+        assert_eq!(lines[4], Line::Comment("#last".to_string()));
+    }
+
+    #[test]
+    fn read_metadata() {
+        let (_test_dir, test_file_path) =
+            setup(b"preamble\n##a=1\r\n\n##b=2\nid\tvalue\n# not metadata\n1\t2\n");
+        let mut tsv_file = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        assert!(tsv_file.metadata().is_empty());
+        assert_eq!(tsv_file.read_metadata().unwrap(), &["##a=1", "##b=2"]);
+        assert_eq!(tsv_file.metadata(), &["##a=1", "##b=2"]);
+        let mut rows = tsv_file.into_iter();
+        let record = rows.next().unwrap().unwrap();
+        assert_eq!((record.line_number(), record.byte_offset()), (5, 23));
+        assert_eq!(record.fields(), vec!["id", "value"]);
+        assert_eq!(rows.next().unwrap().unwrap().line_number(), 7);
+        assert_eq!(rows.leading_comments(), &["##a=1", "##b=2"]);
+        rows.rewind().unwrap();
+        assert_eq!(rows.next().unwrap().unwrap().line_number(), 5);
+
+        let (_test_dir, test_file_path) = setup(b"# only comments\n");
+        let mut tsv_file = Tabfile::open(&test_file_path).unwrap();
+        assert_eq!(tsv_file.read_metadata().unwrap(), &["# only comments"]);
+        assert_eq!(tsv_file.into_iter().count(), 0);
+    }
+
+    #[test]
+    fn iterator_state() {
+        let (_test_dir, test_file_path) = setup(b"# comment\na\t1\n\nb\t2\nc\t3\n");
+        let mut rows = Tabfile::open(&test_file_path).unwrap().into_iter();
+        assert_eq!(
+            (rows.byte_offset(), rows.line_number(), rows.records_read()),
+            (0, 0, 0)
+        );
+        rows.next().unwrap().unwrap();
+        assert_eq!(
+            (rows.byte_offset(), rows.line_number(), rows.records_read()),
+            (14, 2, 1)
+        );
+        rows.next().unwrap().unwrap();
+        assert_eq!(
+            (rows.byte_offset(), rows.line_number(), rows.records_read()),
+            (19, 4, 2)
+        );
+        let token = rows.byte_offset();
+        let rest: Vec<String> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .seek_to(token)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.unwrap()[0].to_string())
+            .collect();
+        assert_eq!(rest, vec!["c"]);
+        assert!(rows.nth(1).is_none());
+        assert_eq!(
+            (rows.byte_offset(), rows.line_number(), rows.records_read()),
+            (23, 5, 3)
+        );
+    }
+
+    #[test]
+    fn page() {
+        let (_test_dir, test_file_path) = setup(b"a\n# comment\nb\n\nc\nd\ne\n");
+        let page = |offset, limit| -> Vec<String> {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .page(offset, limit)
+                .unwrap()
+                .map(|record| record.unwrap().line().trim_end().to_string())
+                .collect()
+        };
+        assert_eq!(page(0, 2), vec!["a", "b"]);
+        assert_eq!(page(2, 2), vec!["c", "d"]);
+        assert_eq!(page(4, 2), vec!["e"]);
+        assert!(page(7, 2).is_empty());
+        assert!(page(1, 0).is_empty());
+    }
+
+    #[test]
+    fn skip_footer() {
+        let (_test_dir, test_file_path) = setup(b"header\na\t1\nb\t2\n\ntotal\t3\n");
+        let first_fields = |tabfile: Tabfile| -> Vec<String> {
+            tabfile
+                .into_iter()
+                .map(|record| record.unwrap()[0].to_string())
+                .collect()
+        };
+        let tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        assert_eq!(first_fields(tabfile.skip_footer(2)), vec!["a", "b"]);
+        let tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        assert_eq!(first_fields(tabfile.skip_footer(1)), vec!["a", "b"]);
+        let tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        assert_eq!(first_fields(tabfile.skip_footer(4)), Vec::<String>::new());
+        let tabfile = Tabfile::open(&test_file_path).unwrap();
+        assert_eq!(first_fields(tabfile.skip_footer(9)), Vec::<String>::new());
+
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_footer(1)
+            .into_iter();
+        assert_eq!(rows.by_ref().count(), 3);
+        assert_eq!(rows.byte_offset(), 16);
+        rows.rewind().unwrap();
+        assert_eq!(rows.count(), 3);
+
+        let kinds: Vec<LineKind> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_footer(2)
+            .physical_lines()
+            .map(|line| line.unwrap().kind())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LineKind::Data,
+                LineKind::Data,
+                LineKind::Data,
+                LineKind::Skipped,
+                LineKind::Skipped
+            ]
+        );
+    }
+
+    #[test]
+    fn recode_column() {
+        let (_test_dir, test_file_path) =
+            setup(b"status\tid\tsex\n1\ta\tm\n0\tb\tf\n2\tc\n10\td\tf\n");
+        let records: Vec<HeaderedRecord> = HeaderedTabfile::open(&test_file_path)
+            .unwrap()
+            .recode_column("status", &[("0", "inactive"), ("1", "active")])
+            .recode_column("sex", &[("m", "male")])
+            .recode_column("sex", &[("f", "female")])
+            .recode_column("unknown", &[("a", "b")])
+            .map(|record| record.unwrap())
+            .collect();
+        let fields: Vec<Vec<&str>> = records.iter().map(|record| record.fields()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                vec!["active", "a", "male"],
+                vec!["inactive", "b", "female"],
+                vec!["2", "c"],
+                vec!["10", "d", "female"],
+            ]
+        );
+        assert_eq!(records[0].line(), "active\ta\tmale\n");
+        assert_eq!(&records[1]["sex"], "female");
+    }
+
+    #[test]
+    fn skip_lines_while() {
+        let (_test_dir, test_file_path) =
+            setup(b"junk\nReport\n\n# generated\nID\tvalue\r\nReport\t1\n");
+        let tabfile = || {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .skip_lines(1)
+                .skip_lines_while(|line| !line.starts_with("ID\t"))
+        };
+        let records: Vec<Record> = tabfile().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].line_number(), &records[0][1]), (5, "value"));
+        assert_eq!(&records[1][0], "Report");
+
+        let kinds: Vec<LineKind> = tabfile()
+            .physical_lines()
+            .map(|line| line.unwrap().kind())
+            .collect();
+        assert_eq!(kinds[..4], [LineKind::Skipped; 4]);
+        assert_eq!(kinds[4..], [LineKind::Data; 2]);
+
+        let mut tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines_while(|line| !line.is_empty());
+        assert_eq!(tabfile.read_metadata().unwrap(), &["# generated"]);
+        let lines: Vec<usize> = tabfile
+            .into_iter()
+            .map(|record| record.unwrap().line_number())
+            .collect();
+        assert_eq!(lines, vec![5, 6]);
+    }
+
+    #[test]
+    fn pseudonymize() {
+        let (test_dir, test_file_path) =
+            setup(b"id\tname\tvalue\n# comment\n17\tbob\t1\r\n18\talice\t2\n19\tbob\n20\n");
+        let mut pseudonymizer = Pseudonymizer::new("ID-").header(true);
+        let mut output = Vec::new();
+        let written = pseudonymizer
+            .pseudonymize(
+                Tabfile::open(&test_file_path).unwrap(),
+                &mut output,
+                &[1, 0],
+            )
+            .unwrap();
+        assert_eq!(written, 5);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id\tname\tvalue\nID-2\tID-1\t1\r\nID-4\tID-3\t2\nID-5\tID-1\nID-6\n"
+        );
+        let map_path = test_dir.path().join("map.tsv");
+        pseudonymizer.write_map(&map_path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&map_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let mut pseudonymizer = Pseudonymizer::load_map(&map_path, "ID-").unwrap();
+        assert_eq!(pseudonymizer.original("ID-3"), Some("alice"));
+        assert_eq!(pseudonymizer.original("ID-7"), None);
+        assert_eq!(pseudonymizer.original("ID-0"), None);
+        assert_eq!(pseudonymizer.token("bob"), "ID-1");
+        assert_eq!(pseudonymizer.token("carol"), "ID-7");
+        assert!(Pseudonymizer::load_map(&map_path, "P").is_err());
+    }
+
+    #[test]
+    fn on_ragged() {
+        let (_test_dir, test_file_path) = setup(b"a\tb\tc\nd\te\nf\tg\th\ti\n");
+        let rows = |policy| -> Vec<Result<Vec<String>, String>> {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .on_ragged(3, policy)
+                .into_iter()
+                .map(|record| {
+                    record
+                        .map(|record| record.iter().map(str::to_string).collect())
+                        .map_err(|e| e.to_string())
+                })
+                .collect()
+        };
+        let lengths = |policy| -> Vec<usize> {
+            rows(policy)
+                .into_iter()
+                .map(|row| row.unwrap().len())
+                .collect()
+        };
+        assert_eq!(lengths(RaggedPolicy::Keep), vec![3, 2, 4]);
+        assert_eq!(lengths(RaggedPolicy::Pad), vec![3, 3, 4]);
+        assert_eq!(lengths(RaggedPolicy::Truncate), vec![3, 2, 3]);
+        let rows = rows(RaggedPolicy::Error);
+        assert!(rows[0].is_ok());
+        assert_eq!(
+            rows[1].as_ref().unwrap_err(),
+            "[TF002] line 2: expected 3 fields but found 2"
+        );
+        assert_eq!(
+            rows[2].as_ref().unwrap_err(),
+            "[TF002] line 3: expected 3 fields but found 4"
+        );
+
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .columns(2)
+            .into_iter();
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "[TF002] line 1: expected 2 fields but found 3"
+        );
+        assert!(rows.next().unwrap().is_ok());
+
+        let parallel: Vec<bool> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .on_ragged(3, RaggedPolicy::Error)
+            .into_parallel_iter(2)
+            .map(|record| record.is_ok())
+            .collect();
+        assert_eq!(parallel, vec![true, false, false]);
+    }
+
+    #[test]
+    fn shared_tabfile() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let shared = SharedTabfile::new(&test_file_path, |tsv_file| tsv_file.skip_lines(1));
+        assert_eq!(shared.path(), test_file_path.as_path());
+        let handles: Vec<std::thread::JoinHandle<usize>> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.rows().unwrap().count())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 4);
+        }
+        let mut first = shared.rows().unwrap();
+        let mut second = shared.open().unwrap().into_iter();
+        first.next();
+        assert_eq!(
+            second.next().unwrap().unwrap().fields(),
+            vec!["foo", "bar", "baz", "quux"]
+        );
+        assert_eq!(first.count(), 3);
+    }
+
+    #[test]
+    fn raw_lines() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let mut lines = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .raw_lines();
+        assert_eq!(lines.next_line().unwrap(), Some("foo\tbar\tbaz\tquux"));
+        assert_eq!((lines.line_number(), lines.byte_offset()), (3, 29));
+        let rest: Vec<String> = lines.map(|line| line.unwrap()).collect();
+        assert_eq!(
+            rest,
+            vec![
+                "alpha\tbeta\tgamma\tdelta",
+                "Leonardo\tMichelangelo\tDonatello\tRaphael",
+                "red\tyellow\tgreen",
+            ]
+        );
+    }
+
+    #[test]
+    fn select_columns() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let rows: Vec<Vec<String>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .select_columns(&[3, 0])
+            .trim_fields(TrimMode::Both)
+            .into_iter()
+            .map(|record| {
+                let record = record.unwrap();
+                record.iter().map(str::to_string).collect()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["quux", "foo"],
+                vec!["delta", "alpha"],
+                vec!["Raphael", "Leonardo"],
+                vec!["red"],
+            ]
+        );
+
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .select_columns(&[1, 3])
+            .columns(2)
+            .into_iter();
+        let record = rows.nth(2).unwrap().unwrap();
+        assert_eq!(record.fields(), vec!["Michelangelo", "Raphael"]);
+        assert_eq!(
+            record.line(),
+            "Leonardo\tMichelangelo\tDonatello\tRaphael\n"
+        );
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "[TF002] line 8: expected 2 fields but found 1"
+        );
+    }
+
+    #[test]
+    fn open_smart() {
+        use std::io::ErrorKind;
+
+        let (test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let mut compressed_path = test_file_path.clone().into_os_string();
+        compressed_path.push(".zst");
+        let tsv_file = Tabfile::open_smart(&compressed_path).unwrap();
+        assert_eq!(tsv_file.into_iter().count(), 5);
+
+        std::fs::rename(&test_file_path, &compressed_path).unwrap();
+        let error = Tabfile::open_smart(&test_file_path).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        assert!(error.to_string().ends_with(".zst cannot be decompressed"));
+
+        let error = Tabfile::open_smart(test_dir.path().join("missing.tsv"))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn select_by_name() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        let tsv_file = HeaderedTabfile::new(tabfile)
+            .unwrap()
+            .recode_column("quux", &[("delta", "DELTA")])
+            .select(&["quux", "bar"])
+            .unwrap()
+            .recode_column("bar", &[("beta", "BETA")]);
+        assert_eq!(tsv_file.header().names(), &["quux", "bar"]);
+        let rows: Vec<Vec<String>> = tsv_file
+            .map(|record| record.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["DELTA", "BETA"],
+                vec!["Raphael", "Michelangelo"],
+                vec!["yellow"],
+            ]
+        );
+
+        let tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        let error = HeaderedTabfile::new(tabfile)
+            .unwrap()
+            .select(&["foo", "missing"])
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "[TF005] missing columns: missing");
+    }
+
+    #[test]
+    fn max_line_length() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let tabfile = Tabfile::open(&test_file_path).unwrap().max_line_length(30);
+        let results: Vec<Result<usize, String>> = tabfile
+            .into_iter()
+            .map(|record| {
+                record
+                    .map(|record| record.byte_offset() as usize)
+                    .map_err(|e| e.to_string())
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Ok(0),
+                Ok(12),
+                Ok(29),
+                Err("[TF007] line 6: longer than 30 bytes".to_string()),
+                Ok(111)
+            ]
+        );
+
+        let lines: Vec<_> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .max_line_length(30)
+            .skip_footer(2)
+            .physical_lines()
+            .map(|line| line.map(|line| line.kind()).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[5],
+            Err("[TF007] line 6: longer than 30 bytes".to_string())
+        );
+        assert_eq!(lines[7], Ok(LineKind::Skipped));
+    }
+
+    #[test]
+    fn error_codes() {
+        let (test_dir, test_file_path) = setup(b"");
+        let error = Tabfile::open(&test_file_path)
+            .unwrap()
+            .empty_policy(EmptyPolicy::Error)
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::EmptyInput));
+        assert_eq!(error.to_string(), "[TF006] the file has no records");
+
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let error = HeaderedTabfile::open(&test_file_path)
+            .unwrap()
+            .require_columns(&["chrom"])
+            .err()
+            .unwrap();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingColumns));
+        assert_eq!(ErrorCode::UnclosedQuote.as_str(), "TF001");
+        assert_eq!(ErrorCode::MissingColumns.as_str(), "TF005");
+        assert_eq!(ErrorCode::InvalidUtf8.as_str(), "TF017");
+
+        let error = Tabfile::open(test_dir.path().join("missing.tsv"))
+            .err()
+            .unwrap();
+        assert_eq!(ErrorCode::of(&error), None);
+    }
+
+    #[cfg(feature = "contract")]
+    #[test]
+    fn interop() {
+        let excel = r#"{"delimiter": ",", "quotechar": "\"", "escapechar": null,
+            "doublequote": true, "skipinitialspace": true, "lineterminator": "\r\n",
+            "quoting": 0, "strict": false}"#;
+        let dialect = Dialect::from_python_csv(excel).unwrap();
+        assert_eq!(
+            dialect.parse_line("a, b,#c\n", 1).fields(),
+            vec!["a", "b", "#c"]
+        );
+        let params = dialect.to_python_csv().unwrap();
+        assert!(params.contains(r#""skipinitialspace":true"#));
+        assert!(params.contains(r#""quoting":0"#));
+        assert!(params.contains(r#""quotechar":"\"""#));
+        let params = Dialect::new().to_python_csv().unwrap();
+        assert!(params.contains(r#""quoting":3"#));
+
+        let error = Dialect::from_python_csv(r#"{"delimiter": "::"}"#).unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::UnsupportedDialect));
+        let error = Dialect::from_python_csv(r#"{"delimeter": ";"}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "[TF015] unknown csv dialect parameter `delimeter`"
+        );
+        assert!(Dialect::new().separator_str("::").to_python_csv().is_err());
+
+        let schema = r#"{"fields": [
+            {"name": "day", "type": "date"},
+            {"name": "level", "type": "integer", "constraints": {"enum": [1, 2, 3]}}
+        ], "primaryKey": "day"}"#;
+        let contract = Contract::from_table_schema(schema).unwrap();
+        assert_eq!(
+            contract.to_table_schema(),
+            r#"{"fields":[{"name":"day","type":"string"},{"constraints":{"enum":["1","2","3"]},"name":"level","type":"integer"}]}"#
+        );
+        let (_test_dir, test_file_path) = setup(b"day\tlevel\n2024-01-01\t2\n2024-01-02\t4\n");
+        let report = Tabfile::open(&test_file_path)
+            .unwrap()
+            .check_contract_with(&contract)
+            .unwrap();
+        assert_eq!(report.violations().len(), 1);
+        assert_eq!(report.violations()[0].line_number(), 3);
+    }
+
+    #[test]
+    fn record_terminator() {
+        let (_test_dir, test_file_path) = setup(b"a,1;;# comment;b,2;c,3\n");
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .separator(',')
+            .record_terminator(b';')
+            .skip_lines(1);
+        let records: Vec<(usize, Vec<String>)> = tabfile
+            .into_iter()
+            .map(|record| {
+                let record = record.unwrap();
+                let fields = record.iter().map(str::to_string).collect();
+                (record.line_number(), fields)
+            })
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (4, vec!["b".into(), "2".into()]),
+                (5, vec!["c".into(), "3".into()])
+            ]
+        );
+
+        let lines: Vec<PhysicalLine> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .record_terminator(b';')
+            .physical_lines()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2].content(), "# comment");
+        assert_eq!(lines[2].kind(), LineKind::Comment);
+        assert_eq!(lines[1].kind(), LineKind::Empty);
+        assert_eq!(lines[4].terminator(), "\n");
+
+        let mut parser = parse::Parser::new(Dialect::new().record_terminator(b'\0'));
+        let records = parser.feed(b"x\ty\0z");
+        assert_eq!(records[0].as_ref().unwrap().fields(), vec!["x", "y"]);
+        assert_eq!(parser.finish()[0].as_ref().unwrap().fields(), vec!["z"]);
+    }
+
+    #[test]
+    fn line_endings() {
+        let (_test_dir, test_file_path) = setup(b"a\r\n# comment\nb\r\nc\nd");
+        let endings = |tabfile: Tabfile| -> Vec<Result<(LineEnding, String), String>> {
+            tabfile
+                .into_iter()
+                .map(|record| {
+                    record
+                        .map(|record| (record.terminator(), record.line().to_string()))
+                        .map_err(|e| e.to_string())
+                })
+                .collect()
+        };
+        let tabfile = Tabfile::open(&test_file_path).unwrap();
+        let kept: Vec<LineEnding> = endings(tabfile)
+            .into_iter()
+            .map(|ending| ending.unwrap().0)
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                LineEnding::CrLf,
+                LineEnding::CrLf,
+                LineEnding::Lf,
+                LineEnding::None
+            ]
+        );
+
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .line_endings(LineEndingPolicy::Normalize);
+        let lines: Vec<String> = endings(tabfile)
+            .into_iter()
+            .map(|ending| ending.unwrap().1)
+            .collect();
+        assert_eq!(lines, vec!["a\n", "b\n", "c\n", "d"]);
+
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .line_endings(LineEndingPolicy::Error);
+        let results = endings(tabfile);
+        assert!(results[1].is_ok() && results[3].is_ok());
+        assert_eq!(
+            results[2],
+            Err("[TF016] line 4: LF line ending, but line 1 ends with CRLF".to_string())
+        );
+
+        let (_test_dir, test_file_path) = setup(b"a\rb\r");
+        let tabfile = Tabfile::open(&test_file_path)
+            .unwrap()
+            .record_terminator(b'\r');
+        assert_eq!(endings(tabfile)[1], Ok((LineEnding::Cr, "b\r".to_string())));
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let (_test_dir, test_file_path) =
+            setup(b"id,text\n1,\"a, b\"\n2,\"first\nsecond\",x\n3,\"say \"\"hi\"\"\"\n4,\"open\n");
+        let results: Vec<Result<(usize, String), String>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .separator(',')
+            .quote_character('"')
+            .into_iter()
+            .map(|record| {
+                record
+                    .map(|record| (record.line_number(), record.fields().join("|")))
+                    .map_err(|e| e.to_string())
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Ok((1, "id|text".to_string())),
+                Ok((2, "1|a, b".to_string())),
+                Ok((3, "2|first\nsecond|x".to_string())),
+                Ok((5, "3|say \"hi\"".to_string())),
+                Err("[TF001] line 6: unclosed quote".to_string()),
+            ]
+        );
+
+        let kinds: Vec<LineKind> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .separator(',')
+            .quote_character('"')
+            .physical_lines()
+            .map(|line| line.unwrap().kind())
+            .collect();
+        assert_eq!(
+            kinds[2..5],
+            [LineKind::Data, LineKind::Continuation, LineKind::Data]
+        );
+
+        let mut parser = parse::Parser::new(Dialect::new().quote_character('\''));
+        assert!(parser.feed(b"a\t'b\n").is_empty());
+        let records = parser.feed(b"c'\td\ne\n");
+        assert_eq!(
+            records[0].as_ref().unwrap().fields(),
+            vec!["a", "b\nc", "d"]
+        );
+        assert_eq!(records[1].as_ref().unwrap().line_number(), 3);
+        assert!(parser.feed(b"'f").is_empty());
+        let records = parser.finish();
+        let error = records[0].as_ref().unwrap_err();
+        assert_eq!(ErrorCode::of(error), Some(ErrorCode::UnclosedQuote));
+    }
+
+    #[test]
+    fn unescape_fields() {
+        let dialect = Dialect::new().unescape(EscapeStyle::Backslash);
+        let record = dialect.parse_line("a\\tb\t\\N\t\\x\\\\\tend\\\n", 1);
+        assert_eq!(record.fields(), vec!["a\tb", "\\N", "x\\", "end\\"]);
+        assert_eq!(record.line(), "a\tb\t\\N\tx\\\tend\\\n");
+        let record = dialect.parse_line("plain\tline", 2);
+        assert_eq!(record.fields(), vec!["plain", "line"]);
+
+        let quoted = dialect.quote_character('"').separator(',');
+        let record = quoted.parse_line("\"a,b\\tc\",d\\ne", 1);
+        assert_eq!(record.fields(), vec!["a,b\tc", "d\ne"]);
+    }
+
+    #[test]
+    fn excel_text() {
+        let dialect = Dialect::new()
+            .separator(',')
+            .unwrap_excel_text(true)
+            .trim_fields(TrimMode::Both);
+        let record = dialect.parse_line("=\" 0012 \",=\",=\"\",\"x\"", 1);
+        assert_eq!(record.fields(), vec!["0012", "=\"", "", "\"x\""]);
+        // Excel quotes the wrapper in CSV files
+        let record = dialect
+            .quote_character('"')
+            .parse_line("\"=\"\"0012\"\"\",7\n", 1);
+        assert_eq!(record.fields(), vec!["0012", "7"]);
+    }
+
+    #[test]
+    fn percent_decoding() {
+        let record = Dialect::new().parse_line("a%20b\t%E2%82%AC\t%zz%4\t%FF\t%25%2541", 1);
+        let decoded: Vec<String> = (0..record.len())
+            .map(|i| record.field_decoded(i).unwrap().into_owned())
+            .collect();
+        assert_eq!(decoded, vec!["a b", "€", "%zz%4", "%FF", "%%41"]);
+        assert!(matches!(record.field_decoded(2), Some(Cow::Borrowed(_))));
+    }
+
+    #[cfg(feature = "bio")]
+    #[test]
+    fn gff_attributes() {
+        let (_test_dir, test_file_path) = setup(
+            b"##gff-version 3\n\
+              chr1\t.\tgene\t1\t9\t.\t+\t.\tID=g1;Name=A%2CB;Parent=x,y\n\
+              chr1\t.\texon\t1\t9\t.\t+\t.\tgene_id \"g1\"; exon_number 2;\n\
+              chr1\t.\tgene\t1\t9\t.\t+\t.\t.\n",
+        );
+        let records: Vec<Record> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.unwrap())
+            .collect();
+        let attributes = records[0].attributes().unwrap();
+        let pairs: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(key, value)| (*key, value.as_ref()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("ID", "g1"), ("Name", "A,B"), ("Parent", "x,y")]
+        );
+        let attributes = records[1].attributes().unwrap();
+        assert_eq!(attributes.get("gene_id"), Some("g1"));
+        assert_eq!(attributes.get("exon_number"), Some("2"));
+        assert!(records[2].attributes().unwrap().is_empty());
+        assert_eq!(Dialect::new().parse_line("chr1\t.", 1).attributes(), None);
+    }
+
+    #[test]
+    fn vcf() {
+        let (_test_dir, test_file_path) = setup(
+            b"##fileformat=VCFv4.2\n\
+              ##INFO=<ID=DP,Number=1,Type=Integer>\n\
+              #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tNA001\n\
+              chr1\t1234\t.\tA\tG\t50\tPASS\tDP=7\tGT\t0/1\n",
+        );
+        let mut vcf_file = HeaderedTabfile::vcf(&test_file_path).unwrap();
+        assert_eq!(vcf_file.header().names()[0], "CHROM");
+        assert_eq!(vcf_file.header().position("NA001"), Some(9));
+        assert_eq!(vcf_file.leading_comments().len(), 2);
+        let record = vcf_file.next().unwrap().unwrap();
+        assert_eq!(record.line_number(), 4);
+        assert_eq!(record.get("INFO"), Some("DP=7"));
+        assert!(vcf_file.next().is_none());
+    }
+
+    #[cfg(feature = "bio")]
+    #[test]
+    fn bed_records() {
+        let (_test_dir, test_file_path) = setup(
+            b"browser position chr1:1-500\n\
+              track name=test\n\
+              chr1\t0\t100\n\
+              chr1\t99\t99\tinsertion\n\
+              chr2\t5\tx\n\
+              chr2\t50\t10\n\
+              chr3\t1\n",
+        );
+        let results: Vec<Result<BedRecord, String>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .bed_records()
+            .map(|record| record.map_err(|e| e.to_string()))
+            .collect();
+        let first = results[0].as_ref().unwrap();
+        let second = results[1].as_ref().unwrap();
+        assert_eq!(first.line_number(), 3);
+        assert!(second.is_empty() && second.rest() == vec!["insertion"]);
+        assert!(!first.overlaps(second));
+        assert!(first.contains(99) && !first.contains(100));
+        assert_eq!(
+            results[2].as_ref().unwrap_err(),
+            "[TF004] line 5: invalid end `x`: invalid digit found in string"
+        );
+        assert_eq!(
+            results[3].as_ref().unwrap_err(),
+            "[TF004] line 6: start 50 is after end 10"
+        );
+        assert_eq!(
+            results[4].as_ref().unwrap_err(),
+            "[TF003] line 7: expected at least 3 BED fields but found 2"
+        );
+    }
+
+    #[test]
+    fn sam_header() {
+        let (_test_dir, test_file_path) = setup(
+            b"@HD\tVN:1.6\n\
+              @CO\tno alignments yet\n\
+              #r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tFFFF\n",
+        );
+        let sam_file = Tabfile::sam(&test_file_path).unwrap();
+        assert_eq!(
+            sam_file.metadata(),
+            &["@HD\tVN:1.6", "@CO\tno alignments yet"]
+        );
+        let records = sam_file.collect_records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].line_number(), &records[0][0]), (3, "#r1"));
+    }
+
+    #[cfg(feature = "tabix")]
+    fn bgzf_block(data: &[u8]) -> Vec<u8> {
+        use flate2::write::DeflateEncoder;
+        use flate2::{Compression, Crc};
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let payload = encoder.finish().unwrap();
+        let mut crc = Crc::new();
+        crc.update(data);
+        let block_size = (payload.len() + 25) as u16;
+        let mut block = vec![31, 139, 8, 4, 0, 0, 0, 0, 0, 255, 6, 0, b'B', b'C', 2, 0];
+        block.extend_from_slice(&block_size.to_le_bytes());
+        block.extend_from_slice(&payload);
+        block.extend_from_slice(&crc.sum().to_le_bytes());
+        block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        block
+    }
+
+    #[cfg(feature = "tabix")]
+    #[test]
+    fn tabix_query() {
+        let header = b"#chrom\tstart\tend\n";
+        let chr1 =
+            b"#chrom\tstart\tend\nchr1\t100\t200\ta\nchr1\t150\t300\tb\nchr1\t1000\t1100\tc\n";
+        let chr2 = b"chr2\t5\t10\td\n";
+        let first = bgzf_block(chr1);
+        let second = bgzf_block(chr2);
+        let chr2_offset = (first.len() as u64) << 16;
+        let end_offset = ((first.len() + second.len()) as u64) << 16;
+        let mut data = [first, second, bgzf_block(b"")].concat();
+
+        // BED-like positions in columns 1 to 3, `#` as meta character
+        let mut columns = Vec::new();
+        for value in &[0x10000, 1, 2, 3, i32::from(b'#'), 0, 10] {
+            columns.extend_from_slice(&i32::to_le_bytes(*value));
+        }
+        columns.extend_from_slice(b"chr1\0chr2\0");
+        let chunks = [
+            (header.len() as u64, chr2_offset),
+            (chr2_offset, end_offset),
+        ];
+        let references = |is_csi: bool| {
+            let mut bytes = Vec::new();
+            for (begin, end) in &chunks {
+                bytes.extend_from_slice(&1i32.to_le_bytes());
+                bytes.extend_from_slice(&4681u32.to_le_bytes());
+                if is_csi {
+                    bytes.extend_from_slice(&begin.to_le_bytes());
+                }
+                bytes.extend_from_slice(&1i32.to_le_bytes());
+                bytes.extend_from_slice(&begin.to_le_bytes());
+                bytes.extend_from_slice(&end.to_le_bytes());
+                if !is_csi {
+                    bytes.extend_from_slice(&1i32.to_le_bytes());
+                    bytes.extend_from_slice(&begin.to_le_bytes());
+                }
+            }
+            bytes
+        };
+        let tbi = [
+            b"TBI\x01".to_vec(),
+            2i32.to_le_bytes().to_vec(),
+            columns.clone(),
+            references(false),
+        ]
+        .concat();
+        let csi = [
+            b"CSI\x01".to_vec(),
+            14i32.to_le_bytes().to_vec(),
+            5i32.to_le_bytes().to_vec(),
+            (columns.len() as i32).to_le_bytes().to_vec(),
+            columns,
+            2i32.to_le_bytes().to_vec(),
+            references(true),
+        ]
+        .concat();
+
+        let test_dir = tempdir().unwrap();
+        let path = test_dir.path().join("intervals.bed.gz");
+        File::create(&path).unwrap().write_all(&data).unwrap();
+        let tbi_path = test_dir.path().join("intervals.bed.gz.tbi");
+        let csi_path = test_dir.path().join("intervals.csi");
+        File::create(&tbi_path)
+            .unwrap()
+            .write_all(&[bgzf_block(&tbi), bgzf_block(b"")].concat())
+            .unwrap();
+        File::create(&csi_path)
+            .unwrap()
+            .write_all(&bgzf_block(&csi))
+            .unwrap();
+
+        let names = |records: RegionRecords<'_>| -> Vec<String> {
+            records
+                .map(|record| record.unwrap()[3].to_string())
+                .collect()
+        };
+        let mut indexed = IndexedTabfile::open(&path).unwrap();
+        assert_eq!(indexed.sequences(), &["chr1", "chr2"]);
+        assert_eq!(names(indexed.query("chr1:201-1,000").unwrap()), vec!["b"]);
+        assert_eq!(
+            names(indexed.query("chr1:200").unwrap()),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(names(indexed.query("chr2").unwrap()), vec!["d"]);
+        assert!(names(indexed.query("chr3:1-10").unwrap()).is_empty());
+        let error = indexed.query("chr1:20-10").err().unwrap();
+        assert_eq!(error.to_string(), "[TF018] invalid region `chr1:20-10`");
+        let record = indexed.query("chr2:10").unwrap().next().unwrap().unwrap();
+        assert_eq!(record.byte_offset(), chr2_offset);
+
+        let mut indexed = IndexedTabfile::with_index(&path, &csi_path).unwrap();
+        assert_eq!(names(indexed.query("chr1:1-150").unwrap()), vec!["a"]);
+
+        data.truncate((chr2_offset >> 16) as usize + 10);
+        File::create(&path).unwrap().write_all(&data).unwrap();
+        let mut indexed = IndexedTabfile::open(&path).unwrap();
+        let error = indexed.query("chr2").unwrap().next().unwrap().unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::InvalidIndex));
+    }
+
+    #[test]
+    fn record_index() {
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let mut index = Tabfile::open(&test_file_path)
+            .unwrap()
+            .skip_lines(1)
+            .build_index()
+            .unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.line_number(2), Some(6));
+        assert_eq!(index.byte_offset(4), None);
+        let last = index.record_at(3).unwrap().unwrap();
+        assert_eq!((last.line_number(), &last[2]), (8, "green"));
+        let first = index.record_at(0).unwrap().unwrap();
+        assert_eq!(first.byte_offset(), index.byte_offset(0).unwrap());
+        assert_eq!(&first[0], "foo");
+        let lines: Vec<usize> = index
+            .records_in(1..3)
+            .unwrap()
+            .map(|record| record.unwrap().line_number())
+            .collect();
+        assert_eq!(lines, vec![4, 6]);
+        assert!(index.record_at(4).unwrap().is_none());
+        assert_eq!(index.records_in(3..10).unwrap().count(), 1);
+
+        let (_test_dir, test_file_path) = setup(b"1,\"a\nb\"\n2,c\n");
+        let mut index = Tabfile::open(&test_file_path)
+            .unwrap()
+            .separator(',')
+            .quote_character('"')
+            .build_index()
+            .unwrap();
+        assert_eq!(index.line_number(1), Some(3));
+        assert_eq!(&index.record_at(0).unwrap().unwrap()[1], "a\nb");
+    }
+
+    #[test]
+    fn binary_search() {
+        let mut content = b"generated by\n# comment\n".to_vec();
+        for i in 0..300 {
+            let key = format!("k{:03}", i / 3);
+            content.extend_from_slice(format!("{}\t{}\n", key, i).as_bytes());
+            if i % 50 == 0 {
+                content.extend_from_slice(b"\n# sorted\n");
+            }
+        }
+        let (_test_dir, test_file_path) = setup(&content);
+        let mut tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        for i in 0..100 {
+            let records = tabfile.binary_search(0, &format!("k{:03}", i)).unwrap();
+            let values: Vec<&str> = records.iter().map(|record| &record[1]).collect();
+            let expected: Vec<String> = (i * 3..i * 3 + 3).map(|v| v.to_string()).collect();
+            assert_eq!(values, expected);
+        }
+        assert!(tabfile.binary_search(0, "a").unwrap().is_empty());
+        assert!(tabfile.binary_search(0, "k0005").unwrap().is_empty());
+        assert!(tabfile.binary_search(0, "z").unwrap().is_empty());
+        let error = tabfile.binary_search(2, "k001").unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+        assert_eq!(tabfile.into_iter().count(), 300);
+    }
+
+    #[test]
+    fn index_by() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\nb\t2\na\t3\n");
+        let index = |policy| {
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .index_by(0, policy)
+                .map(|map| map["a"][1].to_string())
+                .map_err(|e| e.to_string())
+        };
+        assert_eq!(index(DuplicatePolicy::First), Ok("1".to_string()));
+        assert_eq!(index(DuplicatePolicy::Last), Ok("3".to_string()));
+        assert_eq!(
+            index(DuplicatePolicy::Error),
+            Err("[TF020] line 3: the key `a` already occurs in line 1".to_string())
+        );
+        let groups = Tabfile::open(&test_file_path)
+            .unwrap()
+            .group_by_key(0)
+            .unwrap();
+        let lines: Vec<usize> = groups["a"].iter().map(Record::line_number).collect();
+        assert_eq!((lines, groups["b"].len()), (vec![1, 3], 1));
+        let error = Tabfile::open(&test_file_path)
+            .unwrap()
+            .index_by(2, DuplicatePolicy::First)
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+    }
+
+    #[test]
+    fn joins() {
+        let (_left_dir, left_path) = setup(b"a\t1\nb\t2\nb\t3\nd\t4\n");
+        let (_right_dir, right_path) = setup(b"a\tx\nb\ty\nb\tz\nc\tw\n");
+        let summarize = |pairs: Vec<(Record, Option<Record>)>| -> Vec<String> {
+            pairs
+                .iter()
+                .map(|(left, right)| {
+                    let right = right.as_ref().map_or("-", |right| &right[1]);
+                    format!("{}{}", &left[1], right)
+                })
+                .collect()
+        };
+        let open = |path: &PathBuf| Tabfile::open(path).unwrap();
+        let expected_inner = vec!["1x", "2y", "2z", "3y", "3z"];
+        for kind in [JoinKind::Inner, JoinKind::Left] {
+            let mut expected = expected_inner.clone();
+            if kind == JoinKind::Left {
+                expected.push("4-");
+            }
+            let hashed = hash_join(open(&left_path), 0, open(&right_path), 0, kind)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(summarize(hashed), expected);
+            let merged = merge_join(open(&left_path), 0, open(&right_path), 0, kind)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(summarize(merged), expected);
+        }
+        let (_unsorted_dir, unsorted_path) = setup(b"b\t1\na\t2\n");
+        let error = merge_join(
+            open(&unsorted_path),
+            0,
+            open(&right_path),
+            0,
+            JoinKind::Left,
+        )
+        .find_map(Result::err)
+        .unwrap();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::UnsortedInput));
+        assert!(error.to_string().contains("line 2 of the left file"));
+        let error = hash_join(open(&left_path), 2, open(&right_path), 0, JoinKind::Left)
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+    }
+
+    #[test]
+    fn diff_files() {
+        let (_old_dir, old_path) = setup(b"a\t1\nb\t2\nc\t3\n");
+        let (_new_dir, new_path) = setup(b"a\t1\"\nb\t9\"\n");
+        let open = |path: &PathBuf| Tabfile::open(path).unwrap();
+        let differences = diff(open(&old_path), open(&new_path), &DiffOptions::new()).unwrap();
+        assert_eq!(differences.len(), 3);
+        match &differences[1] {
+            Difference::Changed { old, new, columns } => {
+                assert_eq!((&old[1], &new[1], columns), ("2", "9\"", &vec![1]));
+            }
+            other => panic!("unexpected difference {}", other),
+        }
+        assert_eq!(differences[2].to_string(), "removed line 3");
+        let ignoring = DiffOptions::new().ignore_columns(&[1]);
+        let differences = diff(open(&new_path), open(&old_path), &ignoring).unwrap();
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].to_string(), "added line 3");
+        let (_twice_dir, twice_path) = setup(b"a\t1\na\t2\n");
+        let keyed = DiffOptions::new().key_column(Some(0));
+        for (a, b) in [(&twice_path, &old_path), (&old_path, &twice_path)] {
+            let error = diff(open(a), open(b), &keyed).unwrap_err();
+            assert_eq!(ErrorCode::of(&error), Some(ErrorCode::DuplicateKey));
+        }
+    }
+
+    #[test]
+    fn open_many() {
+        let (_first_dir, first_path) = setup(b"# part 0\nname\tvalue\na\t1\n");
+        let (_second_dir, second_path) = setup(b"# part 1\nname\tvalue\nb\t2\n");
+        let (_empty_dir, empty_path) = setup(b"");
+        let paths = [&empty_path, &first_path, &empty_path, &second_path];
+        let mut records = Tabfile::open_many(paths)
+            .unwrap()
+            .configure(|tsv_file| tsv_file.comment_character('#'))
+            .skip_headers(true);
+        let mut seen = Vec::new();
+        while let Some(record) = records.next() {
+            let record = record.unwrap();
+            let path = records.current_path().unwrap().to_path_buf();
+            seen.push((record[0].to_string(), record.line_number(), path));
+        }
+        assert_eq!(
+            seen,
+            vec![
+                ("name".to_string(), 2, first_path.clone()),
+                ("a".to_string(), 3, first_path.clone()),
+                ("b".to_string(), 3, second_path.clone()),
+            ]
+        );
+        assert!(records.next().is_none());
+        let missing = first_path.with_file_name("missing.tsv");
+        let error = Tabfile::open_many([&first_path, &missing]).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+
+        let (_broken_dir, broken_path) = setup(b"\xff\nc\t3\n");
+        let items: Vec<Result<String, std::io::ErrorKind>> =
+            Tabfile::open_many([&first_path, &broken_path])
+                .unwrap()
+                .skip_headers(true)
+                .map(|record| record.map(|r| r[0].to_string()).map_err(|e| e.kind()))
+                .collect();
+        assert_eq!(
+            items,
+            vec![
+                Ok("name".to_string()),
+                Ok("a".to_string()),
+                Err(std::io::ErrorKind::InvalidData),
+                Ok("c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_by_key() {
+        let (test_dir, test_file_path) = setup(b"a\t1\nb\t2\na\t3\nc\t4\nb\t5");
+        let options = SplitOptions::new(test_dir.path())
+            .prefix("part-")
+            .extension("")
+            .max_open_files(1);
+        let counts = split_by(Tabfile::open(&test_file_path).unwrap(), 0, &options).unwrap();
+        assert_eq!((counts["a"], counts["b"], counts["c"]), (2, 2, 1));
+        let contents = |key: &str| std::fs::read_to_string(test_dir.path().join(key)).unwrap();
+        assert_eq!(contents("part-a"), "a\t1\na\t3\n");
+        assert_eq!(contents("part-b"), "b\t2\nb\t5\n");
+        let limited = options.clone().max_files(Some(2));
+        let error = split_by(Tabfile::open(&test_file_path).unwrap(), 0, &limited).unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::TooManyFiles));
+        assert!(error.to_string().starts_with("[TF023] line 4:"));
+        let (bad_dir, bad_path) = setup(b"ok\t1\n../escape\t2\n");
+        let options = SplitOptions::new(bad_dir.path());
+        let error = split_by(Tabfile::open(&bad_path).unwrap(), 0, &options).unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::InvalidFileName));
+    }
+
+    #[test]
+    fn group_by_column() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\na\t2\nNA\t3\nb\t4\na\t5\nc\n");
+        let mut groups = Tabfile::open(&test_file_path)
+            .unwrap()
+            .null_tokens(&["NA"])
+            .group_by_column(0);
+        let mut summary = Vec::new();
+        for group in groups.by_ref().take(4) {
+            let (key, records) = group.unwrap();
+            let lines: Vec<usize> = records.iter().map(Record::line_number).collect();
+            summary.push((key, lines));
+        }
+        assert_eq!(
+            summary,
+            vec![
+                ("a".to_string(), vec![1, 2]),
+                ("NA".to_string(), vec![3]),
+                ("b".to_string(), vec![4]),
+                ("a".to_string(), vec![5]),
+            ]
+        );
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("c", 1));
+        assert!(groups.next().is_none());
+        let error = Tabfile::open(&test_file_path)
+            .unwrap()
+            .group_by_column(1)
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+    }
+
+    #[test]
+    fn group_by_column_keeps_records_before_error() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\na\t2\nb\na\t3\n");
+        let mut groups = Tabfile::open(&test_file_path).unwrap().group_by_column(0);
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("a", 2));
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("b", 1));
+        let mut groups = Tabfile::open(&test_file_path).unwrap().group_by_column(1);
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("1", 1));
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("2", 1));
+        let error = groups.next().unwrap().unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::MissingField));
+        let (key, records) = groups.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), records.len()), ("3", 1));
+        assert!(groups.next().is_none());
+    }
+
+    #[test]
+    fn chunks() {
+        let (_test_dir, test_file_path) = setup(b"a\nb\nc\n\xff\nd\n");
+        let items: Vec<Result<Vec<String>, std::io::ErrorKind>> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .chunks(2)
+            .map(|chunk| {
+                chunk
+                    .map(|records| records.iter().map(|r| r[0].to_string()).collect())
+                    .map_err(|e| e.kind())
+            })
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                Ok(vec!["a".to_string(), "b".to_string()]),
+                Ok(vec!["c".to_string()]),
+                Err(std::io::ErrorKind::InvalidData),
+                Ok(vec!["d".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn partitions() {
+        let mut contents = b"skipped\n".to_vec();
+        for i in 0..100 {
+            contents.extend(format!("{}\t{}\n", i, "x".repeat(i % 7)).bytes());
+        }
+        contents.extend(b"# comment\nlast");
+        let (_test_dir, test_file_path) = setup(&contents);
+        let mut tabfile = Tabfile::open(&test_file_path).unwrap().skip_lines(1);
+        for count in [1, 3, 8, 5000] {
+            let partitions = tabfile.partitions(count).unwrap();
+            assert!(partitions.len() <= count && !partitions.is_empty());
+            assert_eq!(partitions[0].start(), 8);
+            assert_eq!(partitions.last().unwrap().end(), contents.len() as u64);
+            let mut keys = Vec::new();
+            for (i, partition) in partitions.iter().enumerate() {
+                assert!(!partition.is_empty());
+                if i > 0 {
+                    assert_eq!(partition.start(), partitions[i - 1].end());
+                    assert_eq!(contents[partition.start() as usize - 1], b'\n');
+                }
+                for record in partition.open().unwrap() {
+                    keys.push(record.unwrap()[0].to_string());
+                }
+            }
+            let expected: Vec<String> = (0..100)
+                .map(|i| i.to_string())
+                .chain(["last".to_string()])
+                .collect();
+            assert_eq!(keys, expected);
+        }
+        let first = tabfile.into_iter().next().unwrap().unwrap();
+        assert_eq!((&first[0], first.line_number()), ("0", 2));
+    }
+
+    #[test]
+    fn limit() {
+        let (_test_dir, test_file_path) = setup(b"name\tvalue\n# note\na\t1\n\nb\t2\nc\t3\n");
+        let mut rows = Tabfile::open(&test_file_path).unwrap().limit(2).into_iter();
+        let lines: Vec<usize> = rows.by_ref().map(|r| r.unwrap().line_number()).collect();
+        assert_eq!(lines, vec![1, 3]);
+        assert!(rows.next().is_none());
+        let headered = HeaderedTabfile::new(Tabfile::open(&test_file_path).unwrap().limit(2));
+        let values: Vec<String> = headered
+            .unwrap()
+            .map(|r| r.unwrap()["value"].to_string())
+            .collect();
+        assert_eq!(values, vec!["1", "2"]);
+        assert_eq!(
+            Tabfile::open(&test_file_path)
+                .unwrap()
+                .limit(0)
+                .into_iter()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn progress() {
+        use std::sync::{Arc, Mutex};
+        let (_test_dir, test_file_path) = setup(b"a\t1\n# note\nb\t2\nc\t3\nd\t4\n");
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&reports);
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .on_progress(2, move |records, progress| {
+                collected.lock().unwrap().push((records, progress));
+            })
+            .into_iter();
+        assert_eq!(rows.progress(), 0.0);
+        assert_eq!(rows.by_ref().count(), 4);
+        assert_eq!(rows.progress(), 1.0);
+        assert_eq!(*reports.lock().unwrap(), vec![(2, 15.0 / 23.0), (4, 1.0)]);
+        let (_empty_dir, empty_path) = setup(b"");
+        assert_eq!(
+            Tabfile::open(empty_path).unwrap().into_iter().progress(),
+            1.0
+        );
+    }
+
+    #[cfg(feature = "indicatif")]
+    #[test]
+    fn progress_bar() {
+        let (_test_dir, test_file_path) = setup(b"a\t1\nb\t2\n# end\n");
+        let style = indicatif::ProgressStyle::default_bar();
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .with_progress_bar(style);
+        assert_eq!(rows.progress_bar().length(), Some(14));
+        rows.next().unwrap().unwrap();
+        assert_eq!(rows.progress_bar().position(), 4);
+        assert!(rows.by_ref().last().is_some());
+        assert!(rows.next().is_none());
+        assert_eq!(rows.progress_bar().position(), 14);
+        assert!(rows.progress_bar().is_finished());
+    }
+
+    #[test]
+    fn follow() {
+        use std::time::Duration;
+        let (_test_dir, test_file_path) = setup(b"a\t1\n");
+        let mut appender = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&test_file_path)
+            .unwrap();
+        let writer = std::thread::spawn(move || {
+            // the second chunk completes a character split by the first
+            for chunk in [&b"# comment\n\nb\t\xc3"[..], b"\xa92\n", b"c\t3\nd"] {
+                std::thread::sleep(Duration::from_millis(20));
+                appender.write_all(chunk).unwrap();
+            }
+        });
+        let mut rows = Tabfile::open(&test_file_path)
+            .unwrap()
+            .follow(true)
+            .skip_footer(1)
+            .poll_interval(Duration::from_millis(5))
+            .limit(3)
+            .into_iter();
+        let records: Vec<(String, usize)> = rows
+            .by_ref()
+            .map(|r| r.map(|r| (r.fields().join(":"), r.line_number())).unwrap())
+            .collect();
+        writer.join().unwrap();
+        let expected = [("a:1", 1), ("b:\u{e9}2", 4), ("c:3", 5)];
+        let expected: Vec<(String, usize)> =
+            expected.iter().map(|(f, n)| (f.to_string(), *n)).collect();
+        assert_eq!(records, expected);
+        assert_eq!(rows.byte_offset(), 25);
+    }
+
+    #[test]
+    fn reopen_on_rotation() {
+        use std::thread::sleep;
+        use std::time::Duration;
+        let (_test_dir, test_file_path) = setup(b"a\t1\n");
+        let path = test_file_path.clone();
+        let writer = std::thread::spawn(move || {
+            sleep(Duration::from_millis(30));
+            std::fs::rename(&path, path.with_extension("1")).unwrap();
+            std::fs::write(&path, b"# new file\nb\t2\n").unwrap();
+            sleep(Duration::from_millis(30));
+            let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+            file.set_len(0).unwrap();
+            sleep(Duration::from_millis(30));
+            std::fs::write(&path, b"c\t3\n").unwrap();
+        });
+        let items: Vec<String> = Tabfile::open(&test_file_path)
+            .unwrap()
+            .follow(true)
+            .reopen_on_rotation(true)
+            .poll_interval(Duration::from_millis(2))
+            .limit(3)
+            .into_iter()
+            .map(|item| match item {
+                Ok(record) => format!("{}@{}", &record[0], record.line_number()),
+                Err(e) => ErrorCode::of(&e).unwrap().as_str().to_string(),
+            })
+            .collect();
+        writer.join().unwrap();
+        assert_eq!(items, vec!["a@1", "TF024", "b@2", "TF024", "c@1"]);
+    }
+
+    #[test]
+    fn count_records_matches_iteration() {
+        let bytes = "# comment\nskipped\na\t1\n\n \t\nb\t\"2\n3\"\r\n\u{3000}\nc\t4\nfooter";
+        let (_dir, path) = setup(bytes.as_bytes());
+        let configure = |quote: bool| {
+            let tabfile = Tabfile::open(&path).unwrap().skip_lines(2).skip_footer(1);
+            if quote {
+                tabfile.quote_character('"')
+            } else {
+                tabfile
+            }
+        };
+        for quote in [false, true] {
+            let mut tabfile = configure(quote);
+            let count = tabfile.count_records().unwrap();
+            assert_eq!(count, if quote { 3 } else { 4 });
+            assert_eq!(tabfile.into_iter().count(), count);
+        }
+        let mut tabfile = configure(false).limit(2);
+        assert_eq!(tabfile.count_records().unwrap(), 2);
+        let mut tabfile = Tabfile::open(&path).unwrap().skip_empty_lines(false);
+        assert_eq!(tabfile.count_records().unwrap(), 9);
+        assert_eq!(tabfile.into_iter().count(), 9);
+    }
+
+    #[test]
+    fn estimated_remaining_records() {
+        let bytes: String = (0..100).map(|i| format!("{:03}\tvalue\n", i)).collect();
+        let (_dir, path) = setup(bytes.as_bytes());
+        let mut rows = Tabfile::open(&path).unwrap().into_iter();
+        assert_eq!(rows.size_hint(), (0, Some(bytes.len())));
+        assert_eq!(rows.estimated_remaining(), 0);
+        rows.next().unwrap().unwrap();
+        assert_eq!(rows.size_hint(), (0, Some(bytes.len() - 10)));
+        assert_eq!(rows.estimated_remaining(), 99);
+        let mut rows = Tabfile::open(&path).unwrap().limit(5).into_iter();
+        rows.next().unwrap().unwrap();
+        assert_eq!(rows.size_hint(), (0, Some(4)));
+        assert_eq!(rows.estimated_remaining(), 4);
+        let records: Vec<_> = rows.collect();
+        assert_eq!(records.len(), 4);
+    }
+
+    #[test]
+    fn size_hint_after_resuming_in_front_of_start_offset() {
+        let (_dir, path) = setup(b"1\tfoo\n2\tbar\n3\tbaz\n");
+        let cursor = "6:1:1".parse().unwrap();
+        let rows = Tabfile::open(&path)
+            .unwrap()
+            .seek_to(12)
+            .unwrap()
+            .resume_from(cursor)
+            .unwrap();
+        assert_eq!(rows.size_hint(), (0, Some(12)));
+        let records: Vec<_> = rows.map(|record| record.unwrap().line_number()).collect();
+        assert_eq!(records, vec![2, 3]);
+    }
+
+    #[test]
+    fn reverse_matches_forward_iteration() {
+        let long_field = "x".repeat(150_000);
+        let bytes = format!(
+            "header\na\t1\n# comment\n\nb\t{}\nc\t3\r\nfooter\ntruncated",
+            long_field
+        );
+        let (_dir, path) = setup(bytes.as_bytes());
+        let configure = || Tabfile::open(&path).unwrap().skip_lines(1).skip_footer(2);
+        let forward: Vec<_> = configure()
+            .into_iter()
+            .map(|record| {
+                let record = record.unwrap();
+                let line = record.line().to_string();
+                (record.byte_offset(), record.line_number(), line)
+            })
+            .collect();
+        let mut backward: Vec<_> = configure()
+            .reverse()
+            .unwrap()
+            .map(|record| {
+                let record = record.unwrap();
+                let line = record.line().to_string();
+                (record.byte_offset(), record.line_number(), line)
+            })
+            .collect();
+        backward.reverse();
+        assert_eq!(backward, forward);
+        assert_eq!(forward.len(), 3);
+        let in_order: Vec<_> = configure()
+            .reverse()
+            .unwrap()
+            .rev()
+            .map(|record| {
+                let record = record.unwrap();
+                let line = record.line().to_string();
+                (record.byte_offset(), record.line_number(), line)
+            })
+            .collect();
+        assert_eq!(in_order, forward);
+        let last = configure().last_records(1).unwrap();
+        assert_eq!(last[0].line(), "c\t3\r\n");
+        let too_long = Tabfile::open(&path).unwrap().max_line_length(1000);
+        let mut rows = too_long.reverse().unwrap();
+        let error = rows.nth(3).unwrap().unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::LineTooLong));
+        assert!(error.to_string().contains("line 5"));
+        assert_eq!(rows.next().unwrap().unwrap().line(), "a\t1\n");
+    }
+
+    #[test]
+    fn reverse_checks_like_forward() {
+        let (_dir, path) = setup(b"a\t1\nb\t2\t2\nc\t3\n\xff\t4\nd\t5\n");
+        let mut rows = Tabfile::open(&path).unwrap().reverse().unwrap();
+        assert_eq!(rows.next().unwrap().unwrap().line_number(), 5);
+        assert_eq!(rows.next_back().unwrap().unwrap().line(), "a\t1\n");
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::InvalidUtf8));
+        assert!(error.to_string().contains("line 4"));
+        assert_eq!(rows.next_back().unwrap().unwrap().line_number(), 2);
+        assert_eq!(rows.next().unwrap().unwrap().line(), "c\t3\n");
+        assert!(rows.next_back().is_none());
+        assert!(rows.next().is_none());
+        let checked = Tabfile::open(&path).unwrap().columns(2);
+        let errors: Vec<_> = checked
+            .reverse()
+            .unwrap()
+            .map(|record| record.err().and_then(|error| ErrorCode::of(&error)))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                None,
+                Some(ErrorCode::InvalidUtf8),
+                None,
+                Some(ErrorCode::ColumnCount),
+                None
+            ]
+        );
+        let limited = Tabfile::open(&path).unwrap().limit(2).reverse().unwrap();
+        let lines: Vec<_> = limited
+            .map(|record| record.unwrap().line_number())
+            .collect();
+        assert_eq!(lines, [2, 1]);
+    }
+
+    #[test]
+    fn resume_from_cursor() {
+        let (_dir, path) = setup(b"skipped\na\t1\n\"b\t2\nc\t3\nfooter\n");
+        let configure = || Tabfile::open(&path).unwrap().skip_lines(1).skip_footer(1);
+        let mut rows = configure().into_iter();
+        assert_eq!(rows.cursor(), "0:0:0".parse().unwrap());
+        rows.next().unwrap().unwrap();
+        let cursor = rows.cursor();
+        assert_eq!(cursor.to_string(), "12:2:1");
+        let rest: Vec<_> = rows.map(|record| record.unwrap().line_number()).collect();
+        let mut resumed = configure().resume_from(cursor).unwrap();
+        assert_eq!(resumed.next().unwrap().unwrap().line_number(), rest[0]);
+        assert_eq!(resumed.next().unwrap().unwrap().line(), "c\t3\n");
+        assert!(resumed.next().is_none());
+        assert_eq!(resumed.records_read(), 3);
+        let started = configure().resume_from(Cursor::default()).unwrap();
+        assert_eq!(started.count(), 3);
+        let misaligned: Cursor = "13:2:1".parse().unwrap();
+        let error = configure().resume_from(misaligned).err().unwrap();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::InvalidCursor));
+        for text in ["", "1:2", "1:2:3:4", "1:x:3"] {
+            let error = text.parse::<Cursor>().unwrap_err();
+            assert_eq!(ErrorCode::of(&error), Some(ErrorCode::InvalidCursor));
+        }
+    }
+
+    #[test]
+    fn set_field_and_to_line() {
+        let (_dir, path) = setup(b"id\tname\tscore\r\n1\t\"a \"\"b\"\"\"\t9\n2\tc\t10");
+        let mut lines = String::new();
+        for record in Tabfile::open(&path).unwrap().quote_character('"') {
+            let mut record = record.unwrap();
+            if record.line_number() > 1 {
+                let doubled = format!("{}\t{}", &record[1], &record[1]);
+                record.set_field(1, &doubled);
+                record.set_field(2, "");
+            }
+            lines.push_str(&record.to_line("\t"));
+        }
+        assert_eq!(
+            lines,
+            "id\tname\tscore\r\n1\t\"a \"\"b\"\"\ta \"\"b\"\"\"\t\n2\t\"c\tc\"\t"
+        );
+        let (_dir, path) = setup(lines.as_bytes());
+        let fields: Vec<Vec<String>> = Tabfile::open(&path)
+            .unwrap()
+            .quote_character('"')
+            .into_iter()
+            .map(|record| record.unwrap().iter().map(String::from).collect())
+            .collect();
+        assert_eq!(fields[1], ["1", "a \"b\"\ta \"b\"", ""]);
+        assert_eq!(fields[2], ["2", "c\tc", ""]);
+
+        let mut record = parse::Dialect::new().parse_line("a\tbb\tc\n", 1);
+        record.set_field(1, "");
+        record.set_field(2, "ccc");
+        assert_eq!((record.line(), &record[2]), ("a\t\tccc\n", "ccc"));
+
+        let dialect = parse::Dialect::new().select_columns(&[1, 0, 1, 2]);
+        let mut record = dialect.parse_line("a\tbb\tc\n", 1);
+        record.set_field(1, "aaa");
+        record.set_field(0, "b");
+        assert_eq!(record.fields(), vec!["b", "aaa", "b", "c"]);
+        assert_eq!(record.line(), "aaa\tb\tc\n");
+
+        let (_dir, path) = setup(b"a\t1;b\t2;c\t3");
+        let configure = |path| Tabfile::open(path).unwrap().record_terminator(b';');
+        let mut lines = String::new();
+        for record in configure(&path) {
+            let mut record = record.unwrap();
+            let doubled = format!("{};{}", &record[1], &record[1]);
+            record.set_field(1, &doubled);
+            lines.push_str(&record.to_line("\t"));
+        }
+        assert_eq!(lines, "a\t\"1;1\";b\t\"2;2\";c\t\"3;3\"");
+        let (_dir, path) = setup(lines.as_bytes());
+        let fields: Vec<String> = configure(&path)
+            .quote_character('"')
+            .into_iter()
+            .map(|record| record.unwrap()[1].to_string())
+            .collect();
+        assert_eq!(fields, ["1;1", "2;2", "3;3"]);
+    }
+
+    #[test]
+    fn record_from_fields() {
+        let record = Record::from_fields(Vec::<String>::new(), "\t");
+        assert!(record.is_empty());
+        assert_eq!(record.line(), "");
+        let mut record = Record::from_fields(["a,b", "", "c"], ",");
+        assert_eq!(record.fields(), vec!["a,b", "", "c"]);
+        assert_eq!(record.to_line(","), "\"a,b\",,c");
+        record.set_field(1, "filled");
+        assert_eq!(record.line(), "a,b,filled,c");
+        assert_eq!((record.line_number(), record.byte_offset()), (0, 0));
+    }
+
+    #[test]
+    fn write_rows() {
+        struct Count {
+            gene: &'static str,
+            count: Option<u32>,
+        }
+
+        impl IntoRecord for Count {
+            fn column_names() -> Vec<&'static str> {
+                vec!["gene", "count"]
+            }
+
+            fn fields(&self) -> Vec<String> {
+                let count = self
+                    .count
+                    .map_or_else(String::new, |count| count.to_string());
+                vec![self.gene.to_string(), count]
+            }
+        }
+
+        let (test_dir, _) = setup(b"");
+        let path = test_dir.path().join("counts.csv");
+        let mut writer = Tabwriter::create(&path)
+            .unwrap()
+            .separator(',')
+            .record_terminator(b';')
+            .header(true);
+        for (gene, count) in [("BRCA1", Some(5)), ("TP53", None)] {
+            writer.write_row(&Count { gene, count }).unwrap();
+        }
+        let record = Count {
+            gene: "EGFR",
+            count: Some(1),
+        }
+        .to_record("\t");
+        writer.write_record(&record).unwrap();
+        assert_eq!(writer.lines_written(), 4);
+        writer.into_inner().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "gene,count;BRCA1,5;TP53,;EGFR,1;"
+        );
+
+        let mut writer = Tabwriter::new(Vec::new()).header(true);
+        writer.write_fields(["# counts"]).unwrap();
+        writer
+            .write_row(&Count {
+                gene: "TP53",
+                count: Some(2),
+            })
+            .unwrap();
+        assert_eq!(writer.get_ref(), b"# counts\nTP53\t2\n");
+    }
+
+    #[test]
+    fn writer_quoting_round_trips() {
+        let rows: [&[&str]; 3] = [
+            &["1", "two\twords", "5\" tall"],
+            &["2", "first line\nsecond line", ""],
+            &["3", "C:\\temp", "\\N"],
+        ];
+        let read_back = |bytes: &[u8], quote: bool, escape: EscapeStyle| {
+            let (_dir, path) = setup(bytes);
+            let mut tabfile = Tabfile::open(&path).unwrap().unescape(escape);
+            if quote {
+                tabfile = tabfile.quote_character('"');
+            }
+            tabfile
+                .into_iter()
+                .map(|record| record.unwrap().iter().map(String::from).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        for (policy, escape) in [
+            (QuotePolicy::WhenNeeded, EscapeStyle::None),
+            (QuotePolicy::Always, EscapeStyle::None),
+            (QuotePolicy::Never, EscapeStyle::Backslash),
+        ] {
+            let mut writer = Tabwriter::new(Vec::new())
+                .quote_policy(policy)
+                .escape(escape);
+            for row in rows {
+                writer.write_fields(row).unwrap();
+            }
+            let output = writer.into_inner().unwrap();
+            let quote = policy != QuotePolicy::Never;
+            assert_eq!(read_back(&output, quote, escape), rows, "{:?}", policy);
+        }
+
+        let mut writer = Tabwriter::new(Vec::new()).separator(',');
+        writer.write_fields(["a", "b"]).unwrap();
+        let error = writer.write_fields(["c", "d,e"]).unwrap_err();
+        assert_eq!(ErrorCode::of(&error), Some(ErrorCode::UnwritableField));
+        assert_eq!(
+            error.to_string(),
+            "[TF026] line 2: field 1 contains the separator or a line break"
+        );
+        assert_eq!(writer.lines_written(), 1);
+        let mut writer = writer
+            .quote_policy(QuotePolicy::WhenNeeded)
+            .quote_character('\'');
+        writer.write_fields(["c", "d,e", "it's"]).unwrap();
+        assert_eq!(writer.get_ref(), b"a,b\nc,'d,e','it''s'\n");
+    }
+
+    #[test]
+    fn atomic_writer_replaces_only_complete_files() {
+        let (test_dir, _) = setup(b"");
+        let path = test_dir.path().join("out.tsv");
+        std::fs::write(&path, "old\n").unwrap();
+        let entries = || std::fs::read_dir(test_dir.path()).unwrap().count();
+        let before = entries();
+
+        let mut writer = Tabwriter::create_atomic(&path).unwrap();
+        writer.write_fields(["new", "1"]).unwrap();
+        assert!(writer.write_fields(["broken\nline"]).is_err());
+        assert_eq!(entries(), before + 1);
+        drop(writer);
+        assert_eq!(entries(), before);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\n");
+
+        let mut writer = Tabwriter::create_atomic(&path).unwrap();
+        let temporary_path = writer.get_ref().temporary_path().to_path_buf();
+        assert_eq!(temporary_path.parent(), path.parent());
+        assert_eq!(writer.get_ref().path(), path);
+        writer.write_fields(["new", "1"]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\t1\n");
+        assert!(!temporary_path.exists());
+        assert_eq!(entries(), before);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = std::fs::Permissions::from_mode(0o640);
+            std::fs::set_permissions(&path, permissions).unwrap();
+            let mut writer = Tabwriter::create_atomic(&path).unwrap();
+            writer.write_fields(["newer", "2"]).unwrap();
+            writer.finish().unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        let error = AtomicFile::create(test_dir.path().join(".."))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn invalid_utf8_line() {
+        let (_test_dir, test_file_path) = setup(b"a\tb\n\xff\xfe\nc\td\n");
+        let mut rows = Tabfile::open(&test_file_path).unwrap().into_iter();
+        assert_eq!(rows.next().unwrap().unwrap().fields(), vec!["a", "b"]);
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "[TF017] line 2 is not valid UTF-8");
+        let record = rows.next().unwrap().unwrap();
+        assert_eq!(record.fields(), vec!["c", "d"]);
+        assert_eq!((record.line_number(), record.byte_offset()), (3, 7));
+        assert!(rows.next().is_none());
+    }
+
+    #[test]
+    fn split_line_decodes_like_records() {
+        let dialect = Dialect::new()
+            .quote_character('"')
+            .unescape(EscapeStyle::Backslash)
+            .trim_fields(TrimMode::Both);
+        for line in [
+            "plain\tfields\n",
+            "\"quoted\tfield\"\t\"doubled \"\" quote\"\t  padded  \n",
+            "a\\tb\t\\N\tC:\\\\temp\n",
+        ] {
+            let fields: Vec<String> = split_line(line, &dialect).map(Cow::into_owned).collect();
+            assert_eq!(fields, dialect.parse_line(line, 1).fields());
+        }
+    }
+
+    #[cfg(feature = "contract")]
+    #[test]
+    fn check_contract() {
+        let (_contract_dir, contract_path) = setup(
+            br#"{
+                "separator": ",",
+                "columns": [
+                    {"name": "id", "type": "integer", "required": true, "unique": true},
+                    {"name": "score", "type": "float", "min": 0, "max": 1},
+                    {"name": "status", "allowed": ["active", "inactive"]}
+                ]
+            }"#,
+        );
+        let (_test_dir, test_file_path) = setup(
+            b"id,score,state\n1,0.5,active\n1,1.5,inactive\n,x,unknown\n3,0.1\n4,,inactive\n",
+        );
+        let report = Tabfile::open(&test_file_path)
+            .unwrap()
+            .check_contract(&contract_path)
+            .unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.records(), 5);
+        let violations: Vec<(usize, Option<usize>, ViolationKind)> = report
+            .violations()
+            .iter()
+            .map(|v| (v.line_number(), v.column(), v.kind().clone()))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    1,
+                    Some(2),
+                    ViolationKind::HeaderMismatch {
+                        expected: "status".to_string(),
+                        found: "state".to_string()
+                    }
+                ),
+                (
+                    3,
+                    Some(0),
+                    ViolationKind::Duplicate {
+                        value: "1".to_string()
+                    }
+                ),
+                (
+                    3,
+                    Some(1),
+                    ViolationKind::OutOfRange {
+                        value: "1.5".to_string()
+                    }
+                ),
+                (4, Some(0), ViolationKind::Missing),
+                (
+                    4,
+                    Some(1),
+                    ViolationKind::Type {
+                        expected: ColumnType::Float,
+                        value: "x".to_string()
+                    }
+                ),
+                (
+                    4,
+                    Some(2),
+                    ViolationKind::NotAllowed {
+                        value: "unknown".to_string()
+                    }
+                ),
+                (
+                    5,
+                    None,
+                    ViolationKind::ColumnCount {
+                        expected: 3,
+                        found: 2
+                    }
+                ),
+            ]
+        );
+        assert!(Contract::from_json(r#"{"columns": [], "colour": 1}"#).is_err());
+    }
+
+    #[cfg(feature = "duckdb")]
+    #[test]
+    fn duckdb() {
+        let (_test_dir, test_file_path) = setup(UNICODE);
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        let num_rows = Tabfile::open(test_file_path)
+            .unwrap()
+            .register_in_duckdb(&conn, "unicode")
+            .unwrap();
+        assert_eq!(num_rows, 2);
+        let second: String = conn
+            .query_row(
+                "SELECT column1 FROM unicode WHERE column0 LIKE 'é%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(second, "💣ℝ is it?");
+
+        let (_test_dir, test_file_path) = setup(FOUR_COLUMN);
+        let error = Tabfile::open(test_file_path)
+            .unwrap()
+            .skip_lines(2)
+            .register_in_duckdb(&conn, "four_column")
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let tables: usize = conn
+            .query_row(
+                "SELECT count(*) FROM information_schema.tables WHERE table_name = 'four_column'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
+
+        let (_test_dir, test_file_path) = setup(b"");
+        let num_rows = Tabfile::open(test_file_path)
+            .unwrap()
+            .columns(3)
+            .register_in_duckdb(&conn, "empty \"quoted\" table")
+            .unwrap();
+        assert_eq!(num_rows, 0);
+        let columns: usize = conn
+            .query_row(
+                "SELECT count(*) FROM information_schema.columns \
+                 WHERE table_name = 'empty \"quoted\" table'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(columns, 3);
+    }
 }
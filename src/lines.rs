@@ -0,0 +1,112 @@
+//! Reading lines with a custom terminator and a bounded length
+
+use crate::code::{self, ErrorCode};
+use std::io::{BufRead, Error};
+
+/// Why a line was not appended by `LineReader::read_line`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Rejected {
+    /// The line is longer than the maximum length
+    TooLong,
+    /// The line is not valid UTF-8
+    InvalidUtf8,
+    /// The line is not valid UTF-8 and has no terminator, e.g. because it is still being written
+    UnterminatedInvalidUtf8,
+}
+
+/// Reads lines like `BufRead::read_line` with the terminator and maximum length of a file
+pub(crate) struct LineReader {
+    terminator: u8,
+    max_length: Option<usize>,
+    buffer: Vec<u8>,
+}
+
+impl LineReader {
+    pub(crate) fn new(terminator: u8, max_length: Option<usize>) -> LineReader {
+        LineReader {
+            terminator,
+            max_length,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Read the next line including its terminator into `line`
+    ///
+    /// Returns the length of the line and why it was rejected, if it is longer than the
+    /// maximum length including the terminator or not valid UTF-8. A rejected line is consumed
+    /// up to its end, but not appended to `line`.
+    pub(crate) fn read_line<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        line: &mut String,
+    ) -> Result<(usize, Option<Rejected>), Error> {
+        if self.terminator == b'\n' && self.max_length.is_none() {
+            self.buffer.clear();
+            let line_length = reader.read_until(b'\n', &mut self.buffer)?;
+            return Ok((line_length, self.append_to(line)));
+        }
+        let max_length = self.max_length.unwrap_or(usize::MAX);
+        self.buffer.clear();
+        let mut line_length = 0;
+        let mut too_long = false;
+        loop {
+            let available = reader.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            let terminator = self.terminator;
+            let (chunk_length, at_end) = match available.iter().position(|&b| b == terminator) {
+                Some(position) => (position + 1, true),
+                None => (available.len(), false),
+            };
+            line_length += chunk_length;
+            if line_length > max_length {
+                // discard the rest of the line instead of buffering it
+                too_long = true;
+                self.buffer.clear();
+            } else {
+                self.buffer.extend_from_slice(&available[..chunk_length]);
+            }
+            reader.consume(chunk_length);
+            if at_end {
+                break;
+            }
+        }
+        if too_long {
+            return Ok((line_length, Some(Rejected::TooLong)));
+        }
+        Ok((line_length, self.append_to(line)))
+    }
+
+    /// Append the buffered line to `line` if it is valid UTF-8
+    fn append_to(&self, line: &mut String) -> Option<Rejected> {
+        match std::str::from_utf8(&self.buffer) {
+            Ok(text) => {
+                line.push_str(text);
+                None
+            }
+            Err(_) if self.buffer.last() != Some(&self.terminator) => {
+                Some(Rejected::UnterminatedInvalidUtf8)
+            }
+            Err(_) => Some(Rejected::InvalidUtf8),
+        }
+    }
+
+    /// The error for the line with `line_number` that was rejected
+    pub(crate) fn rejected(&self, rejected: Rejected, line_number: usize) -> Error {
+        match rejected {
+            Rejected::TooLong => code::invalid_data(
+                ErrorCode::LineTooLong,
+                format!(
+                    "line {}: longer than {} bytes",
+                    line_number,
+                    self.max_length.unwrap_or(usize::MAX)
+                ),
+            ),
+            Rejected::InvalidUtf8 | Rejected::UnterminatedInvalidUtf8 => code::invalid_data(
+                ErrorCode::InvalidUtf8,
+                format!("line {} is not valid UTF-8", line_number),
+            ),
+        }
+    }
+}
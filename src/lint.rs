@@ -0,0 +1,245 @@
+//! Finding stylistic and structural problems in tab files
+
+use crate::SNIFF_CANDIDATES;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error};
+use std::path::Path;
+use std::str;
+
+/// The checks that [lint](fn.lint.html) performs.
+///
+/// All checks except [sorted_by](#method.sorted_by) are enabled by default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintRules {
+    separator: char,
+    comment_character: Option<char>,
+    mixed_separators: bool,
+    trailing_whitespace: bool,
+    column_count: bool,
+    quoting: bool,
+    encoding: bool,
+    sorted_by: Option<usize>,
+}
+
+impl Default for LintRules {
+    fn default() -> LintRules {
+        LintRules {
+            separator: '\t',
+            comment_character: Some('#'),
+            mixed_separators: true,
+            trailing_whitespace: true,
+            column_count: true,
+            quoting: true,
+            encoding: true,
+            sorted_by: None,
+        }
+    }
+}
+
+impl LintRules {
+    /// Create the default rules for tab-separated files
+    pub fn new() -> LintRules {
+        LintRules::default()
+    }
+
+    /// Set the separator that the file is supposed to use. The default is `'\t'`.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Set the comment character of lines that are not checked or `None` to check all lines.
+    /// The default is `Some('#')`.
+    pub fn comment_character(mut self, comment_character: Option<char>) -> Self {
+        self.comment_character = comment_character;
+        self
+    }
+
+    /// Warn about lines without the separator that contain a different common separator
+    pub fn check_mixed_separators(mut self, check: bool) -> Self {
+        self.mixed_separators = check;
+        self
+    }
+
+    /// Warn about lines that end with spaces or tabs
+    pub fn check_trailing_whitespace(mut self, check: bool) -> Self {
+        self.trailing_whitespace = check;
+        self
+    }
+
+    /// Warn about lines with a different number of fields than the first line
+    pub fn check_column_count(mut self, check: bool) -> Self {
+        self.column_count = check;
+        self
+    }
+
+    /// Warn about fields with unbalanced double quotes
+    pub fn check_quoting(mut self, check: bool) -> Self {
+        self.quoting = check;
+        self
+    }
+
+    /// Warn about lines that are not valid UTF-8, e.g. because they are Latin-1
+    pub fn check_encoding(mut self, check: bool) -> Self {
+        self.encoding = check;
+        self
+    }
+
+    /// Warn about lines whose value in `column` is smaller than the one of the line before
+    ///
+    /// Values are compared as strings.
+    pub fn sorted_by(mut self, column: usize) -> Self {
+        self.sorted_by = Some(column);
+        self
+    }
+}
+
+/// The kind of problem that a [LintWarning](struct.LintWarning.html) is about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// The line does not contain the separator, but `found`
+    MixedSeparators { found: char },
+    /// The line ends with spaces or tabs
+    TrailingWhitespace,
+    /// The line has `found` fields instead of `expected`
+    ColumnCount { expected: usize, found: usize },
+    /// The field at `column` has unbalanced double quotes
+    SuspiciousQuoting { column: usize },
+    /// The line is not valid UTF-8
+    InvalidUtf8,
+    /// The value at `column` is smaller than the one of the previous line
+    Unsorted { column: usize },
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::MixedSeparators { found } => {
+                write!(f, "the separator is missing, but {:?} is used", found)
+            }
+            LintKind::TrailingWhitespace => write!(f, "trailing whitespace"),
+            LintKind::ColumnCount { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            LintKind::SuspiciousQuoting { column } => {
+                write!(f, "unbalanced quotes in column {}", column)
+            }
+            LintKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            LintKind::Unsorted { column } => write!(f, "not sorted by column {}", column),
+        }
+    }
+}
+
+/// A problem found by [lint](fn.lint.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintWarning {
+    line_number: usize,
+    kind: LintKind,
+}
+
+impl LintWarning {
+    /// Get the line number of the problem
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the kind of the problem
+    pub fn kind(&self) -> &LintKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_number, self.kind)
+    }
+}
+
+/// Check a file for stylistic and structural problems.
+///
+/// All lines except empty and comment lines are checked with `rules` and a warning is produced
+/// for every problem. Lines that are not valid UTF-8 are reported but not checked otherwise.
+/// Only I/O errors are returned as errors, so the file does not need to be readable by a
+/// [Tabfile](struct.Tabfile.html).
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"a\tb\n1\t2 \n3,4\n");
+/// use tabfile::{lint, LintRules};
+///
+/// for warning in lint(file.path(), &LintRules::new()).unwrap() {
+///     // prints e.g. "line 2: trailing whitespace"
+///     eprintln!("{}", warning);
+/// }
+/// ```
+pub fn lint<P: AsRef<Path>>(path: P, rules: &LintRules) -> Result<Vec<LintWarning>, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut warnings = Vec::new();
+    let mut buffer = Vec::new();
+    let mut line_number = 0;
+    let mut expected_fields = None;
+    let mut previous_key: Option<String> = None;
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            return Ok(warnings);
+        }
+        line_number += 1;
+        let mut warn = |kind| warnings.push(LintWarning { line_number, kind });
+        let line = match str::from_utf8(&buffer) {
+            Ok(line) => line.trim_end_matches(['\r', '\n']),
+            Err(_) => {
+                if rules.encoding {
+                    warn(LintKind::InvalidUtf8);
+                }
+                continue;
+            }
+        };
+        let is_comment = rules.comment_character.is_some_and(|c| line.starts_with(c));
+        if line.is_empty() || is_comment {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(rules.separator).collect();
+        if rules.mixed_separators && fields.len() == 1 {
+            let other = SNIFF_CANDIDATES
+                .iter()
+                .find(|&&c| c != rules.separator && line.contains(c));
+            if let Some(&found) = other {
+                warn(LintKind::MixedSeparators { found });
+            }
+        }
+        if rules.trailing_whitespace && line.ends_with([' ', '\t']) {
+            warn(LintKind::TrailingWhitespace);
+        }
+        if rules.column_count {
+            let expected = *expected_fields.get_or_insert(fields.len());
+            if fields.len() != expected {
+                warn(LintKind::ColumnCount {
+                    expected,
+                    found: fields.len(),
+                });
+            }
+        }
+        if rules.quoting {
+            for (column, field) in fields.iter().enumerate() {
+                if field.matches('"').count() % 2 == 1 {
+                    warn(LintKind::SuspiciousQuoting { column });
+                }
+            }
+        }
+        if let Some(column) = rules.sorted_by {
+            if let Some(&key) = fields.get(column) {
+                if previous_key
+                    .as_deref()
+                    .is_some_and(|previous| key < previous)
+                {
+                    warn(LintKind::Unsorted { column });
+                }
+                previous_key = Some(key.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Reading the comment lines at the start of a file
+
+use crate::Tabfile;
+use std::io::{Error, Seek, SeekFrom};
+
+impl Tabfile {
+    /// Read the comment lines before the first record without reading the record.
+    ///
+    /// Many formats start with a block of comment lines like `##key=value` that describe the
+    /// data. This reads the lines removed by `skip_lines` and `skip_lines_while` and then all
+    /// comment and empty lines up to the first record, and returns the comment lines with their
+    /// comment prefix but without their line terminator. Iterating afterwards starts at the first
+    /// record and line numbers still count from the beginning of the file. The lines are also
+    /// available through [metadata](#method.metadata) and
+    /// [RowIterator::leading_comments](crate::RowIterator::leading_comments).
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"##fileformat=VCFv4.2\n##source=caller\nchr1\t1234\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut tsv_file = Tabfile::open(file.path()).unwrap();
+    /// let metadata = tsv_file.read_metadata().unwrap();
+    /// assert_eq!(metadata, &["##fileformat=VCFv4.2", "##source=caller"]);
+    /// let record = tsv_file.into_iter().next().unwrap().unwrap();
+    /// assert_eq!(record.line_number(), 3);
+    /// ```
+    pub fn read_metadata(&mut self) -> Result<&[String], Error> {
+        let mut line = String::new();
+        let mut line_reader = self.line_reader();
+        loop {
+            line.clear();
+            let (line_length, rejected) = line_reader.read_line(&mut self.reader, &mut line)?;
+            if line_length == 0 {
+                break;
+            }
+            if let Some(rejected) = rejected {
+                // the iteration runs into the same error
+                self.reader.seek(SeekFrom::Start(self.start_offset))?;
+                let line_number = self.start_line_number + 1;
+                return Err(line_reader.rejected(rejected, line_number));
+            }
+            let in_preamble = self.skip_lines == 0 && self.in_preamble(&line);
+            if self.skip_lines == 0 && !in_preamble {
+                // the preamble is over and must not be searched for again by the iterator
+                self.skip_while = None;
+            }
+            if self.skip_lines == 0 && !in_preamble && self.dialect.is_data_line(&line) {
+                // leave the record for the iterator
+                self.reader.seek(SeekFrom::Start(self.start_offset))?;
+                break;
+            }
+            if self.skip_lines > 0 {
+                self.skip_lines -= 1;
+            } else if !in_preamble && self.dialect.is_comment(&line) {
+                let content = self.dialect.content(&line);
+                self.metadata.push(content.to_string());
+            }
+            self.start_offset += line_length as u64;
+            self.start_line_number += 1;
+        }
+        Ok(&self.metadata)
+    }
+
+    /// Get the comment lines read by [read_metadata](#method.read_metadata)
+    ///
+    /// This is empty until `read_metadata` is called. The comments before the first record are
+    /// also collected while iterating, see
+    /// [RowIterator::leading_comments](crate::RowIterator::leading_comments).
+    pub fn metadata(&self) -> &[String] {
+        &self.metadata
+    }
+}
@@ -0,0 +1,92 @@
+//! Random access to the records of a file through their byte offsets
+
+use crate::{Record, RowIterator, Tabfile};
+use std::io::Error;
+use std::iter::Take;
+use std::ops::Range;
+
+/// The positions of all records of a file, for jumping to any record.
+///
+/// See [Tabfile::build_index](crate::Tabfile::build_index).
+pub struct RecordIndex {
+    rows: RowIterator,
+    /// The byte offset and line number of every record
+    positions: Vec<(u64, usize)>,
+}
+
+impl RecordIndex {
+    /// Get the number of records
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Check whether the file has no records
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Get the byte offset of the record at index `n`
+    pub fn byte_offset(&self, n: usize) -> Option<u64> {
+        self.positions.get(n).map(|&(byte_offset, _)| byte_offset)
+    }
+
+    /// Get the line number of the record at index `n`
+    pub fn line_number(&self, n: usize) -> Option<usize> {
+        self.positions.get(n).map(|&(_, line_number)| line_number)
+    }
+
+    /// Read the record at index `n`, or `None` if there are not that many records
+    pub fn record_at(&mut self, n: usize) -> Result<Option<Record>, Error> {
+        self.records_in(n..n + 1)?.next().transpose()
+    }
+
+    /// Iterate over the records with the indices in `range`.
+    ///
+    /// Indices after the last record are ignored, so the iterator may return fewer records
+    /// than `range` contains. The iterator reads the file like a [RowIterator], just without
+    /// the lines before the first record of the range.
+    pub fn records_in(&mut self, range: Range<usize>) -> Result<Take<&mut RowIterator>, Error> {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+        if start < end {
+            let (byte_offset, line_number) = self.positions[start];
+            self.rows.seek_data_line(byte_offset, line_number, start)?;
+        }
+        Ok(self.rows.by_ref().take(end - start))
+    }
+}
+
+impl Tabfile {
+    /// Read the whole file once to find the position of every record.
+    ///
+    /// Afterwards, any record can be read with [RecordIndex::record_at] and any range of
+    /// records with [RecordIndex::records_in] without reading the lines before it, e.g. for a
+    /// table view of a large file, or for drawing random samples. Records are counted like
+    /// with [page](#method.page), starting at zero. Building the index only finds the lines
+    /// and does not split them. The index stores 16 bytes per record and the first error while
+    /// reading is returned.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "{}\tvalue", i).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut index = Tabfile::open(file.path()).unwrap().build_index().unwrap();
+    /// assert_eq!(index.len(), 1000);
+    /// let record = index.record_at(500).unwrap().unwrap();
+    /// assert_eq!(&record[0], "500");
+    /// let page: Vec<_> = index.records_in(998..1005).unwrap().collect();
+    /// assert_eq!(page.len(), 2);
+    /// ```
+    pub fn build_index(self) -> Result<RecordIndex, Error> {
+        let mut rows = self.into_iter();
+        let mut positions = Vec::new();
+        let mut line = String::new();
+        while let Some(byte_offset) = rows.next_data_line(&mut line)? {
+            positions.push((byte_offset, rows.next_line_number));
+        }
+        Ok(RecordIndex { rows, positions })
+    }
+}
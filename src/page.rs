@@ -0,0 +1,46 @@
+//! Reading a bounded range of records
+
+use crate::{RowIterator, Tabfile};
+use std::io::Error;
+use std::iter::Take;
+
+impl Tabfile {
+    /// Iterate over at most `limit` records, starting with the record at index `offset`.
+    ///
+    /// The first `offset` records are read but not split into fields, which is considerably
+    /// faster than skipping them with `Iterator::skip`. Record indices start at zero and only
+    /// count records, not comment, skipped or empty lines. If the file has fewer than `offset`
+    /// records the page is empty. Errors while skipping are returned right away, since the
+    /// position of the page would be unknown afterwards.
+    ///
+    /// Continuing with the next page from
+    /// [RowIterator::byte_offset](crate::RowIterator::byte_offset) avoids reading the skipped
+    /// records again.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..100 { writeln!(file, "{}\tvalue", i).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let page: Vec<String> = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .page(40, 20)
+    ///     .unwrap()
+    ///     .map(|record| record.unwrap().fields()[0].to_string())
+    ///     .collect();
+    /// assert_eq!(page.len(), 20);
+    /// assert_eq!((page[0].as_str(), page[19].as_str()), ("40", "59"));
+    /// ```
+    pub fn page(self, offset: usize, limit: usize) -> Result<Take<RowIterator>, Error> {
+        let mut rows = self.into_iter();
+        let mut line = String::new();
+        for _ in 0..offset {
+            if rows.next_data_line(&mut line)?.is_none() {
+                break;
+            }
+        }
+        Ok(rows.take(limit))
+    }
+}
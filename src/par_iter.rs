@@ -0,0 +1,112 @@
+//! Integration with rayon
+
+use crate::iter::IterItem;
+use crate::parallel::{read_batch, RawBatch};
+use crate::{RowIterator, Tabfile};
+use rayon::iter::plumbing::{Folder, Reducer, UnindexedConsumer};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::mem;
+
+/// Default number of lines that are read from the file before they are split in parallel
+pub const DEFAULT_PAR_CHUNK_SIZE: usize = 4096;
+
+/// A rayon `ParallelIterator` over the records of a tab file.
+///
+/// The file is read in chunks of lines on one thread while the previous chunk is split into
+/// fields on the rayon thread pool, so there is no lock around the file like with
+/// `par_bridge()`. Unlike `par_bridge()`, the order of the records is preserved, e.g. by
+/// `collect()`. An error ends the iteration after it was yielded.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"1\tfoo\n2\tbar\n");
+/// use rayon::prelude::*;
+/// use tabfile::Tabfile;
+///
+/// let sum: u64 = Tabfile::open(file.path())
+///     .unwrap()
+///     .into_par_iter()
+///     .map(|record| record.unwrap().fields()[0].parse::<u64>().unwrap())
+///     .sum();
+/// assert_eq!(sum, 3);
+/// ```
+///
+/// This type is only available with the `rayon` feature.
+pub struct ParRecords {
+    rows: RowIterator,
+    chunk_size: usize,
+}
+
+impl ParRecords {
+    /// Set the number of lines that are read at once.
+    ///
+    /// The default is [DEFAULT_PAR_CHUNK_SIZE](constant.DEFAULT_PAR_CHUNK_SIZE.html). Larger
+    /// chunks need more memory but spend less time waiting for the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "the chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl ParallelIterator for ParRecords {
+    type Item = IterItem;
+
+    fn drive_unindexed<C>(mut self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let dialect = self.rows.tabfile.dialect.clone();
+        let pool = self.rows.tabfile.record_pool.clone();
+        let (mut chunk, mut done) = read_batch(&mut self.rows, self.chunk_size);
+        let mut result = None;
+        while !chunk.is_empty() && !consumer.full() {
+            let current: RawBatch = mem::take(&mut chunk);
+            let left = consumer.split_off_left();
+            let rows = &mut self.rows;
+            let chunk_size = self.chunk_size;
+            let ((next, next_done), chunk_result) = rayon::join(
+                || match done {
+                    true => (Vec::new(), true),
+                    false => read_batch(rows, chunk_size),
+                },
+                || {
+                    current
+                        .into_par_iter()
+                        .map(|raw| raw.and_then(|raw| raw.into_record(&dialect, pool.clone())))
+                        .drive_unindexed(left)
+                },
+            );
+            result = Some(match result {
+                Some(previous) => consumer.to_reducer().reduce(previous, chunk_result),
+                None => chunk_result,
+            });
+            chunk = next;
+            done = next_done;
+        }
+        let reducer = consumer.to_reducer();
+        let rest = consumer.into_folder().complete();
+        match result {
+            Some(previous) => reducer.reduce(previous, rest),
+            None => rest,
+        }
+    }
+}
+
+impl IntoParallelIterator for Tabfile {
+    type Item = IterItem;
+    type Iter = ParRecords;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParRecords {
+            rows: self.into_iter(),
+            chunk_size: DEFAULT_PAR_CHUNK_SIZE,
+        }
+    }
+}
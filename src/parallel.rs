@@ -0,0 +1,181 @@
+//! Splitting lines into fields on several threads
+
+use crate::iter::IterItem;
+use crate::parse::Dialect;
+use crate::pool::RecordPool;
+use crate::{Record, RowIterator, Tabfile};
+use std::io::Error;
+use std::ops::Range;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::vec;
+
+/// Number of lines that are sent to a worker thread at once
+const BATCH_SIZE: usize = 1024;
+
+/// Number of batches that may wait in a channel before the sending thread blocks
+const CHANNEL_CAPACITY: usize = 4;
+
+/// A line that was read from the file but not yet split into fields
+pub(crate) struct RawLine {
+    line: String,
+    ranges: Vec<Range<usize>>,
+    line_number: usize,
+    byte_offset: u64,
+}
+
+impl RawLine {
+    pub(crate) fn into_record(
+        self,
+        dialect: &Dialect,
+        pool: Option<Arc<RecordPool>>,
+    ) -> Result<Record, Error> {
+        let record = dialect.record(
+            self.line,
+            self.ranges,
+            self.line_number,
+            self.byte_offset,
+            pool,
+        );
+        dialect.check_width(&record)?;
+        Ok(record)
+    }
+}
+
+pub(crate) type RawBatch = Vec<Result<RawLine, Error>>;
+
+/// Read up to `batch_size` lines from `rows` without splitting them
+///
+/// Returns `true` as the second element if the end of the file or an error was reached, in which
+/// case the error is the last element of the batch.
+pub(crate) fn read_batch(rows: &mut RowIterator, batch_size: usize) -> (RawBatch, bool) {
+    let mut batch = Vec::with_capacity(batch_size);
+    while batch.len() < batch_size {
+        let (mut line, ranges) = match &rows.tabfile.record_pool {
+            Some(pool) => pool.take(),
+            None => (String::new(), Vec::new()),
+        };
+        match rows.next_data_line(&mut line) {
+            Ok(Some(byte_offset)) => batch.push(Ok(RawLine {
+                line,
+                ranges,
+                line_number: rows.next_line_number,
+                byte_offset,
+            })),
+            Ok(None) => return (batch, true),
+            Err(e) => {
+                batch.push(Err(e));
+                return (batch, true);
+            }
+        }
+    }
+    (batch, false)
+}
+
+/// Iterator over the records of a tab file that are split into fields on worker threads.
+///
+/// See [Tabfile::into_parallel_iter](struct.Tabfile.html#method.into_parallel_iter).
+pub struct ParallelRowIterator {
+    outputs: Vec<Receiver<Vec<IterItem>>>,
+    next_output: usize,
+    current: vec::IntoIter<IterItem>,
+}
+
+impl Iterator for ParallelRowIterator {
+    type Item = IterItem;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.next() {
+                return Some(item);
+            }
+            // batches are distributed round-robin, so a disconnected worker means that the
+            // reader did not send any further batch
+            let batch = self.outputs[self.next_output].recv().ok()?;
+            self.next_output = (self.next_output + 1) % self.outputs.len();
+            self.current = batch.into_iter();
+        }
+    }
+}
+
+/// Read lines from `rows` and distribute them in batches to `inputs`
+fn read_lines(mut rows: RowIterator, inputs: Vec<SyncSender<RawBatch>>) {
+    for input in inputs.iter().cycle() {
+        let (batch, done) = read_batch(&mut rows, BATCH_SIZE);
+        if (!batch.is_empty() && input.send(batch).is_err()) || done {
+            return; // finished or the iterator was dropped
+        }
+    }
+}
+
+/// Split the lines of every batch from `input` into records
+fn split_lines(
+    dialect: Arc<Dialect>,
+    pool: Option<Arc<RecordPool>>,
+    input: Receiver<RawBatch>,
+    output: SyncSender<Vec<IterItem>>,
+) {
+    for batch in input {
+        let records = batch
+            .into_iter()
+            .map(|raw| raw.and_then(|raw| raw.into_record(&dialect, pool.clone())))
+            .collect();
+        if output.send(records).is_err() {
+            return; // the iterator was dropped
+        }
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the records while splitting the lines into fields on `num_threads` threads.
+    ///
+    /// One additional thread reads the lines from the file and applies `skip_lines`,
+    /// `comment_character` and `skip_empty_lines`. The remaining lines are handed out in batches
+    /// to the worker threads, which split, trim and pad them according to the other options. The
+    /// channels between the threads are bounded, so a slow consumer does not cause the whole
+    /// file to be read into memory.
+    ///
+    /// The records are returned in the same order as by the sequential iterator, including any
+    /// error, after which the iteration ends. This pays off for large files on fast storage when
+    /// splitting is the bottleneck, e.g. with many fields per line or a regex separator. If
+    /// `num_threads` is zero, one worker per available CPU core is started.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let tsv_file = Tabfile::open(file.path()).unwrap();
+    /// for record in tsv_file.into_parallel_iter(4) {
+    ///     println!("{}", record.unwrap().fields()[1]);
+    /// }
+    /// ```
+    pub fn into_parallel_iter(self, num_threads: usize) -> ParallelRowIterator {
+        let num_threads = match num_threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let dialect = Arc::new(self.dialect.clone());
+        let pool = self.record_pool.clone();
+        let mut inputs = Vec::with_capacity(num_threads);
+        let mut outputs = Vec::with_capacity(num_threads);
+        for _ in 0..num_threads {
+            let (input_sender, input_receiver) = sync_channel(CHANNEL_CAPACITY);
+            let (output_sender, output_receiver) = sync_channel(CHANNEL_CAPACITY);
+            let dialect = dialect.clone();
+            let pool = pool.clone();
+            thread::spawn(move || split_lines(dialect, pool, input_receiver, output_sender));
+            inputs.push(input_sender);
+            outputs.push(output_receiver);
+        }
+        let rows = self.into_iter();
+        thread::spawn(move || read_lines(rows, inputs));
+        ParallelRowIterator {
+            outputs,
+            next_output: 0,
+            current: Vec::new().into_iter(),
+        }
+    }
+}
@@ -0,0 +1,1311 @@
+//! Turning lines into records
+//!
+//! This module contains the splitting logic of the crate without any file handling. Use a
+//! [Dialect](struct.Dialect.html) to parse lines that you got from somewhere else, e.g. a
+//! database or a message queue, with the same rules that a [Tabfile](crate::Tabfile) uses.
+//!
+//! ```
+//! use tabfile::parse::Dialect;
+//!
+//! let dialect = Dialect::new().separator(',');
+//! let record = dialect.parse_line("foo,bar,baz\n", 1);
+//! assert_eq!(record.fields(), vec!["foo", "bar", "baz"]);
+//! ```
+
+use crate::code::{self, ErrorCode};
+use crate::pool::RecordPool;
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Error;
+use std::ops::{Index, Range};
+use std::slice;
+use std::sync::Arc;
+
+/// Which side of a field whitespace is removed from.
+///
+/// See [Tabfile::trim_fields](crate::Tabfile::trim_fields).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Keep all whitespace
+    None,
+    /// Remove leading whitespace
+    Start,
+    /// Remove trailing whitespace
+    End,
+    /// Remove leading and trailing whitespace
+    Both,
+}
+
+/// What happens to rows whose number of fields differs from the expected width.
+///
+/// See [Tabfile::on_ragged](crate::Tabfile::on_ragged).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaggedPolicy {
+    /// Keep all rows as they are, which is the default
+    Keep,
+    /// Fill up short rows with empty fields and keep long rows
+    Pad,
+    /// Drop the fields after the expected width from long rows and keep short rows
+    Truncate,
+    /// Return an error of kind `InvalidData` for every row with a different width
+    Error,
+}
+
+/// Which characters count as whitespace when trimming fields or splitting on whitespace.
+///
+/// See [Tabfile::whitespace](crate::Tabfile::whitespace).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Only ASCII whitespace like spaces and tabs
+    Ascii,
+    /// Every character with the Unicode `White_Space` property, including non-breaking spaces
+    Unicode,
+}
+
+/// Which escape sequences are decoded in fields.
+///
+/// See [Tabfile::unescape](crate::Tabfile::unescape).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscapeStyle {
+    /// Keep backslashes as they are, which is the default
+    None,
+    /// Decode `\t`, `\n`, `\r`, `\\` and the other C escape sequences like PostgreSQL `COPY`
+    Backslash,
+}
+
+/// How the fields of a line are separated from each other
+#[derive(Clone, Debug)]
+pub(crate) enum Separator {
+    Char(char),
+    Str(String),
+    Whitespace,
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+/// The rules for turning a line into a [Record](struct.Record.html).
+///
+/// A `Dialect` describes how fields are separated and post-processed and which lines contain
+/// data at all. Every [Tabfile](crate::Tabfile) has a dialect that is configured through
+/// its builder methods, but a `Dialect` can also be used on its own. The builder methods have the
+/// same defaults and meaning as the ones of `Tabfile`.
+#[derive(Clone, Debug)]
+pub struct Dialect {
+    pub(crate) separator: Separator,
+    pub(crate) comment_prefixes: Vec<String>,
+    pub(crate) skip_empty_lines: bool,
+    pub(crate) trim_mode: TrimMode,
+    pub(crate) whitespace: Whitespace,
+    pub(crate) pad_short_rows: Option<usize>,
+    pub(crate) ragged: Option<(usize, RaggedPolicy)>,
+    pub(crate) null_tokens: Option<Arc<[String]>>,
+    /// Stop splitting after this many fields, used when only the first columns are needed
+    pub(crate) field_limit: Option<usize>,
+    pub(crate) projection: Option<Arc<[usize]>>,
+    pub(crate) terminator: u8,
+    pub(crate) quote: Option<char>,
+    pub(crate) escape: EscapeStyle,
+    pub(crate) excel_text: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect {
+            separator: Separator::Char('\t'),
+            comment_prefixes: vec!["#".to_string()],
+            skip_empty_lines: true,
+            trim_mode: TrimMode::None,
+            whitespace: Whitespace::Ascii,
+            pad_short_rows: None,
+            ragged: None,
+            null_tokens: None,
+            field_limit: None,
+            projection: None,
+            terminator: b'\n',
+            quote: None,
+            escape: EscapeStyle::None,
+            excel_text: false,
+        }
+    }
+}
+
+impl Dialect {
+    /// Create a dialect with the default settings
+    pub fn new() -> Dialect {
+        Dialect::default()
+    }
+
+    /// Set the separator. See [Tabfile::separator](crate::Tabfile::separator).
+    pub fn separator(mut self, sep: char) -> Self {
+        self.separator = Separator::Char(sep);
+        self
+    }
+
+    /// Set a multi-character separator. See
+    /// [Tabfile::separator_str](crate::Tabfile::separator_str).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    pub fn separator_str(mut self, sep: &str) -> Self {
+        assert!(!sep.is_empty(), "the separator must not be empty");
+        self.separator = Separator::Str(sep.to_string());
+        self
+    }
+
+    /// Split on runs of spaces and tabs. See
+    /// [Tabfile::split_whitespace](crate::Tabfile::split_whitespace).
+    pub fn split_whitespace(mut self, split: bool) -> Self {
+        if split {
+            self.separator = Separator::Whitespace;
+        } else if let Separator::Whitespace = self.separator {
+            self.separator = Separator::Char('\t');
+        }
+        self
+    }
+
+    /// Split on a regular expression. See
+    /// [Tabfile::separator_regex](crate::Tabfile::separator_regex).
+    #[cfg(feature = "regex")]
+    pub fn separator_regex(mut self, sep: regex::Regex) -> Self {
+        self.separator = Separator::Regex(sep);
+        self
+    }
+
+    /// Set the quote character. See
+    /// [Tabfile::quote_character](crate::Tabfile::quote_character).
+    pub fn quote_character(mut self, quote: char) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    /// Decode escape sequences in fields. See [Tabfile::unescape](crate::Tabfile::unescape).
+    pub fn unescape(mut self, style: EscapeStyle) -> Self {
+        self.escape = style;
+        self
+    }
+
+    /// Strip Excel text formulas. See
+    /// [Tabfile::unwrap_excel_text](crate::Tabfile::unwrap_excel_text).
+    pub fn unwrap_excel_text(mut self, unwrap: bool) -> Self {
+        self.excel_text = unwrap;
+        self
+    }
+
+    /// Get the quote character if it applies to `line`
+    fn quote_in(&self, line: &str) -> Option<char> {
+        match (self.quote, &self.separator) {
+            (Some(quote), Separator::Char(_) | Separator::Str(_)) if line.contains(quote) => {
+                Some(quote)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check whether a quoted field of `line` is not closed, i.e. it continues on the next line
+    pub(crate) fn has_open_quote(&self, line: &str) -> bool {
+        match self.quote_in(line) {
+            Some(quote) => {
+                let separator = self.separator_text();
+                split_quoted(line, &separator, quote, usize::MAX, &mut Vec::new())
+            }
+            None => false,
+        }
+    }
+
+    /// Get a character or string separator as a string
+    fn separator_text(&self) -> String {
+        match &self.separator {
+            Separator::Char(separator) => separator.to_string(),
+            Separator::Str(separator) => separator.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Set the comment character. See
+    /// [Tabfile::comment_character](crate::Tabfile::comment_character).
+    pub fn comment_character(mut self, comment_character: char) -> Self {
+        self.comment_prefixes = vec![comment_character.to_string()];
+        self
+    }
+
+    /// Set a comment prefix. See [Tabfile::comment_prefix](crate::Tabfile::comment_prefix).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is empty.
+    pub fn comment_prefix(self, prefix: &str) -> Self {
+        self.comment_prefixes(&[prefix])
+    }
+
+    /// Set several comment prefixes. See
+    /// [Tabfile::comment_prefixes](crate::Tabfile::comment_prefixes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of `prefixes` is empty.
+    pub fn comment_prefixes(mut self, prefixes: &[&str]) -> Self {
+        assert!(
+            prefixes.iter().all(|prefix| !prefix.is_empty()),
+            "comment prefixes must not be empty"
+        );
+        self.comment_prefixes = prefixes.iter().map(|prefix| prefix.to_string()).collect();
+        self
+    }
+
+    /// Check whether a line is a comment
+    pub(crate) fn is_comment(&self, line: &str) -> bool {
+        self.comment_prefixes
+            .iter()
+            .any(|prefix| line.starts_with(prefix.as_str()))
+    }
+
+    /// Skip empty lines. See [Tabfile::skip_empty_lines](crate::Tabfile::skip_empty_lines).
+    pub fn skip_empty_lines(mut self, skip: bool) -> Self {
+        self.skip_empty_lines = skip;
+        self
+    }
+
+    /// Trim fields. See [Tabfile::trim_fields](crate::Tabfile::trim_fields).
+    pub fn trim_fields(mut self, mode: TrimMode) -> Self {
+        self.trim_mode = mode;
+        self
+    }
+
+    /// Choose the whitespace characters. See [Tabfile::whitespace](crate::Tabfile::whitespace).
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// Set null tokens. See [Tabfile::null_tokens](crate::Tabfile::null_tokens).
+    pub fn null_tokens(mut self, tokens: &[&str]) -> Self {
+        self.null_tokens = Some(tokens.iter().map(|token| token.to_string()).collect());
+        self
+    }
+
+    /// Pad short rows. See [Tabfile::pad_short_rows](crate::Tabfile::pad_short_rows).
+    pub fn pad_short_rows(mut self, width: usize) -> Self {
+        self.pad_short_rows = Some(width);
+        self
+    }
+
+    /// Handle rows with a different width than `width`. See
+    /// [Tabfile::on_ragged](crate::Tabfile::on_ragged).
+    pub fn on_ragged(mut self, width: usize, policy: RaggedPolicy) -> Self {
+        self.ragged = match policy {
+            RaggedPolicy::Keep => None,
+            policy => Some((width, policy)),
+        };
+        self
+    }
+
+    /// Set the byte that ends a record. See
+    /// [Tabfile::record_terminator](crate::Tabfile::record_terminator).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `terminator` is not an ASCII character.
+    pub fn record_terminator(mut self, terminator: u8) -> Self {
+        assert!(terminator.is_ascii(), "the record terminator must be ASCII");
+        self.terminator = terminator;
+        self
+    }
+
+    /// Get a line without its terminator
+    pub(crate) fn content<'a>(&self, line: &'a str) -> &'a str {
+        content(line, self.terminator)
+    }
+
+    /// Keep only the given columns. See
+    /// [Tabfile::select_columns](crate::Tabfile::select_columns).
+    pub fn select_columns(mut self, columns: &[usize]) -> Self {
+        self.projection = Some(columns.into());
+        self
+    }
+
+    /// Check a record against the width of [RaggedPolicy::Error](enum.RaggedPolicy.html)
+    pub(crate) fn check_width(&self, record: &Record) -> Result<(), Error> {
+        match self.ragged {
+            Some((width, RaggedPolicy::Error)) if record.len() != width => Err(code::invalid_data(
+                ErrorCode::ColumnCount,
+                format!(
+                    "line {}: expected {} fields but found {}",
+                    record.line_number(),
+                    width,
+                    record.len()
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check whether a line contains data, i.e. it is neither a comment nor an empty line that
+    /// should be skipped
+    pub fn is_data_line(&self, line: &str) -> bool {
+        if self.is_comment(line) {
+            return false;
+        }
+        !(self.skip_empty_lines && self.content(line).trim() == "")
+    }
+
+    /// Split a line into a record
+    ///
+    /// The line may or may not end with a line terminator. Since the line does not come from a
+    /// file, the [byte offset](struct.Record.html#method.byte_offset) of the record is zero.
+    /// Comment and empty lines are not filtered out, use `is_data_line` for that.
+    pub fn parse_line<S: Into<String>>(&self, line: S, line_number: usize) -> Record {
+        self.record(line.into(), Vec::new(), line_number, 0, None)
+    }
+
+    /// Build a record from a line, reusing the `ranges` buffer
+    pub(crate) fn record(
+        &self,
+        line: String,
+        mut ranges: Vec<Range<usize>>,
+        line_number: usize,
+        byte_offset: u64,
+        pool: Option<Arc<RecordPool>>,
+    ) -> Record {
+        let line = self.split_record(line, &mut ranges);
+        Record {
+            line,
+            line_number,
+            byte_offset,
+            ranges,
+            pool,
+            null_tokens: self.null_tokens.clone(),
+            terminator: self.terminator,
+        }
+    }
+
+    /// Replace the contents of `record` with `line`, reusing the `ranges` buffer of the record
+    pub(crate) fn refill(
+        &self,
+        record: &mut Record,
+        line: String,
+        line_number: usize,
+        byte_offset: u64,
+    ) {
+        record.line = self.split_record(line, &mut record.ranges);
+        record.line_number = line_number;
+        record.byte_offset = byte_offset;
+        record.null_tokens = self.null_tokens.clone();
+        record.terminator = self.terminator;
+    }
+
+    /// Split `line` like `split_record` without copying it if no field has to be decoded
+    ///
+    /// Returns the line with the decoded fields if a field may be quoted or escaped, otherwise
+    /// the positions are in `line`.
+    fn split_borrowed(&self, line: &str, ranges: &mut Vec<Range<usize>>) -> Option<String> {
+        let quote = self.split_raw(line, ranges);
+        let escaped = self.escape == EscapeStyle::Backslash && line.contains('\\');
+        if quote.is_some() || escaped {
+            return Some(self.split_record(line.to_string(), ranges));
+        }
+        self.finish_split(line, ranges);
+        None
+    }
+
+    /// Split `line` like `split_into`, but replace quoted and escaped fields by their content
+    ///
+    /// Returns the line with the decoded fields, which is `line` itself if nothing is quoted or
+    /// escaped.
+    fn split_record(&self, line: String, ranges: &mut Vec<Range<usize>>) -> String {
+        let line = match self.split_raw(&line, ranges) {
+            Some(quote) => unquote(&line, ranges, quote),
+            None => line,
+        };
+        let line = match self.escape {
+            EscapeStyle::Backslash if line.contains('\\') => unescape(&line, ranges),
+            _ => line,
+        };
+        self.finish_split(&line, ranges);
+        line
+    }
+
+    /// Get the number of fields that have to be split out
+    fn split_limit(&self) -> usize {
+        match &self.projection {
+            // the fields after the last selected column are not needed
+            Some(columns) => columns.iter().max().map_or(0, |max| max + 1),
+            None => self.field_limit.unwrap_or(usize::MAX),
+        }
+    }
+
+    /// Compute the positions of the fields of `line` including their quotes
+    ///
+    /// Returns the quote character if fields may be quoted.
+    fn split_raw(&self, line: &str, ranges: &mut Vec<Range<usize>>) -> Option<char> {
+        ranges.clear();
+        // the splitters only know about line breaks
+        let line = match self.terminator {
+            b'\n' => line,
+            _ => self.content(line),
+        };
+        let limit = self.split_limit();
+        if let Some(quote) = self.quote_in(line) {
+            split_quoted(line, &self.separator_text(), quote, limit, ranges);
+            ranges.truncate(limit);
+            return Some(quote);
+        }
+        match &self.separator {
+            Separator::Char(sep) => split_char(line, *sep, limit, ranges),
+            Separator::Str(sep) => split_str(line, sep, limit, ranges),
+            Separator::Whitespace => split_whitespace(line, self.whitespace, ranges),
+            #[cfg(feature = "regex")]
+            Separator::Regex(sep) => split_regex(line, sep, ranges),
+        }
+        ranges.truncate(limit);
+        None
+    }
+
+    /// Select, unwrap, trim and pad the fields of `line`
+    fn finish_split(&self, line: &str, ranges: &mut Vec<Range<usize>>) {
+        let field_limit = self.field_limit.unwrap_or(usize::MAX);
+        if let Some(columns) = &self.projection {
+            // append the selected ranges and drop the original ones to reuse the buffer
+            let split = ranges.len();
+            for &column in columns.iter().filter(|&&column| column < split) {
+                ranges.push(ranges[column].clone());
+            }
+            ranges.drain(..split);
+            ranges.truncate(field_limit);
+        }
+        if self.excel_text {
+            for range in ranges.iter_mut() {
+                let field = &line[range.clone()];
+                if field.len() >= 3 && field.starts_with("=\"") && field.ends_with('"') {
+                    range.start += 2;
+                    range.end -= 1;
+                }
+            }
+        }
+        if self.trim_mode != TrimMode::None {
+            trim(line, ranges, self.trim_mode, self.whitespace);
+        }
+        let pad_to = match self.ragged {
+            Some((width, RaggedPolicy::Pad)) => Some(width.max(self.pad_short_rows.unwrap_or(0))),
+            Some((width, RaggedPolicy::Truncate)) => {
+                ranges.truncate(width);
+                self.pad_short_rows
+            }
+            _ => self.pad_short_rows,
+        };
+        if let Some(width) = pad_to {
+            let end = ranges.last().map_or(0, |range| range.end);
+            while ranges.len() < width {
+                ranges.push(end..end);
+            }
+        }
+    }
+}
+
+/// Split a line into its fields without constructing a [Record](struct.Record.html).
+///
+/// The fields are split, unquoted, unescaped, trimmed and padded with exactly the same rules
+/// that are used for records, which is handy for lines from other sources like databases or
+/// message queues. Null tokens are not taken into account because the fields are returned as
+/// plain strings. The fields are borrowed from `line`, unless they have to be decoded because
+/// the line has quoted or escaped fields.
+///
+/// ```
+/// use tabfile::{split_line, Dialect, TrimMode};
+///
+/// let dialect = Dialect::new().separator(';').trim_fields(TrimMode::Both);
+/// let fields: Vec<_> = split_line("a ; b;c\n", &dialect).collect();
+/// assert_eq!(fields, vec!["a", "b", "c"]);
+///
+/// let dialect = Dialect::new().separator(';').quote_character('"');
+/// let fields: Vec<_> = split_line("\"say \"\"hi\"\"\";b\n", &dialect).collect();
+/// assert_eq!(fields, vec!["say \"hi\"", "b"]);
+/// ```
+pub fn split_line<'a>(line: &'a str, dialect: &Dialect) -> impl Iterator<Item = Cow<'a, str>> {
+    let mut ranges = Vec::new();
+    let fields: Vec<Cow<'a, str>> = match dialect.split_borrowed(line, &mut ranges) {
+        Some(decoded) => ranges
+            .into_iter()
+            .map(|range| Cow::Owned(decoded[range].to_string()))
+            .collect(),
+        None => ranges
+            .into_iter()
+            .map(|range| Cow::Borrowed(&line[range]))
+            .collect(),
+    };
+    fields.into_iter()
+}
+
+fn trim(line: &str, ranges: &mut [Range<usize>], mode: TrimMode, whitespace: Whitespace) {
+    if whitespace == Whitespace::Unicode {
+        return trim_unicode(line, ranges, mode);
+    }
+    let line = line.as_bytes();
+    for range in ranges {
+        if let TrimMode::Start | TrimMode::Both = mode {
+            while range.start < range.end && line[range.start].is_ascii_whitespace() {
+                range.start += 1;
+            }
+        }
+        if let TrimMode::End | TrimMode::Both = mode {
+            while range.start < range.end && line[range.end - 1].is_ascii_whitespace() {
+                range.end -= 1;
+            }
+        }
+    }
+}
+
+fn trim_unicode(line: &str, ranges: &mut [Range<usize>], mode: TrimMode) {
+    for range in ranges {
+        let field = &line[range.clone()];
+        if let TrimMode::Start | TrimMode::Both = mode {
+            range.start += field.len() - field.trim_start().len();
+        }
+        if let TrimMode::End | TrimMode::Both = mode {
+            range.end = range.start + line[range.clone()].trim_end().len();
+        }
+    }
+}
+
+/// A push-based parser for data that arrives in arbitrary chunks.
+///
+/// Chunks do not need to be aligned to line boundaries: incomplete lines are kept until the rest
+/// of the line arrives with a later chunk. Comment and empty lines are filtered out according to
+/// the dialect and line numbers and byte offsets are counted from the first chunk.
+///
+/// Like the items of [RowIterator](crate::RowIterator), every record is a result of its own,
+/// so a line that is not valid UTF-8 or has the wrong number of fields does not affect the
+/// lines around it.
+///
+/// ```
+/// use tabfile::parse::{Dialect, Parser};
+///
+/// let mut parser = Parser::new(Dialect::new());
+/// assert!(parser.feed(b"foo\tb").is_empty());
+/// let records = parser.feed(b"ar\nbaz");
+/// assert_eq!(records[0].as_ref().unwrap().fields(), vec!["foo", "bar"]);
+/// let records = parser.finish();
+/// assert_eq!(records[0].as_ref().unwrap().fields(), vec!["baz"]);
+/// ```
+pub struct Parser {
+    dialect: Dialect,
+    buffer: Vec<u8>,
+    next_line_number: usize,
+    next_byte_offset: u64,
+}
+
+impl Parser {
+    /// Create a parser that splits lines according to `dialect`
+    pub fn new(dialect: Dialect) -> Parser {
+        Parser {
+            dialect,
+            buffer: Vec::new(),
+            next_line_number: 0,
+            next_byte_offset: 0,
+        }
+    }
+
+    /// Add a chunk of data and get the records of all lines that are complete now
+    ///
+    /// A line that is not valid UTF-8 is an error of kind `InvalidData` in place of its record.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Result<Record, Error>> {
+        let terminator = self.dialect.terminator;
+        let end = match chunk.iter().rposition(|&byte| byte == terminator) {
+            Some(position) => self.buffer.len() + position + 1,
+            None => {
+                self.buffer.extend_from_slice(chunk);
+                return Vec::new();
+            }
+        };
+        self.buffer.extend_from_slice(chunk);
+        let complete: Vec<u8> = self.buffer.drain(..end).collect();
+        self.parse(&complete, false)
+    }
+
+    /// Get the record of the last line if it was not terminated by a newline
+    ///
+    /// The record is an error of kind `InvalidData` if a quoted field is still open.
+    pub fn finish(mut self) -> Vec<Result<Record, Error>> {
+        let rest = std::mem::take(&mut self.buffer);
+        self.parse(&rest, true)
+    }
+
+    /// Parse complete lines, keeping a record with an open quoted field for the next chunk
+    fn parse(&mut self, complete: &[u8], finishing: bool) -> Vec<Result<Record, Error>> {
+        let terminator = self.dialect.terminator;
+        let not_utf8 = |line_number: usize| {
+            code::invalid_data(
+                ErrorCode::InvalidUtf8,
+                format!("line {} is not valid UTF-8", line_number),
+            )
+        };
+        let mut records = Vec::new();
+        let mut lines = complete.split_inclusive(|&byte| byte == terminator);
+        'lines: while let Some(line) = lines.next() {
+            let byte_offset = self.next_byte_offset;
+            self.next_byte_offset += line.len() as u64;
+            self.next_line_number += 1;
+            let line_number = self.next_line_number;
+            let line = match std::str::from_utf8(line) {
+                Ok(line) => line,
+                Err(_) => {
+                    records.push(Err(not_utf8(line_number)));
+                    continue;
+                }
+            };
+            if !self.dialect.is_data_line(line) {
+                continue;
+            }
+            let mut line = line.to_string();
+            while self.dialect.has_open_quote(&line) {
+                match lines.next() {
+                    Some(next) => {
+                        self.next_byte_offset += next.len() as u64;
+                        self.next_line_number += 1;
+                        match std::str::from_utf8(next) {
+                            Ok(next) => line.push_str(next),
+                            Err(_) => {
+                                records.push(Err(not_utf8(self.next_line_number)));
+                                continue 'lines;
+                            }
+                        }
+                    }
+                    None if finishing => {
+                        records.push(Err(code::invalid_data(
+                            ErrorCode::UnclosedQuote,
+                            format!("line {}: unclosed quote", line_number),
+                        )));
+                        return records;
+                    }
+                    None => {
+                        // wait for the rest of the quoted field
+                        let mut rest = line.into_bytes();
+                        rest.append(&mut self.buffer);
+                        self.buffer = rest;
+                        self.next_line_number = line_number - 1;
+                        self.next_byte_offset = byte_offset;
+                        return records;
+                    }
+                }
+            }
+            let record = self
+                .dialect
+                .record(line, Vec::new(), line_number, byte_offset, None);
+            records.push(self.dialect.check_width(&record).map(|_| record));
+        }
+        records
+    }
+}
+
+/// Get `line` without the line terminator `terminator` and a line break in front of it
+fn content(line: &str, terminator: u8) -> &str {
+    let line = match terminator {
+        b'\n' => line,
+        terminator => line.strip_suffix(terminator as char).unwrap_or(line),
+    };
+    line.trim_end_matches(['\r', '\n'])
+}
+
+/// Get the length of the line without its terminator or anything after the first `'\r'`
+fn content_end(line: &str) -> usize {
+    let newline = line.find('\n').unwrap_or(line.len());
+    line[..newline].find('\r').unwrap_or(newline) // no tolerance for multiline strings
+}
+
+fn split_char(line: &str, separator: char, limit: usize, ranges: &mut Vec<Range<usize>>) {
+    if separator.is_ascii() {
+        return split_byte(line, separator as u8, limit, ranges);
+    }
+    let mut slice_start = 0;
+    let mut slice_end = 0;
+    let mut seen_newline = false;
+    for c in line.chars() {
+        if c == separator {
+            ranges.push(slice_start..slice_end);
+            if ranges.len() == limit {
+                return;
+            }
+            slice_start = slice_end + c.len_utf8();
+        } else if c == '\n' || c == '\r' {
+            seen_newline = true;
+            ranges.push(slice_start..slice_end);
+            break; // no tolerance for multiline strings
+        }
+        slice_end += c.len_utf8();
+    }
+    if !seen_newline {
+        ranges.push(slice_start..line.len())
+    }
+}
+
+/// Fast path of `split_char` for ASCII separators
+///
+/// An ASCII byte never occurs inside a multi-byte UTF-8 sequence, so the line can be searched
+/// byte by byte. With the `memchr` feature the search uses SIMD instructions.
+fn split_byte(line: &str, separator: u8, limit: usize, ranges: &mut Vec<Range<usize>>) {
+    let bytes = line.as_bytes();
+    #[cfg(feature = "memchr")]
+    let positions = memchr::memchr3_iter(separator, b'\n', b'\r', bytes);
+    #[cfg(not(feature = "memchr"))]
+    let positions = bytes
+        .iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == separator || byte == b'\n' || byte == b'\r')
+        .map(|(position, _)| position);
+    let mut slice_start = 0;
+    for position in positions {
+        ranges.push(slice_start..position);
+        if bytes[position] != separator || ranges.len() == limit {
+            return; // no tolerance for multiline strings
+        }
+        slice_start = position + 1;
+    }
+    ranges.push(slice_start..bytes.len());
+}
+
+/// Split a line with quoted fields, which may contain the separator and line breaks
+///
+/// A field is quoted if it starts with `quote` and ends at the next single `quote`, while two
+/// quotes in a row stand for one quote. The ranges include the quotes. Returns whether the last
+/// field is still open at the end of `line`.
+fn split_quoted(
+    line: &str,
+    separator: &str,
+    quote: char,
+    limit: usize,
+    ranges: &mut Vec<Range<usize>>,
+) -> bool {
+    let mut field_start = 0;
+    let mut in_quotes = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                if chars.peek().map(|&(_, next)| next) == Some(quote) {
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+        } else if position == field_start && c == quote {
+            in_quotes = true;
+        } else if c == '\n' || c == '\r' {
+            ranges.push(field_start..position);
+            return false; // the line break is not part of a quoted field
+        } else if line[position..].starts_with(separator) {
+            ranges.push(field_start..position);
+            if ranges.len() == limit {
+                return false;
+            }
+            field_start = position + separator.len();
+            while chars.peek().is_some_and(|&(next, _)| next < field_start) {
+                chars.next();
+            }
+        }
+    }
+    ranges.push(field_start..line.len());
+    in_quotes
+}
+
+/// Build a line in which every field at `ranges` is replaced by what `decode` makes of it
+///
+/// The ranges are updated to point into the new line.
+fn rewrite_fields<F>(line: &str, ranges: &mut [Range<usize>], mut decode: F) -> String
+where
+    F: FnMut(&str, &mut String),
+{
+    let mut rewritten = String::with_capacity(line.len());
+    let mut previous_end = 0;
+    for range in ranges.iter_mut() {
+        // the separator before the field
+        rewritten.push_str(&line[previous_end..range.start]);
+        previous_end = range.end;
+        let start = rewritten.len();
+        decode(&line[range.clone()], &mut rewritten);
+        *range = start..rewritten.len();
+    }
+    rewritten.push_str(&line[previous_end..]);
+    rewritten
+}
+
+/// Build a line in which the quoted fields at `ranges` are replaced by their content
+fn unquote(line: &str, ranges: &mut [Range<usize>], quote: char) -> String {
+    rewrite_fields(line, ranges, |field, unquoted| {
+        match field.strip_prefix(quote) {
+            Some(quoted) => {
+                let mut chars = quoted.chars().peekable();
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == quote && !closed {
+                        if chars.peek() == Some(&quote) {
+                            chars.next();
+                            unquoted.push(quote);
+                        } else {
+                            closed = true;
+                        }
+                    } else {
+                        unquoted.push(c);
+                    }
+                }
+            }
+            None => unquoted.push_str(field),
+        }
+    })
+}
+
+/// Build a line in which the backslash escape sequences of the fields at `ranges` are decoded
+///
+/// A field that is exactly `\N` is kept, because PostgreSQL uses it as null token.
+fn unescape(line: &str, ranges: &mut [Range<usize>]) -> String {
+    rewrite_fields(line, ranges, |field, unescaped| {
+        if field == "\\N" {
+            return unescaped.push_str(field);
+        }
+        let mut chars = field.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('0') => unescaped.push('\0'),
+                Some('b') => unescaped.push('\u{8}'),
+                Some('f') => unescaped.push('\u{c}'),
+                Some('v') => unescaped.push('\u{b}'),
+                // any other character stands for itself
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        }
+    })
+}
+
+fn split_str(line: &str, separator: &str, limit: usize, ranges: &mut Vec<Range<usize>>) {
+    let end = content_end(line);
+    let mut slice_start = 0;
+    for (position, _) in line[..end].match_indices(separator) {
+        ranges.push(slice_start..position);
+        if ranges.len() == limit {
+            return;
+        }
+        slice_start = position + separator.len();
+    }
+    ranges.push(slice_start..end);
+}
+
+fn split_whitespace(line: &str, whitespace: Whitespace, ranges: &mut Vec<Range<usize>>) {
+    let end = content_end(line);
+    let mut slice_start = None;
+    for (position, c) in line[..end].char_indices() {
+        let is_whitespace = match whitespace {
+            Whitespace::Ascii => c == ' ' || c == '\t',
+            Whitespace::Unicode => c.is_whitespace(),
+        };
+        match (is_whitespace, slice_start) {
+            (true, Some(start)) => {
+                ranges.push(start..position);
+                slice_start = None;
+            }
+            (false, None) => slice_start = Some(position),
+            _ => {}
+        }
+    }
+    if let Some(start) = slice_start {
+        ranges.push(start..end);
+    }
+}
+
+#[cfg(feature = "regex")]
+fn split_regex(line: &str, separator: &regex::Regex, ranges: &mut Vec<Range<usize>>) {
+    let end = content_end(line);
+    let mut slice_start = 0;
+    for found in separator.find_iter(&line[..end]) {
+        ranges.push(slice_start..found.start());
+        slice_start = found.end();
+    }
+    ranges.push(slice_start..end);
+}
+
+/// One line from a tab-separated file.
+///
+/// A `Record` gives you access to the original line as well as the individual fields of the
+/// line. You can keep ownership of the `Record` even if you continue looping over the
+/// [Tabfile](crate::Tabfile). Records are the logical view of a file, see
+/// [PhysicalLine](crate::PhysicalLine) for the lines as they are stored.
+pub struct Record {
+    line_number: usize,
+    byte_offset: u64,
+    line: String,
+    ranges: Vec<Range<usize>>,
+    pool: Option<Arc<RecordPool>>,
+    null_tokens: Option<Arc<[String]>>,
+    /// The terminator of the line, which is kept by `to_line`
+    terminator: u8,
+}
+
+impl Drop for Record {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.give_back(
+                std::mem::take(&mut self.line),
+                std::mem::take(&mut self.ranges),
+            );
+        }
+    }
+}
+
+impl Clone for Record {
+    /// Copy the line and the positions of the fields
+    ///
+    /// If the record comes from a pool, the buffers of the copy are returned to the same pool.
+    fn clone(&self) -> Record {
+        Record {
+            line_number: self.line_number,
+            byte_offset: self.byte_offset,
+            line: self.line.clone(),
+            ranges: self.ranges.clone(),
+            pool: self.pool.clone(),
+            null_tokens: self.null_tokens.clone(),
+            terminator: self.terminator,
+        }
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("line_number", &self.line_number)
+            .field("byte_offset", &self.byte_offset)
+            .field("fields", &self.fields())
+            .finish()
+    }
+}
+
+/// Records are equal if they have the same fields, regardless of where they were read from
+impl PartialEq for Record {
+    fn eq(&self, other: &Record) -> bool {
+        self.len() == other.len() && self.into_iter().eq(other)
+    }
+}
+
+impl Eq for Record {}
+
+impl Hash for Record {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for field in self {
+            field.hash(state);
+        }
+    }
+}
+
+impl Index<usize> for Record {
+    type Output = str;
+
+    /// Get the field at position `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record has no field at `index`. Use
+    /// [get_opt](struct.Record.html#method.get_opt) if the field may be missing.
+    fn index(&self, index: usize) -> &str {
+        &self.line[self.ranges[index].clone()]
+    }
+}
+
+impl<'a> IntoIterator for &'a Record {
+    type Item = &'a str;
+    type IntoIter = Fields<'a>;
+    fn into_iter(self) -> Fields<'a> {
+        Fields {
+            line: &self.line,
+            ranges: self.ranges.iter(),
+        }
+    }
+}
+
+/// Iterator over the fields of a [Record](struct.Record.html).
+///
+/// The fields are sliced from the line on the fly, so iterating does not allocate.
+pub struct Fields<'a> {
+    line: &'a str,
+    ranges: slice::Iter<'a, Range<usize>>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<&'a str> {
+        self.ranges.next().map(|range| &self.line[range.clone()])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ranges.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Fields<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.ranges
+            .next_back()
+            .map(|range| &self.line[range.clone()])
+    }
+}
+
+impl<'a> ExactSizeIterator for Fields<'a> {}
+
+impl Default for Record {
+    /// Create a record without any fields, e.g. as a buffer for
+    /// [RowIterator::read_record_into](crate::RowIterator::read_record_into)
+    fn default() -> Record {
+        Record {
+            line_number: 0,
+            byte_offset: 0,
+            line: String::new(),
+            ranges: Vec::new(),
+            pool: None,
+            null_tokens: None,
+            terminator: b'\n',
+        }
+    }
+}
+
+impl Record {
+    /// Build a record from `fields`, e.g. for rows that are not read from a file.
+    ///
+    /// The [line](#method.line) of the record is the fields joined with `separator`, without a
+    /// line terminator. The fields are taken as they are, so a field may contain the
+    /// separator and is still a single field, see [to_line](#method.to_line) for writing such
+    /// records. The line number and the byte offset are zero.
+    ///
+    /// ```
+    /// use tabfile::Record;
+    ///
+    /// let record = Record::from_fields(&["chr1", "1234", "A"], "\t");
+    /// assert_eq!(record.line(), "chr1\t1234\tA");
+    /// assert_eq!(&record[1], "1234");
+    ///
+    /// let defaults = vec![String::from("unknown"), String::from("0")];
+    /// assert_eq!(Record::from_fields(defaults, ",").fields(), vec!["unknown", "0"]);
+    /// ```
+    pub fn from_fields<I, S>(fields: I, separator: &str) -> Record
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut line = String::new();
+        let mut ranges = Vec::new();
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                line.push_str(separator);
+            }
+            let start = line.len();
+            line.push_str(field.as_ref());
+            ranges.push(start..line.len());
+        }
+        Record {
+            line_number: 0,
+            byte_offset: 0,
+            line,
+            ranges,
+            pool: None,
+            null_tokens: None,
+            terminator: b'\n',
+        }
+    }
+
+    /// Get the individual (tab-)separated fields of a line
+    ///
+    /// This method generates string slices on the fly from precomputed positions. Use
+    /// [iter](#method.iter) to avoid allocating the vector.
+    pub fn fields(&self) -> Vec<&str> {
+        self.iter().collect()
+    }
+
+    /// Iterate over the fields of a line without allocating
+    ///
+    /// ```
+    /// use tabfile::parse::Dialect;
+    ///
+    /// let record = Dialect::new().parse_line("chr1\t1234\tA\tG", 1);
+    /// let alleles: Vec<&str> = record.iter().skip(2).collect();
+    /// assert_eq!(alleles, vec!["A", "G"]);
+    /// ```
+    pub fn iter(&self) -> Fields<'_> {
+        self.into_iter()
+    }
+
+    /// Get the field at position `index`
+    ///
+    /// An empty field like the middle one in `a\t\tb` is returned as `Some("")` whereas a field
+    /// that is missing because the row is too short is returned as `None`. See
+    /// [Tabfile::pad_short_rows](crate::Tabfile::pad_short_rows) if short rows should
+    /// be treated as if they had empty fields instead. Fields that match one of the
+    /// [null tokens](crate::Tabfile::null_tokens) are returned as `None` as well.
+    pub fn get_opt(&self, index: usize) -> Option<&str> {
+        let field = &self.line[self.ranges.get(index)?.clone()];
+        match &self.null_tokens {
+            Some(tokens) if tokens.iter().any(|token| token == field) => None,
+            _ => Some(field),
+        }
+    }
+
+    /// Get the original line
+    ///
+    /// The line is unchanged except for [quoted](crate::Tabfile::quote_character) and
+    /// [escaped](crate::Tabfile::unescape) fields, which are decoded.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Get the original line number
+    ///
+    /// Please note that the line number counts start from one. Lines that were dropped because of
+    /// `skip_lines`, a comment character or `skip_empty_lines` are still counted, so the number
+    /// always refers to the physical position of the line in the file.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the position of the first byte of the line in the file
+    ///
+    /// The offset can be passed to [Tabfile::seek_to](crate::Tabfile::seek_to) to
+    /// resume reading at this line.
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// Get the number of fields
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Take the line buffer out of the record, which leaves the record without fields
+    pub(crate) fn take_line(&mut self) -> String {
+        self.ranges.clear();
+        std::mem::take(&mut self.line)
+    }
+
+    /// Replace the field at `index` with `value` in the line and move the following fields
+    ///
+    /// Fields that are selected more than once share their text, so all copies change.
+    pub(crate) fn replace_field(&mut self, index: usize, value: &str) {
+        let old = self.ranges[index].clone();
+        self.line.replace_range(old.clone(), value);
+        let new = old.start..old.start + value.len();
+        for range in self.ranges.iter_mut() {
+            // empty fields at the same position, e.g. padding, are different fields
+            if *range == old && !old.is_empty() {
+                *range = new.clone();
+            } else if range.start >= old.end {
+                *range = range.start - old.end + new.end..range.end - old.end + new.end;
+            }
+        }
+        self.ranges[index] = new;
+    }
+
+    /// Replace the field at `index` with `value`.
+    ///
+    /// The line is edited in place, so the other fields, the separators and the line terminator
+    /// stay exactly as they were and [line](#method.line) returns the edited line. If `value`
+    /// contains the separator or a line break, the line can no longer be split correctly, so
+    /// write such records with [to_line](#method.to_line) instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record has no field at `index`.
+    ///
+    /// ```
+    /// use tabfile::parse::Dialect;
+    ///
+    /// let mut record = Dialect::new().parse_line("chr1\t1234\tA\tG\n", 1);
+    /// record.set_field(0, "1");
+    /// assert_eq!(record.line(), "1\t1234\tA\tG\n");
+    /// assert_eq!(&record[1], "1234");
+    /// ```
+    pub fn set_field(&mut self, index: usize, value: &str) {
+        assert!(
+            index < self.ranges.len(),
+            "the record has no field at index {}",
+            index
+        );
+        self.replace_field(index, value);
+    }
+
+    /// Join the fields with `separator` into a line that can be read again.
+    ///
+    /// Fields that contain the separator, a double quote, a line break or the record terminator
+    /// are put in double quotes, with the double quotes inside doubled, so the line can be read
+    /// with [Tabfile::quote_character('"')](crate::Tabfile::quote_character). The line ends
+    /// like the original one, i.e. with `"\n"`, with `"\r\n"`, with the
+    /// [record terminator](crate::Tabfile::record_terminator) of the file or without a
+    /// terminator at the end of a file. Text between the fields that is not part of them, like the quotes of quoted
+    /// fields or the columns left out by
+    /// [Tabfile::select_columns](crate::Tabfile::select_columns), is dropped.
+    ///
+    /// ```
+    /// use tabfile::parse::Dialect;
+    ///
+    /// let mut record = Dialect::new().parse_line("a\tb\tc\n", 1);
+    /// record.set_field(1, "two\twords");
+    /// assert_eq!(record.to_line("\t"), "a\t\"two\twords\"\tc\n");
+    /// assert_eq!(record.to_line(","), "a,two\twords,c\n");
+    /// ```
+    pub fn to_line(&self, separator: &str) -> String {
+        let mut line = String::with_capacity(self.line.len() + 2);
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                line.push_str(separator);
+            }
+            let field = &self.line[range.clone()];
+            let needs_quotes = (!separator.is_empty() && field.contains(separator))
+                || field.contains(['"', '\r', '\n', self.terminator as char]);
+            if needs_quotes {
+                line.push('"');
+                line.push_str(&field.replace('"', "\"\""));
+                line.push('"');
+            } else {
+                line.push_str(field);
+            }
+        }
+        let fields_end = self.ranges.iter().map(|range| range.end).max().unwrap_or(0);
+        let content_end = content(&self.line, self.terminator).len();
+        line.push_str(&self.line[fields_end.max(content_end)..]);
+        line
+    }
+
+    /// Get pointers and lengths of all fields at once
+    ///
+    /// This is meant for FFI bindings that want to hand the fields to C or Python without calling
+    /// back into Rust for every field. `ptrs[i]` and `lens[i]` describe the UTF-8 bytes of the
+    /// `i`th field, which are not NUL-terminated. The pointers are only valid for as long as the
+    /// `Record` is alive.
+    pub fn as_struct_of_slices(&self) -> FieldSlices {
+        let base = self.line.as_ptr();
+        FieldSlices {
+            ptrs: self
+                .ranges
+                .iter()
+                .map(|range| base.wrapping_add(range.start))
+                .collect(),
+            lens: self.ranges.iter().map(|range| range.len()).collect(),
+        }
+    }
+}
+
+/// Parallel pointer and length arrays for the fields of a [Record](struct.Record.html).
+///
+/// See [Record::as_struct_of_slices](struct.Record.html#method.as_struct_of_slices).
+pub struct FieldSlices {
+    /// Pointer to the first byte of each field
+    pub ptrs: Vec<*const u8>,
+    /// Length of each field in bytes
+    pub lens: Vec<usize>,
+}
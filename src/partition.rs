@@ -0,0 +1,165 @@
+//! Splitting a file into byte ranges that can be read independently
+
+use crate::{Dialect, LineEndingPolicy, Record, RowIterator, Tabfile};
+use std::io::{BufRead, Error, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A range of whole lines of a file, see
+/// [Tabfile::partitions](struct.Tabfile.html#method.partitions).
+///
+/// A partition can be sent to another thread and opened there, it opens the file again.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    path: Arc<PathBuf>,
+    start: u64,
+    end: u64,
+    dialect: Dialect,
+    max_line_length: Option<usize>,
+    auto_fix: bool,
+    line_ending_policy: LineEndingPolicy,
+}
+
+impl Partition {
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the byte offset of the first line of the partition
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Get the byte offset behind the last line of the partition
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Get the number of bytes of the partition
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Check whether the partition has no bytes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Open the file and iterate over the records of the partition.
+    ///
+    /// Line numbers are counted relative to the start of the partition, i.e. the first line
+    /// of the partition has the line number one, while the byte offsets refer to the file.
+    pub fn open(&self) -> Result<PartitionRecords, Error> {
+        let mut tabfile = Tabfile::open(self.path.as_path())?
+            .dialect(self.dialect.clone())
+            .auto_fix(self.auto_fix)
+            .line_endings(self.line_ending_policy);
+        tabfile.max_line_length = self.max_line_length;
+        Ok(PartitionRecords {
+            rows: tabfile.seek_to(self.start)?.into_iter(),
+            end: self.end,
+        })
+    }
+}
+
+/// Iterator over the records of a [Partition](struct.Partition.html).
+pub struct PartitionRecords {
+    rows: RowIterator,
+    end: u64,
+}
+
+impl Iterator for PartitionRecords {
+    type Item = Result<Record, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.rows.next()? {
+            Ok(record) if record.byte_offset() >= self.end => None,
+            item => Some(item),
+        }
+    }
+}
+
+impl Tabfile {
+    /// Split the file into at most `count` byte ranges of about the same size that start at
+    /// the beginning of a line, e.g. to process the parts of a huge file on several cores.
+    ///
+    /// Every [Partition](struct.Partition.html) opens the file again, so the partitions can be
+    /// read at the same time from different threads. Together, the partitions contain every
+    /// record exactly once. Only the lines around the boundaries are read to align them, so
+    /// splitting is fast even for files of many gigabytes. Ranges that would be empty because
+    /// of very long lines are left out.
+    ///
+    /// The partitions use the dialect, the maximum line length, the line ending policy and
+    /// `auto_fix` of this `Tabfile`. The lines removed by `skip_lines` and `skip_lines_while`
+    /// come before the first partition, but `skip_footer` and the empty input policy are not
+    /// applied. Quoted fields must not contain line breaks, because a partition could start
+    /// inside of them. Iterating the `Tabfile` afterwards starts at the beginning again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is zero.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "id{}\t{}", i, i * i).unwrap(); }
+    /// use std::thread;
+    /// use tabfile::Tabfile;
+    ///
+    /// let partitions = Tabfile::open(file.path()).unwrap().partitions(4).unwrap();
+    /// assert_eq!(partitions.len(), 4);
+    /// let workers: Vec<_> = partitions
+    ///     .into_iter()
+    ///     .map(|partition| thread::spawn(move || partition.open().unwrap().count()))
+    ///     .collect();
+    /// let total: usize = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+    /// assert_eq!(total, 1000);
+    /// ```
+    pub fn partitions(&mut self, count: usize) -> Result<Vec<Partition>, Error> {
+        assert!(count > 0, "the number of partitions must be positive");
+        let result = self.partition_offsets(count);
+        self.reader.seek(SeekFrom::Start(self.start_offset))?;
+        let path = Arc::new(self.path.clone());
+        let partitions = result?
+            .windows(2)
+            .filter(|range| range[0] < range[1])
+            .map(|range| Partition {
+                path: Arc::clone(&path),
+                start: range[0],
+                end: range[1],
+                dialect: self.dialect.clone(),
+                max_line_length: self.max_line_length,
+                auto_fix: self.auto_fix,
+                line_ending_policy: self.line_ending_policy,
+            })
+            .collect();
+        Ok(partitions)
+    }
+
+    /// Compute the boundaries of the partitions, leaving the reader anywhere
+    fn partition_offsets(&mut self, count: usize) -> Result<Vec<u64>, Error> {
+        let start = self.skipped_offset(&mut self.line_reader())?;
+        let end = self.reader.seek(SeekFrom::End(0))?.max(start);
+        let mut offsets = vec![start];
+        let mut partial = Vec::new();
+        for i in 1..count {
+            let target = start + ((end - start) as u128 * i as u128 / count as u128) as u64;
+            let previous = *offsets.last().expect("the first offset is the start");
+            let offset = if target <= previous {
+                previous
+            } else {
+                // move to the line after the one that contains `target`
+                self.reader.seek(SeekFrom::Start(target - 1))?;
+                partial.clear();
+                let rest = self
+                    .reader
+                    .read_until(self.dialect.terminator, &mut partial)?;
+                target - 1 + rest as u64
+            };
+            offsets.push(offset);
+        }
+        offsets.push(end);
+        Ok(offsets)
+    }
+}
@@ -0,0 +1,69 @@
+//! Decoding percent-encoded fields
+
+use crate::Record;
+use std::borrow::Cow;
+
+/// Get the value of an ASCII hex digit
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// Decode the `%XX` sequences of `text` as described in RFC 3986
+///
+/// Sequences that are not followed by two hex digits are kept. If the decoded bytes are not
+/// valid UTF-8, `text` is returned unchanged.
+pub(crate) fn percent_decode(text: &str) -> Cow<'_, str> {
+    if !text.contains('%') {
+        return Cow::Borrowed(text);
+    }
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        let escaped = match bytes.get(position + 1..position + 3) {
+            Some(&[high, low]) if bytes[position] == b'%' => hex_value(high).zip(hex_value(low)),
+            _ => None,
+        };
+        match escaped {
+            Some((high, low)) => {
+                decoded.push(high << 4 | low);
+                position += 3;
+            }
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+    if decoded.len() == bytes.len() {
+        // nothing was decoded
+        return Cow::Borrowed(text);
+    }
+    match String::from_utf8(decoded) {
+        Ok(decoded) => Cow::Owned(decoded),
+        Err(_) => Cow::Borrowed(text),
+    }
+}
+
+impl Record {
+    /// Get a field with its percent-encoded characters decoded.
+    ///
+    /// Formats like GFF3 and many web exports write tabs, line breaks and other special
+    /// characters inside fields as `%09`, `%0A` and so on. The field is decoded only when this
+    /// method is called, and without copying if it does not contain an encoded character. A `%`
+    /// that is not followed by two hex digits is kept, and so is the whole field if the decoded
+    /// bytes are not valid UTF-8. Like [get_opt](#method.get_opt), this returns `None` for missing and
+    /// null fields.
+    ///
+    /// ```
+    /// use tabfile::Dialect;
+    ///
+    /// let record = Dialect::new().parse_line("Note=a%3Bb%09c\t100%", 1);
+    /// assert_eq!(record.field_decoded(0).unwrap(), "Note=a;b\tc");
+    /// assert_eq!(record.field_decoded(1).unwrap(), "100%");
+    /// assert_eq!(record.field_decoded(2), None);
+    /// ```
+    pub fn field_decoded(&self, index: usize) -> Option<Cow<'_, str>> {
+        self.get_opt(index).map(percent_decode)
+    }
+}
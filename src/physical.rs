@@ -0,0 +1,173 @@
+//! Reading the lines of a file exactly as they are stored
+
+use crate::footer::Lookahead;
+use crate::Tabfile;
+use std::io::Error;
+
+/// How a physical line is treated when records are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    /// The line is skipped by [Tabfile::skip_lines](crate::Tabfile::skip_lines) or
+    /// [Tabfile::skip_footer](crate::Tabfile::skip_footer)
+    Skipped,
+    /// The line starts with one of the [comment prefixes](crate::Tabfile::comment_prefixes)
+    Comment,
+    /// The line is empty and [skipped](crate::Tabfile::skip_empty_lines)
+    Empty,
+    /// The line becomes a [Record](crate::Record)
+    Data,
+    /// The line continues the record of a previous data line with an open
+    /// [quoted field](crate::Tabfile::quote_character)
+    Continuation,
+}
+
+/// One line of a file including its line terminator.
+///
+/// Physical lines are what a file consists of, whereas a [Record](crate::Record) is the logical
+/// unit of data that is produced from the data lines. Use physical lines for tools that have to
+/// reproduce the file byte for byte, e.g. formatters and linters. A record is produced from
+/// one data line with the same line number and byte offset, unless a quoted field continues
+/// on the following lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhysicalLine {
+    text: String,
+    content_length: usize,
+    line_number: usize,
+    byte_offset: u64,
+    kind: LineKind,
+}
+
+impl PhysicalLine {
+    /// Get the line including its terminator
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the line without its terminator
+    pub fn content(&self) -> &str {
+        &self.text[..self.content_length]
+    }
+
+    /// Get the line terminator, which is `"\n"`, `"\r\n"` or `""` for a last line without one
+    ///
+    /// With a [record terminator](crate::Tabfile::record_terminator) this is the terminator
+    /// byte, possibly followed by a line break.
+    pub fn terminator(&self) -> &str {
+        &self.text[self.content_length..]
+    }
+
+    /// Get the 1-based line number
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the position of the first byte of the line in the file
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// Get how the line is treated when records are read
+    pub fn kind(&self) -> LineKind {
+        self.kind
+    }
+}
+
+/// Iterator over all lines of a tab file.
+///
+/// See [Tabfile::physical_lines](crate::Tabfile::physical_lines).
+pub struct PhysicalLines {
+    tabfile: Tabfile,
+    lines_to_skip: usize,
+    in_preamble: bool,
+    footer: Lookahead,
+    open_record: Option<String>,
+    next_line_number: usize,
+    next_byte_offset: u64,
+}
+
+impl Iterator for PhysicalLines {
+    type Item = Result<PhysicalLine, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::new();
+        let (line_length, in_footer, rejected) =
+            match self.footer.read_line(&mut self.tabfile.reader, &mut text) {
+                Ok((0, _, _)) => return None,
+                Ok(read) => read,
+                Err(e) => return Some(Err(e)),
+            };
+        let byte_offset = self.next_byte_offset;
+        self.next_byte_offset += line_length as u64;
+        self.next_line_number += 1;
+        if let Some(rejected) = rejected {
+            let line_number = self.next_line_number;
+            return Some(Err(self.footer.reader.rejected(rejected, line_number)));
+        }
+        if self.in_preamble && self.lines_to_skip == 0 && !in_footer {
+            self.in_preamble = self.tabfile.in_preamble(&text);
+        }
+        let dialect = &self.tabfile.dialect;
+        let kind = if let Some(mut record) = self.open_record.take() {
+            record.push_str(&text);
+            if dialect.has_open_quote(&record) {
+                self.open_record = Some(record);
+            }
+            LineKind::Continuation
+        } else if self.lines_to_skip > 0 {
+            self.lines_to_skip -= 1;
+            LineKind::Skipped
+        } else if in_footer || self.in_preamble {
+            LineKind::Skipped
+        } else if dialect.is_data_line(&text) {
+            if dialect.has_open_quote(&text) {
+                self.open_record = Some(text.clone());
+            }
+            LineKind::Data
+        } else if dialect.is_comment(&text) {
+            LineKind::Comment
+        } else {
+            LineKind::Empty
+        };
+        Some(Ok(PhysicalLine {
+            content_length: dialect.content(&text).len(),
+            text,
+            line_number: self.next_line_number,
+            byte_offset,
+            kind,
+        }))
+    }
+}
+
+impl Tabfile {
+    /// Iterate over every line of the file without splitting or filtering it.
+    ///
+    /// Unlike the records, the physical lines include skipped, comment and empty lines as well
+    /// as the line terminators, so concatenating their texts reproduces the file. Every line is
+    /// classified with the options of this `Tabfile`, see [LineKind](enum.LineKind.html). Options
+    /// that modify the data like `auto_fix` are not applied.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# comment\r\n1\t2\r\n");
+    /// use tabfile::{LineKind, Tabfile};
+    ///
+    /// for line in Tabfile::open(file.path()).unwrap().physical_lines() {
+    ///     let line = line.unwrap();
+    ///     if line.kind() == LineKind::Data && line.terminator() == "\r\n" {
+    ///         println!("line {} has a Windows line ending", line.line_number());
+    ///     }
+    /// }
+    /// ```
+    pub fn physical_lines(self) -> PhysicalLines {
+        PhysicalLines {
+            lines_to_skip: self.skip_lines,
+            in_preamble: self.skip_while.is_some(),
+            footer: Lookahead::new(self.skip_footer, self.line_reader()),
+            open_record: None,
+            next_line_number: self.start_line_number,
+            next_byte_offset: self.start_offset,
+            tabfile: self,
+        }
+    }
+}
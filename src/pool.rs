@@ -0,0 +1,46 @@
+//! Recycling of record buffers
+
+use std::ops::Range;
+use std::sync::Mutex;
+
+type Buffers = (String, Vec<Range<usize>>);
+
+/// Buffers of dropped records that are waiting to be reused for new records.
+pub(crate) struct RecordPool {
+    capacity: usize,
+    buffers: Mutex<Vec<Buffers>>,
+}
+
+impl RecordPool {
+    pub(crate) fn new(capacity: usize) -> RecordPool {
+        RecordPool {
+            capacity,
+            buffers: Mutex::new(Vec::with_capacity(capacity)),
+        }
+    }
+
+    /// Get a pair of cleared buffers, either recycled or freshly allocated
+    pub(crate) fn take(&self) -> Buffers {
+        let recycled = match self.buffers.lock() {
+            Ok(mut buffers) => buffers.pop(),
+            Err(_) => None,
+        };
+        match recycled {
+            Some((mut line, mut ranges)) => {
+                line.clear();
+                ranges.clear();
+                (line, ranges)
+            }
+            None => (String::new(), Vec::new()),
+        }
+    }
+
+    /// Return buffers to the pool unless it is already full
+    pub(crate) fn give_back(&self, line: String, ranges: Vec<Range<usize>>) {
+        if let Ok(mut buffers) = self.buffers.lock() {
+            if buffers.len() < self.capacity {
+                buffers.push((line, ranges));
+            }
+        }
+    }
+}
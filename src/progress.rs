@@ -0,0 +1,54 @@
+//! Reporting how far the records of a file have been read
+
+use crate::Tabfile;
+
+/// A function that is called with the number of records and the fraction of the file read
+pub(crate) struct ProgressCallback {
+    pub(crate) every: usize,
+    pub(crate) callback: Box<dyn FnMut(usize, f64) + Send>,
+}
+
+impl Tabfile {
+    /// Call `callback` after every `every` records, e.g. to update a progress indicator.
+    ///
+    /// The callback gets the number of records read so far and the
+    /// [progress](struct.RowIterator.html#method.progress) of the iterator, a fraction from `0.0`
+    /// to `1.0`. Since the file size is only looked up once, a file that grows while it is
+    /// read reports a progress of `1.0` early. There is no extra call at the end of the file,
+    /// so call the callback yourself after the loop if the indicator needs to finish.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..10_000 { writeln!(file, "{}\tvalue", i).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let tsv_file = Tabfile::open(file.path())
+    ///     .unwrap()
+    ///     .on_progress(1000, |records, progress| {
+    ///         eprint!("\r{} records, {:.0}%", records, progress * 100.0);
+    ///     });
+    /// for record in tsv_file {
+    ///     let _record = record.unwrap();
+    /// }
+    /// ```
+    pub fn on_progress<F>(mut self, every: usize, callback: F) -> Self
+    where
+        F: FnMut(usize, f64) + Send + 'static,
+    {
+        assert!(
+            every > 0,
+            "the progress must be reported at least every record"
+        );
+        self.progress_callback = Some(ProgressCallback {
+            every,
+            callback: Box::new(callback),
+        });
+        self
+    }
+}
@@ -0,0 +1,80 @@
+//! Showing the progress of reading a file with a progress bar
+
+use crate::{Record, RowIterator, Tabfile};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Error;
+
+/// Iterator over the records of a file that moves a progress bar.
+///
+/// See [Tabfile::with_progress_bar](struct.Tabfile.html#method.with_progress_bar).
+pub struct ProgressBarRows {
+    rows: RowIterator,
+    bar: ProgressBar,
+}
+
+impl ProgressBarRows {
+    /// Get the progress bar, e.g. to set a message or to print lines above it
+    pub fn progress_bar(&self) -> &ProgressBar {
+        &self.bar
+    }
+
+    /// Get the underlying iterator
+    pub fn rows(&self) -> &RowIterator {
+        &self.rows
+    }
+}
+
+impl Iterator for ProgressBarRows {
+    type Item = Result<Record, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.rows.next();
+        self.bar.set_position(self.rows.byte_offset());
+        match &item {
+            None => self.bar.finish(),
+            Some(Err(_)) => self.bar.abandon(),
+            Some(Ok(_)) => {}
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the records while a progress bar shows how many bytes have been read.
+    ///
+    /// The length of the bar is the size of the file and its position is the
+    /// [byte offset](struct.RowIterator.html#method.byte_offset) behind the last record, so the
+    /// bar moves evenly even if the records have very different lengths. Templates of `style`
+    /// like `{bytes}`, `{total_bytes}`, `{bytes_per_sec}` and `{eta}` therefore refer to the
+    /// file. The bar is finished at the end of the file and abandoned at the first error. Like
+    /// every `indicatif` bar, it draws to the standard error and is hidden if that is not a
+    /// terminal.
+    ///
+    /// This method is only available with the `indicatif` feature.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "{}\tvalue", i).unwrap(); }
+    /// use indicatif::ProgressStyle;
+    /// use tabfile::Tabfile;
+    ///
+    /// let style = ProgressStyle::with_template("{bar:40} {bytes}/{total_bytes} {eta}").unwrap();
+    /// let rows = Tabfile::open(file.path()).unwrap().with_progress_bar(style);
+    /// let mut total = 0;
+    /// for record in rows {
+    ///     total += record.unwrap()[0].parse::<u64>().unwrap();
+    /// }
+    /// assert_eq!(total, 499_500);
+    /// ```
+    pub fn with_progress_bar(self, style: ProgressStyle) -> ProgressBarRows {
+        let rows = self.into_iter();
+        let bar = ProgressBar::new(rows.file_size).with_style(style);
+        bar.set_position(rows.byte_offset());
+        ProgressBarRows { rows, bar }
+    }
+}
@@ -0,0 +1,189 @@
+//! Replacing identifying values by tokens that can be mapped back
+
+use crate::code::{self, ErrorCode};
+use crate::Tabfile;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+/// Replaces the values of key columns by stable tokens and remembers the originals.
+///
+/// Every distinct value gets a token made of a prefix and a running number, e.g. `P1`, `P2` and
+/// so on, and the same value always gets the same token. The pseudonymized file can be shared
+/// while the token map, written with [write_map](#method.write_map), stays internal and allows
+/// the re-identification of the records. Loading an existing map with
+/// [load_map](#method.load_map) keeps the tokens stable across several deliveries.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::{tempdir, NamedTempFile};
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"patient\tvalue\nalice\t1\nbob\t2\nalice\t3\n");
+/// # let dir = tempdir().unwrap();
+/// # let map_path = dir.path().join("tokens.tsv");
+/// use tabfile::{Pseudonymizer, Tabfile};
+///
+/// let mut pseudonymizer = Pseudonymizer::new("P").header(true);
+/// let mut shared = Vec::new();
+/// let input = Tabfile::open(file.path()).unwrap();
+/// pseudonymizer.pseudonymize(input, &mut shared, &[0]).unwrap();
+/// assert_eq!(shared, b"patient\tvalue\nP1\t1\nP2\t2\nP1\t3\n");
+/// pseudonymizer.write_map(&map_path).unwrap();
+///
+/// let pseudonymizer = Pseudonymizer::load_map(&map_path, "P").unwrap();
+/// assert_eq!(pseudonymizer.original("P2"), Some("bob"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Pseudonymizer {
+    prefix: String,
+    header: bool,
+    tokens: HashMap<String, String>,
+    originals: Vec<String>,
+}
+
+impl Pseudonymizer {
+    /// Create a pseudonymizer whose tokens start with `prefix`
+    pub fn new(prefix: &str) -> Pseudonymizer {
+        Pseudonymizer {
+            prefix: prefix.to_string(),
+            ..Pseudonymizer::default()
+        }
+    }
+
+    /// Continue with the tokens of a map written by [write_map](#method.write_map)
+    ///
+    /// New values get tokens with numbers after the ones in the map. An error of kind
+    /// `InvalidData` is returned if a line of the map is not a token of this prefix followed by
+    /// a tab and the original value, or if the tokens are not numbered consecutively.
+    pub fn load_map<P: AsRef<Path>>(path: P, prefix: &str) -> Result<Pseudonymizer, Error> {
+        let mut pseudonymizer = Pseudonymizer::new(prefix);
+        let map = Tabfile::open(path)?
+            .comment_prefixes(&[])
+            .skip_empty_lines(false);
+        for record in map {
+            let record = record?;
+            let expected = pseudonymizer.next_token();
+            match record.fields()[..] {
+                [token, original] if token == expected => {
+                    pseudonymizer.insert(original);
+                }
+                _ => {
+                    return Err(code::invalid_data(
+                        ErrorCode::InvalidPseudonymMap,
+                        format!(
+                            "line {}: expected the token `{}` and an original value",
+                            record.line_number(),
+                            expected
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(pseudonymizer)
+    }
+
+    /// Leave the first record of every file unchanged because it is a header
+    ///
+    /// The default is `false`.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    fn next_token(&self) -> String {
+        format!("{}{}", self.prefix, self.originals.len() + 1)
+    }
+
+    fn insert(&mut self, value: &str) -> String {
+        let token = self.next_token();
+        self.tokens.insert(value.to_string(), token.clone());
+        self.originals.push(value.to_string());
+        token
+    }
+
+    /// Get the token for `value`, creating a new one for a value that was not seen before
+    pub fn token(&mut self, value: &str) -> &str {
+        if !self.tokens.contains_key(value) {
+            self.insert(value);
+        }
+        &self.tokens[value]
+    }
+
+    /// Get the original value of `token`
+    pub fn original(&self, token: &str) -> Option<&str> {
+        let number: usize = token.strip_prefix(&self.prefix)?.parse().ok()?;
+        let original = self.originals.get(number.checked_sub(1)?)?;
+        Some(original.as_str())
+    }
+
+    /// Copy the records of `input` to `output` with the fields at `columns` replaced by tokens.
+    ///
+    /// The records are written as they are in the file apart from the replaced fields, including
+    /// the separators and line terminators. Comment, skipped and empty lines are dropped. Records
+    /// that are too short to have a field at one of `columns` are written without replacing it.
+    /// Returns the number of records written.
+    pub fn pseudonymize<W: Write>(
+        &mut self,
+        input: Tabfile,
+        mut output: W,
+        columns: &[usize],
+    ) -> Result<usize, Error> {
+        let mut written = 0;
+        for record in input {
+            let mut record = record?;
+            if !(self.header && written == 0) {
+                for &column in columns {
+                    if let Some(value) = record.iter().nth(column) {
+                        let token = self.token(value).to_string();
+                        record.replace_field(column, &token);
+                    }
+                }
+            }
+            output.write_all(record.line().as_bytes())?;
+            written += 1;
+        }
+        output.flush()?;
+        Ok(written)
+    }
+
+    /// Write the map from tokens to original values to `path`.
+    ///
+    /// Every line holds a token and its original value, separated by a tab. The file is
+    /// replaced if it exists. On Unix a new file is only readable and writable by its owner,
+    /// since the map allows the re-identification of the pseudonymized data. An error of kind
+    /// `InvalidData` is returned if an original value contains a tab or a line break.
+    pub fn write_map<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut map = BufWriter::new(create_private(path.as_ref())?);
+        for (i, original) in self.originals.iter().enumerate() {
+            if original.contains(['\t', '\r', '\n']) {
+                return Err(code::invalid_data(
+                    ErrorCode::InvalidPseudonymMap,
+                    format!("the value `{}` cannot be written to the map", original),
+                ));
+            }
+            writeln!(map, "{}{}\t{}", self.prefix, i + 1, original)?;
+        }
+        map.flush()
+    }
+}
+
+#[cfg(unix)]
+fn create_private(path: &Path) -> Result<File, Error> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> Result<File, Error> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
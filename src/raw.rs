@@ -0,0 +1,77 @@
+//! Reading filtered lines without splitting them
+
+use crate::{RowIterator, Tabfile};
+use std::io::Error;
+
+/// The data lines of a tab file without any splitting.
+///
+/// See [Tabfile::raw_lines](struct.Tabfile.html#method.raw_lines).
+pub struct RawLines {
+    rows: RowIterator,
+    line: String,
+}
+
+impl RawLines {
+    /// Read the next data line into the internal buffer and return it without its terminator.
+    ///
+    /// The buffer is reused for every line, so this does not allocate once the buffer has grown
+    /// to the length of the longest line. Returns `None` at the end of the file.
+    pub fn next_line(&mut self) -> Result<Option<&str>, Error> {
+        match self.rows.next_data_line(&mut self.line)? {
+            Some(_) => Ok(Some(self.rows.tabfile.dialect.content(&self.line))),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the line number of the line returned last
+    pub fn line_number(&self) -> usize {
+        self.rows.line_number()
+    }
+
+    /// Get the byte offset of the line after the one returned last
+    pub fn byte_offset(&self) -> u64 {
+        self.rows.byte_offset()
+    }
+}
+
+impl Iterator for RawLines {
+    type Item = Result<String, Error>;
+    /// Get the next line as an owned `String`, which allocates unlike `next_line`
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line()
+            .map(|line| line.map(str::to_string))
+            .transpose()
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the data lines without splitting them into records.
+    ///
+    /// The lines are filtered exactly like for records, i.e. with `skip_lines`, comment prefixes,
+    /// `skip_empty_lines` and so on, but no [Record](struct.Record.html) is built, which is the
+    /// fastest way through a file for consumers that do their own parsing. Options that work on
+    /// fields like the separator or `trim_fields` have no effect. Use
+    /// [RawLines::next_line](struct.RawLines.html#method.next_line) to read without
+    /// allocating.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"# comment\n1;foo\n\n2;bar\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut lines = Tabfile::open(file.path()).unwrap().raw_lines();
+    /// let mut total = 0;
+    /// while let Some(line) = lines.next_line().unwrap() {
+    ///     total += line.split(';').next().unwrap().parse::<u32>().unwrap();
+    /// }
+    /// assert_eq!(total, 3);
+    /// ```
+    pub fn raw_lines(self) -> RawLines {
+        RawLines {
+            rows: self.into_iter(),
+            line: String::new(),
+        }
+    }
+}
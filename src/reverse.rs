@@ -0,0 +1,353 @@
+//! Reading the records of a file from the end towards the start
+
+use crate::iter::IterItem;
+use crate::lines::{LineReader, Rejected};
+use crate::{Record, Tabfile};
+use std::io::{Error, Read, Seek, SeekFrom};
+
+/// The number of bytes that are read at once
+const BLOCK_SIZE: usize = 1 << 16;
+
+/// Iterator over the records of a file from the last to the first one.
+///
+/// See [Tabfile::reverse](struct.Tabfile.html#method.reverse). The iterator is double-ended:
+/// [next_back](#method.next_back) reads the records from the start of the file, until both ends
+/// meet, so `rev()` returns the records in the order of the file.
+pub struct ReverseRows {
+    tabfile: Tabfile,
+    line_reader: LineReader,
+    /// The byte offset of the first line that has not been returned from the front
+    front: u64,
+    /// The line number of the line in front of `front`
+    front_line_number: usize,
+    /// The byte offset behind the last line that has not been returned from the back
+    end: u64,
+    /// The line number of the line that ends at `end`
+    back_line_number: usize,
+    /// The unread bytes directly in front of `end`
+    buffer: Vec<u8>,
+    /// The byte offset of the first byte in `buffer`
+    buffer_offset: u64,
+    /// The position of the reader if it is known, so that reading from the front does not
+    /// seek for every line
+    reader_position: Option<u64>,
+    done: bool,
+}
+
+/// A line and its byte offset, or `None` at the end
+type Line<T> = Result<Option<(T, u64)>, Error>;
+
+impl ReverseRows {
+    /// Get the byte offset of the line that was read last, or the file size before the first one
+    pub fn byte_offset(&self) -> u64 {
+        self.end
+    }
+
+    /// Read the block in front of `buffer`, but not in front of `front`
+    fn read_block(&mut self) -> Result<(), Error> {
+        let length = (self.buffer_offset - self.front).min(BLOCK_SIZE as u64) as usize;
+        let mut block = vec![0; length + self.buffer.len()];
+        let block_offset = self.buffer_offset - length as u64;
+        self.tabfile.reader.seek(SeekFrom::Start(block_offset))?;
+        self.tabfile.reader.read_exact(&mut block[..length])?;
+        self.reader_position = Some(self.buffer_offset);
+        block[length..].copy_from_slice(&self.buffer);
+        self.buffer = block;
+        self.buffer_offset = block_offset;
+        Ok(())
+    }
+
+    /// Remove the last line from the back and return it with its byte offset
+    ///
+    /// The line is `None` if it is longer than the maximum line length, in which case its bytes
+    /// are not kept while the start of the line is searched.
+    fn previous_line(&mut self) -> Line<Option<Vec<u8>>> {
+        if self.end <= self.front {
+            return Ok(None);
+        }
+        let terminator = self.tabfile.dialect.terminator;
+        let max_length = self
+            .tabfile
+            .max_line_length
+            .map_or(u64::MAX, |max| max as u64);
+        let line_end = self.end;
+        // the last byte is the terminator of the line itself
+        let mut unsearched_end = line_end - 1;
+        let mut too_long = false;
+        let line_start = loop {
+            let searchable = unsearched_end.saturating_sub(self.buffer_offset) as usize;
+            let searchable = &self.buffer[..searchable.min(self.buffer.len())];
+            if let Some(position) = rfind_terminator(terminator, searchable) {
+                break (self.buffer_offset + position as u64 + 1).max(self.front);
+            }
+            if self.buffer_offset <= self.front {
+                break self.front;
+            }
+            unsearched_end = unsearched_end.min(self.buffer_offset);
+            if line_end - self.buffer_offset > max_length {
+                too_long = true;
+                self.buffer.clear();
+            }
+            self.read_block()?;
+        };
+        self.end = line_start;
+        let kept = (line_start - self.buffer_offset) as usize;
+        if too_long || line_end - line_start > max_length {
+            self.buffer.truncate(kept);
+            return Ok(Some((None, line_start)));
+        }
+        Ok(Some((Some(self.buffer.split_off(kept)), line_start)))
+    }
+
+    /// Read the first line from the front and return it with its byte offset and why the line
+    /// reader rejected it
+    fn following_line(&mut self) -> Line<(String, Option<Rejected>)> {
+        if self.front >= self.end {
+            return Ok(None);
+        }
+        if self.reader_position != Some(self.front) {
+            self.tabfile.reader.seek(SeekFrom::Start(self.front))?;
+        }
+        let mut line = String::new();
+        let (line_length, rejected) = self
+            .line_reader
+            .read_line(&mut self.tabfile.reader, &mut line)?;
+        let byte_offset = self.front;
+        self.front += line_length as u64;
+        self.reader_position = Some(self.front);
+        if line_length == 0 {
+            // the file was truncated
+            self.end = self.front;
+            return Ok(None);
+        }
+        Ok(Some(((line, rejected), byte_offset)))
+    }
+
+    /// Build the record of a data line like the forward iteration does
+    fn record(&self, line: String, line_number: usize, byte_offset: u64) -> Option<IterItem> {
+        let dialect = &self.tabfile.dialect;
+        if !dialect.is_data_line(&line) {
+            return None;
+        }
+        let record = dialect.record(line, Vec::new(), line_number, byte_offset, None);
+        Some(dialect.check_width(&record).map(|_| record))
+    }
+
+    /// Get the next item from the back, or an error after which the iteration ends
+    fn next_from_back(&mut self) -> Result<Option<IterItem>, Error> {
+        while let Some((bytes, byte_offset)) = self.previous_line()? {
+            let line_number = self.back_line_number;
+            self.back_line_number -= 1;
+            let line = match bytes.map(String::from_utf8) {
+                Some(Ok(line)) => line,
+                Some(Err(_)) => {
+                    let error = self
+                        .line_reader
+                        .rejected(Rejected::InvalidUtf8, line_number);
+                    return Ok(Some(Err(error)));
+                }
+                None => {
+                    let error = self.line_reader.rejected(Rejected::TooLong, line_number);
+                    return Ok(Some(Err(error)));
+                }
+            };
+            if let Some(item) = self.record(line, line_number, byte_offset) {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the next item from the front, or an error after which the iteration ends
+    fn next_from_front(&mut self) -> Result<Option<IterItem>, Error> {
+        while let Some(((line, rejected), byte_offset)) = self.following_line()? {
+            self.front_line_number += 1;
+            let line_number = self.front_line_number;
+            if let Some(rejected) = rejected {
+                return Ok(Some(Err(self.line_reader.rejected(rejected, line_number))));
+            }
+            if let Some(item) = self.record(line, line_number, byte_offset) {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Count the lines between the line starts `from` and `to`
+    fn count_lines(&mut self, from: u64, to: u64) -> Result<usize, Error> {
+        let terminator = self.tabfile.dialect.terminator;
+        self.tabfile.reader.seek(SeekFrom::Start(from))?;
+        let mut block = vec![0; BLOCK_SIZE];
+        let mut remaining = to - from;
+        let mut lines = 0;
+        let mut last = terminator;
+        while remaining > 0 {
+            let length = remaining.min(BLOCK_SIZE as u64) as usize;
+            self.tabfile.reader.read_exact(&mut block[..length])?;
+            lines += count_terminators(terminator, &block[..length]);
+            last = block[length - 1];
+            remaining -= length as u64;
+        }
+        self.reader_position = Some(to);
+        // a last line without a terminator
+        Ok(lines + (last != terminator) as usize)
+    }
+
+    /// Skip the footer and the records after the limit, and number the lines
+    fn init(&mut self) -> Result<(), Error> {
+        for _ in 0..self.tabfile.skip_footer {
+            if self.previous_line()?.is_none() {
+                break;
+            }
+        }
+        let start = self.front;
+        let first_line_number = self.front_line_number;
+        let limit = match self.tabfile.limit {
+            Some(limit) => limit,
+            None => {
+                let lines = self.count_lines(start, self.end)?;
+                self.back_line_number = first_line_number + lines;
+                return Ok(());
+            }
+        };
+        // only the first `limit` records are returned, so the lines behind them are cut off
+        let mut records = 0;
+        while records < limit {
+            match self.following_line()? {
+                Some(((line, None), _)) if self.tabfile.dialect.is_data_line(&line) => records += 1,
+                Some(_) => {}
+                None => break,
+            }
+            self.front_line_number += 1;
+        }
+        if self.front < self.end {
+            self.end = self.front;
+            if self.end <= self.buffer_offset {
+                self.buffer.clear();
+                self.buffer_offset = self.end;
+            } else {
+                self.buffer
+                    .truncate((self.end - self.buffer_offset) as usize);
+            }
+        }
+        self.back_line_number = self.front_line_number;
+        self.front = start;
+        self.front_line_number = first_line_number;
+        Ok(())
+    }
+}
+
+/// Find the last `terminator` in `bytes`
+fn rfind_terminator(terminator: u8, bytes: &[u8]) -> Option<usize> {
+    #[cfg(feature = "memchr")]
+    return memchr::memrchr(terminator, bytes);
+    #[cfg(not(feature = "memchr"))]
+    bytes.iter().rposition(|&byte| byte == terminator)
+}
+
+/// Count how often `terminator` occurs in `bytes`
+fn count_terminators(terminator: u8, bytes: &[u8]) -> usize {
+    #[cfg(feature = "memchr")]
+    return memchr::memchr_iter(terminator, bytes).count();
+    #[cfg(not(feature = "memchr"))]
+    bytes.iter().filter(|&&byte| byte == terminator).count()
+}
+
+impl Iterator for ReverseRows {
+    type Item = IterItem;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_from_back().unwrap_or_else(|e| Some(Err(e)));
+        self.done = item.is_none();
+        item
+    }
+}
+
+impl DoubleEndedIterator for ReverseRows {
+    /// Get the next record from the start of the file
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_from_front().unwrap_or_else(|e| Some(Err(e)));
+        self.done = item.is_none();
+        item
+    }
+}
+
+impl Tabfile {
+    /// Iterate over the records from the end of the file towards the start.
+    ///
+    /// The file is read backwards in blocks, so the last records of a huge file are available
+    /// right away, without reading the lines in front of them. Comment and empty lines are
+    /// skipped, `skip_footer` removes the last lines and the iteration stops at the lines
+    /// removed by `skip_lines` and `skip_lines_while`, which are read from the start to find
+    /// them. Like the forward iteration, the records are checked against the expected
+    /// columns, lines longer than `max_line_length` are errors without being buffered and
+    /// `limit` keeps only the first records of the file, which are found by reading from the
+    /// start. The terminators in front of the last line are counted once to number the lines.
+    /// Quoted fields must not contain line breaks and neither `auto_fix` nor the line ending
+    /// policy are applied. The iteration continues after an invalid line and ends after the
+    /// first I/O error.
+    ///
+    /// The iterator is double-ended, so `rev()` returns the records in the order of the file.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "{}\tevent{}", i, i).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut records = Tabfile::open(file.path()).unwrap().reverse().unwrap();
+    /// assert_eq!(&records.next().unwrap().unwrap()[1], "event999");
+    /// assert_eq!(&records.next().unwrap().unwrap()[1], "event998");
+    /// ```
+    pub fn reverse(mut self) -> Result<ReverseRows, Error> {
+        let mut line_reader = self.line_reader();
+        let start = self.skipped_offset(&mut line_reader)?;
+        let end = self.reader.seek(SeekFrom::End(0))?.max(start);
+        let start_offset = self.start_offset;
+        let start_line_number = self.start_line_number;
+        let mut rows = ReverseRows {
+            tabfile: self,
+            line_reader,
+            front: start,
+            front_line_number: 0,
+            end,
+            back_line_number: 0,
+            buffer: Vec::new(),
+            buffer_offset: end,
+            reader_position: None,
+            done: false,
+        };
+        rows.front_line_number = start_line_number + rows.count_lines(start_offset, start)?;
+        rows.init()?;
+        Ok(rows)
+    }
+
+    /// Get the last `count` records in the order of the file, see
+    /// [reverse](#method.reverse).
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "{}\tevent{}", i, i).unwrap(); }
+    /// use tabfile::Tabfile;
+    ///
+    /// let records = Tabfile::open(file.path()).unwrap().last_records(2).unwrap();
+    /// assert_eq!(&records[0][1], "event998");
+    /// assert_eq!(&records[1][1], "event999");
+    /// ```
+    pub fn last_records(self, count: usize) -> Result<Vec<Record>, Error> {
+        let mut records = self
+            .reverse()?
+            .take(count)
+            .collect::<Result<Vec<_>, Error>>()?;
+        records.reverse();
+        Ok(records)
+    }
+}
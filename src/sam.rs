@@ -0,0 +1,37 @@
+//! Reading SAM files with their header block
+
+use crate::Tabfile;
+use std::io::Error;
+use std::path::Path;
+
+/// The prefix of the header lines of a SAM file
+const HEADER_PREFIX: &str = "@";
+
+impl Tabfile {
+    /// Open a SAM file and read its header block.
+    ///
+    /// SAM files start with header lines like `@HD` and `@SQ` that begin with `@`, followed by
+    /// the alignments with 11 or more fields. The header lines are read right away and kept
+    /// in [metadata](#method.metadata), and iterating yields the alignments. Only lines
+    /// starting with `@` are comments, so a `#` in an alignment is data.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"@HD\tVN:1.6\n@SQ\tSN:chr1\tLN:248956422\n\
+    /// #     r1\t0\tchr1\t100\t60\t4M\t*\t0\t0\tACGT\t####\n");
+    /// use tabfile::Tabfile;
+    ///
+    /// let sam_file = Tabfile::sam(file.path()).unwrap();
+    /// assert_eq!(sam_file.metadata()[1], "@SQ\tSN:chr1\tLN:248956422");
+    /// let alignment = sam_file.into_iter().next().unwrap().unwrap();
+    /// assert_eq!(&alignment[2], "chr1");
+    /// assert_eq!(&alignment[10], "####");
+    /// ```
+    pub fn sam<P: AsRef<Path>>(path: P) -> Result<Tabfile, Error> {
+        let mut sam_file = Tabfile::open(path)?.comment_prefix(HEADER_PREFIX);
+        sam_file.read_metadata()?;
+        Ok(sam_file)
+    }
+}
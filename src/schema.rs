@@ -0,0 +1,121 @@
+//! Per-column rules for deserializing records
+
+use crate::Header;
+use std::collections::HashMap;
+
+/// Rules for turning the values of named columns into typed fields.
+///
+/// A schema complements the header of a file: the header tells where a column is and the schema
+/// tells how its values are interpreted. Columns are identified by the names in the header.
+///
+/// ```
+/// use tabfile::Schema;
+///
+/// let schema = Schema::new().list_separator("tags", ';');
+/// assert_eq!(schema.get_list_separator("tags"), Some(';'));
+/// assert_eq!(schema.get_list_separator("name"), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    list_separators: HashMap<String, char>,
+    default_values: HashMap<String, String>,
+}
+
+impl Schema {
+    /// Create a schema without any rules
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    /// Split the values of `column` on `separator` when they are deserialized into a sequence.
+    ///
+    /// This turns a cell like `a;b;c` into a `Vec<String>` with three elements or a cell like
+    /// `1,2` into a `Vec<u32>`. An empty cell becomes an empty sequence. The parts are not
+    /// trimmed.
+    pub fn list_separator(mut self, column: &str, separator: char) -> Self {
+        self.list_separators.insert(column.to_string(), separator);
+        self
+    }
+
+    /// Get the separator for the values of `column` if there is one
+    pub fn get_list_separator(&self, column: &str) -> Option<char> {
+        self.list_separators.get(column).copied()
+    }
+
+    /// Use `value` for `column` if the column is missing from a file or a field is null.
+    ///
+    /// The value is parsed like any other field, so a default of `"0"` works for numeric fields.
+    /// This keeps older files without a newly added column readable. A field is null if the row
+    /// is too short to have it or if it matches one of the
+    /// [null tokens](crate::Tabfile::null_tokens). Since an empty field is not null, add `""` to
+    /// the null tokens to replace empty fields as well. Fields with `#[serde(default)]` fall back
+    /// to their `Default` value if the column is missing, but not if the field is null.
+    pub fn default_value(mut self, column: &str, value: &str) -> Self {
+        self.default_values
+            .insert(column.to_string(), value.to_string());
+        self
+    }
+
+    /// Get the default value of `column` if there is one
+    pub fn get_default_value(&self, column: &str) -> Option<&str> {
+        self.default_values.get(column).map(String::as_str)
+    }
+}
+
+/// Several versions of a [Schema](struct.Schema.html) for a file format that evolves.
+///
+/// Every version has a marker that is matched against a `##version=<marker>` comment at the top
+/// of a file. Versions of files without such a comment can be recognized by the exact list of
+/// column names in their header instead. [select](#method.select) prefers the version comment
+/// over the header.
+///
+/// ```
+/// use tabfile::{Header, Schema, SchemaVersions};
+///
+/// let versions = SchemaVersions::new()
+///     .version_with_header("1", &["id", "weight"], Schema::new().default_value("unit", "kg"))
+///     .version("2", Schema::new());
+/// let header = Header::new(vec!["id", "weight"]);
+/// assert_eq!(versions.select(None, &header).unwrap().0, "1");
+/// assert_eq!(versions.select(Some("2"), &header).unwrap().0, "2");
+/// assert!(versions.select(Some("3"), &header).is_none());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaVersions {
+    versions: Vec<(String, Option<Vec<String>>, Schema)>,
+}
+
+impl SchemaVersions {
+    /// Create an empty list of versions
+    pub fn new() -> SchemaVersions {
+        SchemaVersions::default()
+    }
+
+    /// Register the schema for files with a `##version=<marker>` comment
+    pub fn version(mut self, marker: &str, schema: Schema) -> Self {
+        self.versions.push((marker.to_string(), None, schema));
+        self
+    }
+
+    /// Register the schema for files with a version comment or exactly the given columns
+    pub fn version_with_header(mut self, marker: &str, columns: &[&str], schema: Schema) -> Self {
+        let columns = columns.iter().map(|column| column.to_string()).collect();
+        self.versions
+            .push((marker.to_string(), Some(columns), schema));
+        self
+    }
+
+    /// Find the version of a file by its version marker or by its header
+    ///
+    /// Returns the marker of the matching version together with its schema. If the file has a
+    /// version marker, only the marker is compared.
+    pub fn select(&self, marker: Option<&str>, header: &Header) -> Option<(&str, &Schema)> {
+        self.versions
+            .iter()
+            .find(|(version, columns, _)| match marker {
+                Some(marker) => marker == version,
+                None => columns.as_deref() == Some(header.names()),
+            })
+            .map(|(version, _, schema)| (version.as_str(), schema))
+    }
+}
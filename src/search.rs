@@ -0,0 +1,168 @@
+//! Looking up records in files that are sorted by a column
+
+use crate::code::{self, ErrorCode};
+use crate::lines::{LineReader, Rejected};
+use crate::{Record, Tabfile};
+use std::cmp::Ordering;
+use std::io::{BufRead, Error, Seek, SeekFrom};
+
+impl Tabfile {
+    /// Read the line starting at the current position of the reader, returns its length
+    fn read_search_line(
+        &mut self,
+        line_reader: &mut LineReader,
+        line: &mut String,
+        byte_offset: u64,
+    ) -> Result<usize, Error> {
+        line.clear();
+        let (line_length, rejected) = line_reader.read_line(&mut self.reader, line)?;
+        match rejected {
+            Some(Rejected::TooLong) => Err(code::invalid_data(
+                ErrorCode::LineTooLong,
+                format!("the line at byte offset {} is too long", byte_offset),
+            )),
+            Some(Rejected::InvalidUtf8) | Some(Rejected::UnterminatedInvalidUtf8) => {
+                Err(code::invalid_data(
+                    ErrorCode::InvalidUtf8,
+                    format!("the line at byte offset {} is not valid UTF-8", byte_offset),
+                ))
+            }
+            None => Ok(line_length),
+        }
+    }
+
+    /// Find the first data line at or after the line start `byte_offset`, where the reader is
+    ///
+    /// Returns the record of the line and the byte offset behind it.
+    fn next_search_record(
+        &mut self,
+        line_reader: &mut LineReader,
+        mut byte_offset: u64,
+    ) -> Result<Option<(Record, u64)>, Error> {
+        let mut line = String::new();
+        loop {
+            let line_length = self.read_search_line(line_reader, &mut line, byte_offset)?;
+            if line_length == 0 {
+                return Ok(None);
+            }
+            let line_offset = byte_offset;
+            byte_offset += line_length as u64;
+            if self.dialect.is_data_line(&line) {
+                let record = self.dialect.record(line, Vec::new(), 0, line_offset, None);
+                return Ok(Some((record, byte_offset)));
+            }
+        }
+    }
+
+    /// Compare the field at `column` of `record` with `key`
+    fn compare_key(record: &Record, column: usize, key: &str) -> Result<Ordering, Error> {
+        match record.iter().nth(column) {
+            Some(field) => Ok(field.cmp(key)),
+            None => Err(code::invalid_data(
+                ErrorCode::MissingField,
+                format!(
+                    "the line at byte offset {} has no field at column {}",
+                    record.byte_offset(),
+                    column
+                ),
+            )),
+        }
+    }
+
+    /// Find the records whose field at `column` equals `key` in a file sorted by that column.
+    ///
+    /// Instead of reading the whole file, the search bisects the byte range of the file and
+    /// realigns to the next line after every jump, so only about `log2(file size)` lines are
+    /// read, plus the matching records. The file must be sorted by the column in byte order,
+    /// like `LC_ALL=C sort -t$'\t' -k1,1` does for the first column, otherwise records may be
+    /// missed. Comment and empty lines are skipped and the lines removed by `skip_lines` and
+    /// `skip_lines_while` are never looked at, but `skip_footer` is not taken into account.
+    /// Quoted fields must not contain line breaks. Since the lines before a record are not
+    /// counted, the records have the line number zero. The search can be repeated with other
+    /// keys, and iterating the `Tabfile` afterwards starts at the beginning again.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # for i in 0..1000 { writeln!(file, "id{:04}\t{}", i, i * i).unwrap(); }
+    /// # writeln!(file, "id0999\tagain").unwrap();
+    /// use tabfile::Tabfile;
+    ///
+    /// let mut tsv_file = Tabfile::open(file.path()).unwrap();
+    /// let records = tsv_file.binary_search(0, "id0123").unwrap();
+    /// assert_eq!(&records[0][1], "15129");
+    /// assert_eq!(tsv_file.binary_search(0, "id0999").unwrap().len(), 2);
+    /// assert!(tsv_file.binary_search(0, "id5000").unwrap().is_empty());
+    /// ```
+    pub fn binary_search(&mut self, column: usize, key: &str) -> Result<Vec<Record>, Error> {
+        let result = self.search_sorted(column, key);
+        self.reader.seek(SeekFrom::Start(self.start_offset))?;
+        result
+    }
+
+    /// Find the byte offset of the first line after the lines removed by `skip_lines` and
+    /// `skip_lines_while`, leaving the reader anywhere
+    pub(crate) fn skipped_offset(&mut self, line_reader: &mut LineReader) -> Result<u64, Error> {
+        let mut offset = self.start_offset;
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        let mut lines_to_skip = self.skip_lines;
+        let in_preamble = self.skip_while.is_some();
+        loop {
+            let line_length = self.read_search_line(line_reader, &mut line, offset)?;
+            if line_length == 0 {
+                break;
+            } else if lines_to_skip > 0 {
+                lines_to_skip -= 1;
+            } else if !(in_preamble && self.in_preamble(&line)) {
+                break;
+            }
+            offset += line_length as u64;
+        }
+        Ok(offset)
+    }
+
+    /// Bisect the file for the records of `key`, leaving the reader anywhere
+    fn search_sorted(&mut self, column: usize, key: &str) -> Result<Vec<Record>, Error> {
+        let mut line_reader = self.line_reader();
+        let terminator = self.dialect.terminator;
+        // the data lines before `low` have smaller keys
+        let mut low = self.skipped_offset(&mut line_reader)?;
+        let mut high = self.reader.seek(SeekFrom::End(0))?;
+        let mut partial = Vec::new();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let mut line_start = middle;
+            if middle > low {
+                // realign to the line after the one that contains `middle`
+                self.reader.seek(SeekFrom::Start(middle - 1))?;
+                partial.clear();
+                line_start += self.reader.read_until(terminator, &mut partial)? as u64 - 1;
+            } else {
+                self.reader.seek(SeekFrom::Start(middle))?;
+            }
+            match self.next_search_record(&mut line_reader, line_start)? {
+                Some((record, end)) if record.byte_offset() < high => {
+                    if Tabfile::compare_key(&record, column, key)? == Ordering::Less {
+                        low = end;
+                    } else {
+                        high = middle;
+                    }
+                }
+                _ => high = middle,
+            }
+        }
+        let mut records = Vec::new();
+        self.reader.seek(SeekFrom::Start(low))?;
+        while let Some((record, end)) = self.next_search_record(&mut line_reader, low)? {
+            match Tabfile::compare_key(&record, column, key)? {
+                Ordering::Less => {}
+                Ordering::Equal => records.push(record),
+                Ordering::Greater => break,
+            }
+            low = end;
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,69 @@
+//! Reading the same file from several threads
+
+use crate::{RowIterator, Tabfile};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type Configure = dyn Fn(Tabfile) -> Tabfile + Send + Sync;
+
+/// A tab file that can be read any number of times, also from several threads at once.
+///
+/// A `SharedTabfile` stores the path of a file and a function that configures a
+/// [Tabfile](struct.Tabfile.html) for it. Every call of [open](#method.open) or
+/// [rows](#method.rows) opens the file again, so the readers have their own position and
+/// do not need to coordinate with each other, e.g. to compute statistics on one thread while
+/// another one processes the records. Cloning a `SharedTabfile` is cheap.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"a,1\nb,2\n");
+/// use std::thread;
+/// use tabfile::SharedTabfile;
+///
+/// let shared = SharedTabfile::new(file.path(), |tsv_file| tsv_file.separator(','));
+/// let stats = {
+///     let shared = shared.clone();
+///     thread::spawn(move || shared.rows().unwrap().count())
+/// };
+/// for record in shared.rows().unwrap() {
+///     println!("{}", record.unwrap().fields()[0]);
+/// }
+/// assert_eq!(stats.join().unwrap(), 2);
+/// ```
+#[derive(Clone)]
+pub struct SharedTabfile {
+    path: Arc<PathBuf>,
+    configure: Arc<Configure>,
+}
+
+impl SharedTabfile {
+    /// Share the file at `path`, configuring every reader with `configure`
+    pub fn new<P, F>(path: P, configure: F) -> SharedTabfile
+    where
+        P: AsRef<Path>,
+        F: Fn(Tabfile) -> Tabfile + Send + Sync + 'static,
+    {
+        SharedTabfile {
+            path: Arc::new(path.as_ref().to_path_buf()),
+            configure: Arc::new(configure),
+        }
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the file again and configure it
+    pub fn open(&self) -> Result<Tabfile, Error> {
+        Ok((self.configure)(Tabfile::open(self.path.as_path())?))
+    }
+
+    /// Open the file again and iterate over its records
+    pub fn rows(&self) -> Result<RowIterator, Error> {
+        Ok(self.open()?.into_iter())
+    }
+}
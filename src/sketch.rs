@@ -0,0 +1,303 @@
+//! Probabilistic summaries of column values
+
+use crate::code::{self, ErrorCode};
+use crate::Tabfile;
+use std::io::Error;
+
+/// Precision of the estimators created by
+/// [Tabfile::approx_distinct](struct.Tabfile.html#method.approx_distinct).
+pub const DEFAULT_HLL_PRECISION: u8 = 14;
+
+/// A stable 64-bit hasher (FNV-1a followed by the MurmurHash3 finalizer).
+///
+/// Unlike `std`'s `DefaultHasher` the result is guaranteed to be the same on every machine and
+/// with every compiler version, which is required for merging estimators from different shards.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> StableHasher {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^ (hash >> 33)
+    }
+}
+
+fn hash64(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// A HyperLogLog estimator for the number of distinct values.
+///
+/// The estimator uses `2^precision` bytes of memory regardless of the number of values and has a
+/// standard error of about `1.04 / sqrt(2^precision)`. Estimators with the same precision can be
+/// merged, e.g. after processing the shards of a file independently, and serialized with
+/// `to_bytes` to move them between processes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Create an empty estimator
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 18.
+    pub fn new(precision: u8) -> HyperLogLog {
+        assert!(
+            (4..=18).contains(&precision),
+            "the precision must be between 4 and 18"
+        );
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Get the precision of the estimator
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Add a value to the estimator
+    pub fn insert(&mut self, value: &str) {
+        let hash = hash64(value.as_bytes());
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank as u8);
+    }
+
+    /// Add all values from `other` to this estimator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the precisions of the two estimators differ.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(
+            self.precision, other.precision,
+            "only estimators with the same precision can be merged"
+        );
+        for (register, &other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(other);
+        }
+    }
+
+    /// Estimate the number of distinct values that were inserted
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&register| 2f64.powi(-i32::from(register)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|&&register| register == 0)
+            .count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln() // linear counting for small cardinalities
+        } else {
+            estimate
+        }
+    }
+
+    /// Serialize the estimator
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.registers.len() + 1);
+        bytes.push(self.precision);
+        bytes.extend_from_slice(&self.registers);
+        bytes
+    }
+
+    /// Deserialize an estimator that was serialized with `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<HyperLogLog, Error> {
+        match bytes.split_first() {
+            Some((&precision, registers))
+                if (4..=18).contains(&precision) && registers.len() == 1 << precision =>
+            {
+                Ok(HyperLogLog {
+                    precision,
+                    registers: registers.to_vec(),
+                })
+            }
+            _ => Err(code::invalid_data(
+                ErrorCode::InvalidSketch,
+                "not a serialized HyperLogLog estimator",
+            )),
+        }
+    }
+}
+
+/// A Bloom filter for testing whether a value is contained in a set of values.
+///
+/// A Bloom filter never reports a value as missing if it was inserted, but it reports values as
+/// contained that were never inserted with a configurable probability. This makes it well suited
+/// for cheaply dropping records of a large file that cannot possibly match the keys of another
+/// file before an expensive join.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BloomFilter {
+    num_bits: u64,
+    num_hashes: u32,
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// Create an empty filter for `expected_items` values and the given false positive rate
+    ///
+    /// # Panics
+    ///
+    /// Panics if `false_positive_rate` is not strictly between `0.0` and `1.0`.
+    pub fn with_rate(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "the false positive rate must be between 0 and 1"
+        );
+        let expected_items = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-expected_items * false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / expected_items) * ln2).round().max(1.0) as u32;
+        BloomFilter {
+            num_bits,
+            num_hashes,
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+        }
+    }
+
+    fn bit_positions(&self, value: &str) -> impl Iterator<Item = u64> {
+        let h1 = hash64(value.as_bytes());
+        let h2 = hash64(&h1.to_le_bytes()) | 1;
+        let num_bits = self.num_bits;
+        (0..u64::from(self.num_hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    /// Add a value to the filter
+    pub fn insert(&mut self, value: &str) {
+        for position in self.bit_positions(value) {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+
+    /// Check whether a value might have been inserted
+    pub fn contains(&self, value: &str) -> bool {
+        self.bit_positions(value)
+            .all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+
+    /// Serialize the filter
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + 8 * self.bits.len());
+        bytes.extend_from_slice(&self.num_bits.to_le_bytes());
+        bytes.extend_from_slice(&self.num_hashes.to_le_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a filter that was serialized with `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<BloomFilter, Error> {
+        let invalid =
+            || code::invalid_data(ErrorCode::InvalidSketch, "not a serialized Bloom filter");
+        if bytes.len() < 12 {
+            return Err(invalid());
+        }
+        let mut num_bits = [0; 8];
+        num_bits.copy_from_slice(&bytes[..8]);
+        let num_bits = u64::from_le_bytes(num_bits);
+        let mut num_hashes = [0; 4];
+        num_hashes.copy_from_slice(&bytes[8..12]);
+        let num_hashes = u32::from_le_bytes(num_hashes);
+        let words = &bytes[12..];
+        if num_bits == 0 || num_hashes == 0 || words.len() as u64 != num_bits.div_ceil(64) * 8 {
+            return Err(invalid());
+        }
+        let bits = words
+            .chunks(8)
+            .map(|chunk| {
+                let mut word = [0; 8];
+                word.copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        Ok(BloomFilter {
+            num_bits,
+            num_hashes,
+            bits,
+        })
+    }
+}
+
+impl Tabfile {
+    /// Estimate the number of distinct values in `column` with a HyperLogLog estimator.
+    ///
+    /// The returned estimator has a precision of
+    /// [DEFAULT_HLL_PRECISION](constant.DEFAULT_HLL_PRECISION.html), i.e. it needs 16 KiB of
+    /// memory and typically deviates by less than 1% from the true number. Call `estimate()` on
+    /// it to get the number or `merge()` it with the estimators of other shards. Records that are
+    /// too short to have a field at `column` are ignored.
+    pub fn approx_distinct(self, column: usize) -> Result<HyperLogLog, Error> {
+        let mut hll = HyperLogLog::new(DEFAULT_HLL_PRECISION);
+        for record in self {
+            if let Some(value) = record?.get_opt(column) {
+                hll.insert(value);
+            }
+        }
+        Ok(hll)
+    }
+
+    /// Build a Bloom filter of the values in `column`.
+    ///
+    /// The file is read twice: first to estimate the number of distinct values with
+    /// `approx_distinct`, which determines the size of the filter, and then to insert the values.
+    /// Records that are too short to have a field at `column` are ignored.
+    pub fn build_bloom(
+        self,
+        column: usize,
+        false_positive_rate: f64,
+    ) -> Result<BloomFilter, Error> {
+        let mut records = self.into_iter();
+        let mut hll = HyperLogLog::new(DEFAULT_HLL_PRECISION);
+        for record in &mut records {
+            if let Some(value) = record?.get_opt(column) {
+                hll.insert(value);
+            }
+        }
+        let expected_items = hll.estimate().ceil() as usize;
+        let mut bloom = BloomFilter::with_rate(expected_items, false_positive_rate);
+        records.rewind()?;
+        for record in records {
+            if let Some(value) = record?.get_opt(column) {
+                bloom.insert(value);
+            }
+        }
+        Ok(bloom)
+    }
+}
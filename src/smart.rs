@@ -0,0 +1,81 @@
+//! Finding a file under its compressed or uncompressed name
+
+use crate::code::{self, ErrorCode};
+use crate::Tabfile;
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// The extensions of the compressed variants of a file
+const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "zst"];
+
+/// Get the path of the uncompressed file for a path with a compressed extension
+fn uncompressed_path(path: &Path) -> Option<PathBuf> {
+    let extension = path.extension()?.to_str()?;
+    if COMPRESSED_EXTENSIONS.contains(&extension) {
+        Some(path.with_extension(""))
+    } else {
+        None
+    }
+}
+
+/// Get the paths of the compressed variants of an uncompressed file
+fn compressed_paths(path: &Path) -> Vec<PathBuf> {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .map(|extension| {
+            let mut name = OsString::from(path.as_os_str());
+            name.push(".");
+            name.push(extension);
+            PathBuf::from(name)
+        })
+        .collect()
+}
+
+impl Tabfile {
+    /// Open a tab file, falling back to its compressed or uncompressed sibling.
+    ///
+    /// If `path` does not exist and ends in `.gz` or `.zst`, the file without that extension is
+    /// opened instead. If `path` has no such extension, `path.gz` and `path.zst` are looked for.
+    /// Since files are read without decompression, a compressed sibling cannot be opened and is
+    /// reported with an error of kind `Unsupported` that names it, which is more helpful than
+    /// the plain `NotFound` error of [open](#method.open). If there is no sibling either, the
+    /// `NotFound` error for `path` is returned.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::write(dir.path().join("data.tsv"), b"1\t2\n").unwrap();
+    /// use tabfile::Tabfile;
+    ///
+    /// // the archival process did not compress this file yet
+    /// let tsv_file = Tabfile::open_smart(dir.path().join("data.tsv.gz")).unwrap();
+    /// let record = tsv_file.into_iter().next().unwrap().unwrap();
+    /// assert_eq!(record.fields(), vec!["1", "2"]);
+    /// ```
+    pub fn open_smart<P: AsRef<Path>>(path: P) -> Result<Tabfile, Error> {
+        let path = path.as_ref();
+        let error = match Tabfile::open(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => e,
+            result => return result,
+        };
+        match uncompressed_path(path) {
+            Some(plain) if plain.is_file() => Tabfile::open(plain),
+            Some(_) => Err(error),
+            None => match compressed_paths(path)
+                .into_iter()
+                .find(|path| path.is_file())
+            {
+                Some(compressed) => Err(code::error(
+                    ErrorKind::Unsupported,
+                    ErrorCode::CompressedFile,
+                    format!(
+                        "{} does not exist and its compressed sibling {} cannot be decompressed",
+                        path.display(),
+                        compressed.display()
+                    ),
+                )),
+                None => Err(error),
+            },
+        }
+    }
+}
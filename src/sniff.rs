@@ -0,0 +1,134 @@
+//! Guessing the separator of a file
+
+use crate::lines::LineReader;
+use crate::{Dialect, Tabfile};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Error};
+use std::path::Path;
+
+/// The separators that [Tabfile::sniff](struct.Tabfile.html#method.sniff) chooses from.
+///
+/// If several separators are equally plausible, the one that occurs more often per line wins,
+/// and earlier candidates win if that is the same as well.
+pub const SNIFF_CANDIDATES: [char; 4] = ['\t', ',', ';', '|'];
+
+/// The result of guessing the separator of a file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sniff {
+    separator: char,
+    confidence: f64,
+}
+
+impl Sniff {
+    /// Get the guessed separator
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+
+    /// Get the confidence of the guess between `0.0` and `1.0`
+    ///
+    /// This is the fraction of inspected lines that contain the separator exactly as often as
+    /// the majority of lines. A confidence of `0.0` means that none of the candidates occurred in
+    /// the file and the separator defaults to `'\t'`.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+}
+
+impl Tabfile {
+    /// Guess the separator of a file by inspecting its first `num_lines` lines.
+    ///
+    /// Every character from [SNIFF_CANDIDATES](constant.SNIFF_CANDIDATES.html) is counted on
+    /// every line and the candidate that occurs the same number of times on the most lines wins.
+    /// Ties are broken by that number of times, so `a;b;c,d` is split at the semicolons. Empty
+    /// lines and lines starting with `#` are ignored. Use [Dialect::sniff](crate::Dialect::sniff)
+    /// for files with other comment prefixes or record terminators.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"id,name\n1,foo\n2,bar");
+    /// use tabfile::Tabfile;
+    ///
+    /// let sniff = Tabfile::sniff(file.path(), 10).unwrap();
+    /// let tabfile = Tabfile::open(file.path()).unwrap().separator(sniff.separator());
+    /// # assert_eq!(sniff.separator(), ',');
+    /// ```
+    pub fn sniff<P: AsRef<Path>>(path: P, num_lines: usize) -> Result<Sniff, Error> {
+        Dialect::new().sniff(path, num_lines)
+    }
+}
+
+impl Dialect {
+    /// Guess the separator of a file like [Tabfile::sniff](crate::Tabfile::sniff), but with the
+    /// comment prefixes and the record terminator of this dialect.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"//exported;id,name;1,foo;2,bar;");
+    /// use tabfile::Dialect;
+    ///
+    /// let dialect = Dialect::new().comment_prefix("//").record_terminator(b';');
+    /// let sniff = dialect.sniff(file.path(), 10).unwrap();
+    /// assert_eq!(sniff.separator(), ',');
+    /// ```
+    pub fn sniff<P: AsRef<Path>>(&self, path: P, num_lines: usize) -> Result<Sniff, Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line_reader = LineReader::new(self.terminator, None);
+        let mut counts: Vec<Vec<usize>> = vec![Vec::new(); SNIFF_CANDIDATES.len()];
+        let mut line = String::new();
+        let mut line_number = 0;
+        let mut inspected = 0;
+        while inspected < num_lines {
+            line.clear();
+            let (line_length, rejected) = line_reader.read_line(&mut reader, &mut line)?;
+            if line_length == 0 {
+                break;
+            }
+            line_number += 1;
+            if let Some(rejected) = rejected {
+                return Err(line_reader.rejected(rejected, line_number));
+            }
+            let content = self.content(&line);
+            if content.trim().is_empty() || self.is_comment(content) {
+                continue;
+            }
+            inspected += 1;
+            for (i, &candidate) in SNIFF_CANDIDATES.iter().enumerate() {
+                counts[i].push(content.matches(candidate).count());
+            }
+        }
+
+        let mut best = Sniff {
+            separator: '\t',
+            confidence: 0.0,
+        };
+        let mut best_occurrences = 0;
+        for (i, &candidate) in SNIFF_CANDIDATES.iter().enumerate() {
+            let mut frequencies: HashMap<usize, usize> = HashMap::new();
+            for &count in counts[i].iter().filter(|&&count| count > 0) {
+                *frequencies.entry(count).or_insert(0) += 1;
+            }
+            let modal = frequencies
+                .into_iter()
+                .max_by_key(|&(occurrences, lines)| (lines, occurrences));
+            if let Some((occurrences, lines)) = modal {
+                let confidence = lines as f64 / inspected as f64;
+                if confidence > best.confidence
+                    || (confidence == best.confidence && occurrences > best_occurrences)
+                {
+                    best = Sniff {
+                        separator: candidate,
+                        confidence,
+                    };
+                    best_occurrences = occurrences;
+                }
+            }
+        }
+        Ok(best)
+    }
+}
@@ -0,0 +1,234 @@
+//! Writing the records of a file into one file per key
+
+use crate::code::{self, ErrorCode};
+use crate::collect::required_field;
+use crate::{Record, Tabfile};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Error, Write};
+use std::path::{Path, PathBuf};
+
+/// The default of [SplitOptions::max_open_files](struct.SplitOptions.html#method.max_open_files)
+pub const DEFAULT_MAX_OPEN_FILES: usize = 64;
+
+/// Where and how [split_by](fn.split_by.html) writes the output files.
+///
+/// By default, the records with the key `sample1` are written to `sample1.tsv` in the output
+/// directory, no header is copied and at most
+/// [DEFAULT_MAX_OPEN_FILES](constant.DEFAULT_MAX_OPEN_FILES.html) files are open at once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitOptions {
+    directory: PathBuf,
+    prefix: String,
+    extension: String,
+    header: bool,
+    max_open_files: usize,
+    max_files: Option<usize>,
+}
+
+impl SplitOptions {
+    /// Write the output files into `directory`, which must exist
+    pub fn new<P: AsRef<Path>>(directory: P) -> SplitOptions {
+        SplitOptions {
+            directory: directory.as_ref().to_path_buf(),
+            prefix: String::new(),
+            extension: "tsv".to_string(),
+            header: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            max_files: None,
+        }
+    }
+
+    /// Put `prefix` in front of the key in the file names. The default is no prefix.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Set the extension of the file names without the dot, or `""` for none. The default is
+    /// `"tsv"`.
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extension = extension.to_string();
+        self
+    }
+
+    /// Treat the first record as a header and write it at the top of every output file. The
+    /// default is `false`.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Set the number of output files that are open at the same time.
+    ///
+    /// If there are more keys, the file that was written least recently is closed and opened
+    /// again when its key comes up next. The default is
+    /// [DEFAULT_MAX_OPEN_FILES](constant.DEFAULT_MAX_OPEN_FILES.html).
+    ///
+    /// # Panics
+    ///
+    /// If `max_open_files` is zero
+    pub fn max_open_files(mut self, max_open_files: usize) -> Self {
+        assert!(max_open_files > 0, "at least one file must be open");
+        self.max_open_files = max_open_files;
+        self
+    }
+
+    /// Set the number of output files that may be created, or `None` for no limit.
+    ///
+    /// This guards against splitting by a column with unexpectedly many distinct values, like
+    /// an ID instead of a sample name. The default is `None`.
+    pub fn max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Get the path of the output file for `key`
+    pub fn path(&self, key: &str) -> PathBuf {
+        let mut name = format!("{}{}", self.prefix, key);
+        if !self.extension.is_empty() {
+            name.push('.');
+            name.push_str(&self.extension);
+        }
+        self.directory.join(name)
+    }
+}
+
+/// Check that `key` can be used as a file name without leaving the output directory
+fn check_key(key: &str, record: &Record) -> Result<(), Error> {
+    if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\', '\0']) {
+        return Err(code::invalid_data(
+            ErrorCode::InvalidFileName,
+            format!(
+                "line {}: the key `{}` cannot be used in a file name",
+                record.line_number(),
+                key
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// An open output file and when it was last written
+struct Output {
+    writer: BufWriter<File>,
+    last_used: usize,
+}
+
+/// Write the line of `record` and add the terminator if it is the last line of a file without one
+fn write_record(
+    writer: &mut BufWriter<File>,
+    record: &Record,
+    terminator: u8,
+) -> Result<(), Error> {
+    let line = record.line().as_bytes();
+    writer.write_all(line)?;
+    if line.last() != Some(&terminator) {
+        writer.write_all(&[terminator])?;
+    }
+    Ok(())
+}
+
+/// Write the records of `input` into one file per value of the field at `column`.
+///
+/// The input is read once and every record is appended to the file of its key, so the files
+/// keep the order of the input and the input does not need to be sorted or fit into memory.
+/// The records are written as they are in the input, including the separators and line
+/// terminators, while comment, skipped and empty lines are dropped. Files that already exist
+/// are replaced. The file names are made of the key and the options, see
+/// [SplitOptions::path](struct.SplitOptions.html#method.path), so a key that is empty or
+/// contains a slash, a backslash or a null character is rejected with an error of kind
+/// `InvalidData`. So is a record without a field at `column` and a new key beyond
+/// [max_files](struct.SplitOptions.html#method.max_files).
+///
+/// Returns the number of records written per key, without the headers.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::{tempdir, NamedTempFile};
+/// # let mut file = NamedTempFile::new().unwrap();
+/// # file.write_all(b"sample\tgene\tcount\ns1\tBRCA1\t5\ns2\tBRCA1\t7\ns1\tTP53\t3\n");
+/// # let directory = tempdir().unwrap();
+/// use std::fs;
+/// use tabfile::{split_by, SplitOptions, Tabfile};
+///
+/// let input = Tabfile::open(file.path()).unwrap();
+/// let options = SplitOptions::new(directory.path()).header(true);
+/// let counts = split_by(input, 0, &options).unwrap();
+/// assert_eq!((counts["s1"], counts["s2"]), (2, 1));
+/// assert_eq!(
+///     fs::read_to_string(options.path("s1")).unwrap(),
+///     "sample\tgene\tcount\ns1\tBRCA1\t5\ns1\tTP53\t3\n"
+/// );
+/// ```
+pub fn split_by(
+    input: Tabfile,
+    column: usize,
+    options: &SplitOptions,
+) -> Result<HashMap<String, usize>, Error> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut outputs: HashMap<String, Output> = HashMap::new();
+    let mut header = None;
+    let terminator = input.dialect.terminator;
+    for (i, record) in input.into_iter().enumerate() {
+        let record = record?;
+        if options.header && header.is_none() {
+            header = Some(record);
+            continue;
+        }
+        let key = required_field(&record, column)?;
+        if !outputs.contains_key(key) {
+            let created = counts.contains_key(key);
+            if !created {
+                check_key(key, &record)?;
+                if options.max_files.is_some_and(|max| counts.len() >= max) {
+                    return Err(code::invalid_data(
+                        ErrorCode::TooManyFiles,
+                        format!(
+                            "line {}: the key `{}` would exceed the limit of {} files",
+                            record.line_number(),
+                            key,
+                            counts.len()
+                        ),
+                    ));
+                }
+            }
+            if outputs.len() >= options.max_open_files {
+                let oldest = outputs
+                    .iter()
+                    .min_by_key(|(_, output)| output.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(mut output) = oldest.and_then(|key| outputs.remove(&key)) {
+                    output.writer.flush()?;
+                }
+            }
+            let path = options.path(key);
+            let mut writer = if created {
+                BufWriter::new(OpenOptions::new().append(true).open(path)?)
+            } else {
+                BufWriter::new(File::create(path)?)
+            };
+            if let (Some(header), false) = (&header, created) {
+                write_record(&mut writer, header, terminator)?;
+            }
+            let output = Output {
+                writer,
+                last_used: i,
+            };
+            outputs.insert(key.to_string(), output);
+        }
+        let output = outputs.get_mut(key).expect("the output was just opened");
+        output.last_used = i;
+        write_record(&mut output.writer, &record, terminator)?;
+        match counts.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(key.to_string(), 1);
+            }
+        }
+    }
+    for output in outputs.values_mut() {
+        output.writer.flush()?;
+    }
+    Ok(counts)
+}
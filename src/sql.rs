@@ -0,0 +1,78 @@
+//! Loading tab files into SQL databases
+
+use crate::code::{self, ErrorCode};
+use crate::Tabfile;
+use std::io::Error;
+
+/// The schema that tables are created in
+const SCHEMA: &str = "main";
+
+impl Tabfile {
+    /// Load the records of this tab file into a new DuckDB table called `table_name`.
+    ///
+    /// All configured options like `skip_lines` or `comment_character` are applied before the
+    /// data reaches DuckDB, so files with odd preambles can be queried with SQL. The table has
+    /// one `VARCHAR` column per field named `column0`, `column1`, ... and the number of columns
+    /// is taken from the first record. If the file has no records, the table is empty and has
+    /// the number of columns set with [columns](#method.columns), or a single column. A record
+    /// with a different number of fields results in an error of kind `InvalidData`. The table
+    /// is created in the `main` schema and filled in one transaction, so after an error it does
+    /// not exist. Returns the number of rows that were inserted.
+    ///
+    /// This method is only available with the `duckdb` feature.
+    pub fn register_in_duckdb(
+        self,
+        conn: &duckdb::Connection,
+        table_name: &str,
+    ) -> Result<usize, Error> {
+        let to_io_error = Error::other;
+        let width = self.dialect.ragged.map(|(width, _)| width);
+        let mut records = self.into_iter();
+        let first = records.next().transpose()?;
+        let num_columns = match &first {
+            Some(record) => record.len(),
+            None => width.unwrap_or(1),
+        };
+        let columns: Vec<String> = (0..num_columns)
+            .map(|i| format!("\"column{}\" VARCHAR", i))
+            .collect();
+        // rolled back when it is dropped before the commit
+        let transaction = conn.unchecked_transaction().map_err(to_io_error)?;
+        transaction
+            .execute_batch(&format!(
+                "CREATE TABLE \"{}\".\"{}\" ({})",
+                SCHEMA,
+                table_name.replace('"', "\"\""),
+                columns.join(", ")
+            ))
+            .map_err(to_io_error)?;
+
+        // the appender takes the names as they are, without SQL quoting
+        let mut appender = transaction
+            .appender_to_db(table_name, SCHEMA)
+            .map_err(to_io_error)?;
+        let mut num_rows = 0;
+        for record in first.map(Ok).into_iter().chain(records) {
+            let record = record?;
+            if record.len() != num_columns {
+                return Err(code::invalid_data(
+                    ErrorCode::ColumnCount,
+                    format!(
+                        "line {} has {} fields but {} were expected",
+                        record.line_number(),
+                        record.len(),
+                        num_columns
+                    ),
+                ));
+            }
+            appender
+                .append_row(duckdb::appender_params_from_iter(record.iter()))
+                .map_err(to_io_error)?;
+            num_rows += 1;
+        }
+        appender.flush().map_err(to_io_error)?;
+        drop(appender);
+        transaction.commit().map_err(to_io_error)?;
+        Ok(num_rows)
+    }
+}
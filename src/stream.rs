@@ -0,0 +1,94 @@
+//! Reading records as an asynchronous stream
+
+use crate::iter::IterItem;
+use crate::Tabfile;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::mpsc::{sync_channel, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// A `Stream` over the records of a tab file.
+///
+/// See [Tabfile::into_stream](struct.Tabfile.html#method.into_stream).
+///
+/// This type is only available with the `stream` feature.
+pub struct RecordStream {
+    receiver: Receiver<IterItem>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl RecordStream {
+    fn try_next(&self) -> Option<Poll<Option<IterItem>>> {
+        match self.receiver.try_recv() {
+            Ok(item) => Some(Poll::Ready(Some(item))),
+            Err(TryRecvError::Disconnected) => Some(Poll::Ready(None)),
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+}
+
+impl Stream for RecordStream {
+    type Item = IterItem;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(poll) = self.try_next() {
+            return poll;
+        }
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        // the reader may have sent a record before it could see the new waker
+        self.try_next().unwrap_or(Poll::Pending)
+    }
+}
+
+fn wake(waker: &Mutex<Option<Waker>>) {
+    if let Some(waker) = waker.lock().unwrap().take() {
+        waker.wake();
+    }
+}
+
+impl Tabfile {
+    /// Read the records on a background thread and yield them as a `Stream`.
+    ///
+    /// The file is read with blocking I/O on a dedicated thread, which never blocks the async
+    /// executor and works with any runtime. Up to `capacity` records are read ahead; after that
+    /// the thread waits until the stream is polled again. Dropping the stream stops the thread.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"1\tfoo\n2\tbar\n");
+    /// use futures::StreamExt;
+    /// use tabfile::Tabfile;
+    ///
+    /// let stream = Tabfile::open(file.path()).unwrap().into_stream(64);
+    /// let lines: Vec<usize> = futures::executor::block_on(
+    ///     stream.map(|record| record.unwrap().line_number()).collect(),
+    /// );
+    /// assert_eq!(lines, vec![1, 2]);
+    /// ```
+    ///
+    /// This method is only available with the `stream` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn into_stream(self, capacity: usize) -> RecordStream {
+        assert!(capacity > 0, "the capacity must be positive");
+        let (sender, receiver) = sync_channel(capacity);
+        let waker = Arc::new(Mutex::new(None));
+        let reader_waker = waker.clone();
+        thread::spawn(move || {
+            for item in self {
+                if sender.send(item).is_err() {
+                    return; // the stream was dropped
+                }
+                wake(&reader_waker);
+            }
+            drop(sender);
+            wake(&reader_waker);
+        });
+        RecordStream { receiver, waker }
+    }
+}
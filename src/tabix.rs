@@ -0,0 +1,614 @@
+//! Region queries over BGZF files with a tabix or CSI index
+
+use crate::code::{self, ErrorCode};
+use crate::{Dialect, Record};
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// The magic bytes at the start of a tabix index
+const TBI_MAGIC: &[u8] = b"TBI\x01";
+
+/// The magic bytes at the start of a CSI index
+const CSI_MAGIC: &[u8] = b"CSI\x01";
+
+/// The binning scheme of tabix indexes, which CSI indexes can change
+const TBI_MIN_SHIFT: u32 = 14;
+const TBI_DEPTH: u32 = 5;
+
+/// The preset in the format field of the index
+const FORMAT_SAM: i32 = 1;
+const FORMAT_VCF: i32 = 2;
+
+/// The flag in the format field for 0-based, half-open positions like in BED files
+const ZERO_BASED: i32 = 0x10000;
+
+/// The bytes at the start of every BGZF block: a gzip header with an extra field
+const BGZF_HEADER: &[u8] = &[31, 139, 8, 4];
+
+fn invalid_index<M: Into<String>>(message: M) -> Error {
+    code::invalid_data(ErrorCode::InvalidIndex, message)
+}
+
+/// Reader for the little-endian values of an index
+struct IndexReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> IndexReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid_index("the index is truncated"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(i32::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(self.i32()? as u32)
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn count(&mut self) -> Result<usize, Error> {
+        let count = self.i32()?;
+        usize::try_from(count).map_err(|_| invalid_index(format!("negative count {}", count)))
+    }
+}
+
+/// The columns that hold the position of a record, as 0-based column indexes
+struct Columns {
+    format: i32,
+    sequence: usize,
+    begin: usize,
+    end: Option<usize>,
+    meta: Option<char>,
+}
+
+/// The index of one reference sequence
+struct Reference {
+    bins: HashMap<u32, Vec<(u64, u64)>>,
+    linear: Vec<u64>,
+}
+
+/// A tabix or CSI index
+struct Index {
+    min_shift: u32,
+    depth: u32,
+    columns: Columns,
+    names: Vec<String>,
+    references: Vec<Reference>,
+}
+
+/// Read the tabix header with the columns and the sequence names
+fn read_columns(reader: &mut IndexReader<'_>) -> Result<(Columns, Vec<String>), Error> {
+    let format = reader.i32()?;
+    let column = |value: i32| {
+        usize::try_from(value)
+            .ok()
+            .and_then(|value| value.checked_sub(1))
+            .ok_or_else(|| invalid_index(format!("invalid column {}", value)))
+    };
+    let sequence = column(reader.i32()?)?;
+    let begin = column(reader.i32()?)?;
+    let end = match reader.i32()? {
+        0 => None,
+        end => Some(column(end)?),
+    };
+    let meta = match reader.i32()? {
+        0 => None,
+        meta => Some(
+            u8::try_from(meta)
+                .map_err(|_| invalid_index(format!("invalid meta character {}", meta)))?
+                as char,
+        ),
+    };
+    let _skip = reader.i32()?;
+    let names_length = reader.count()?;
+    let names = reader
+        .take(names_length)?
+        .split(|&byte| byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            String::from_utf8(name.to_vec())
+                .map_err(|_| invalid_index("a sequence name is not valid UTF-8"))
+        })
+        .collect::<Result<_, Error>>()?;
+    let columns = Columns {
+        format,
+        sequence,
+        begin,
+        end,
+        meta,
+    };
+    Ok((columns, names))
+}
+
+/// Read the chunks of a bin
+fn read_chunks(reader: &mut IndexReader<'_>) -> Result<Vec<(u64, u64)>, Error> {
+    let count = reader.count()?;
+    (0..count)
+        .map(|_| Ok((reader.u64()?, reader.u64()?)))
+        .collect()
+}
+
+impl Index {
+    fn read(path: &Path) -> Result<Index, Error> {
+        let mut bytes = Vec::new();
+        MultiGzDecoder::new(BufReader::new(File::open(path)?)).read_to_end(&mut bytes)?;
+        let mut reader = IndexReader {
+            bytes: &bytes,
+            position: 0,
+        };
+        let magic = reader.take(4)?;
+        let (min_shift, depth, columns, names, count, is_csi) = if magic == TBI_MAGIC {
+            let count = reader.count()?;
+            let (columns, names) = read_columns(&mut reader)?;
+            (TBI_MIN_SHIFT, TBI_DEPTH, columns, names, count, false)
+        } else if magic == CSI_MAGIC {
+            let min_shift = reader.u32()?;
+            let depth = reader.u32()?;
+            if min_shift > 63 || depth > 20 || min_shift + 3 * depth > 63 {
+                return Err(invalid_index("the binning scheme is too deep"));
+            }
+            let aux_length = reader.count()?;
+            let mut aux = IndexReader {
+                bytes: reader.take(aux_length)?,
+                position: 0,
+            };
+            let (columns, names) = read_columns(&mut aux)?;
+            (min_shift, depth, columns, names, reader.count()?, true)
+        } else {
+            return Err(invalid_index("not a tabix or CSI index"));
+        };
+        if count != names.len() {
+            return Err(invalid_index("the number of sequences is wrong"));
+        }
+        let mut references = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut bins = HashMap::new();
+            for _ in 0..reader.count()? {
+                let bin = reader.u32()?;
+                if is_csi {
+                    let _min_offset = reader.u64()?;
+                }
+                bins.insert(bin, read_chunks(&mut reader)?);
+            }
+            let mut linear = Vec::new();
+            if !is_csi {
+                for _ in 0..reader.count()? {
+                    linear.push(reader.u64()?);
+                }
+            }
+            references.push(Reference { bins, linear });
+        }
+        Ok(Index {
+            min_shift,
+            depth,
+            columns,
+            names,
+            references,
+        })
+    }
+
+    /// Get the bins that may contain records in the 0-based, half-open range
+    fn bins(&self, begin: u64, end: u64) -> Vec<u32> {
+        let max = (1u64 << (self.min_shift + 3 * self.depth)) - 1;
+        let begin = begin.min(max);
+        let end = end.saturating_sub(1).clamp(begin, max);
+        let mut bins = Vec::new();
+        let mut first_bin = 0;
+        for level in 0..=self.depth {
+            let shift = self.min_shift + 3 * (self.depth - level);
+            for bin in first_bin + (begin >> shift)..=first_bin + (end >> shift) {
+                bins.push(bin as u32);
+            }
+            first_bin += 1 << (3 * level);
+        }
+        bins
+    }
+
+    /// Get the sorted and merged chunks that may contain the records of a region
+    fn chunks(&self, reference: &Reference, begin: u64, end: u64) -> Vec<(u64, u64)> {
+        let interval = (begin >> self.min_shift) as usize;
+        let min_offset = reference
+            .linear
+            .get(interval)
+            .or_else(|| reference.linear.last())
+            .copied()
+            .unwrap_or(0);
+        let mut chunks: Vec<(u64, u64)> = self
+            .bins(begin, end)
+            .iter()
+            .filter_map(|bin| reference.bins.get(bin))
+            .flatten()
+            .filter(|&&(_, chunk_end)| chunk_end > min_offset)
+            .copied()
+            .collect();
+        chunks.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(chunks.len());
+        for (chunk_begin, chunk_end) in chunks {
+            match merged.last_mut() {
+                Some(last) if chunk_begin <= last.1 => last.1 = last.1.max(chunk_end),
+                _ => merged.push((chunk_begin.max(min_offset), chunk_end)),
+            }
+        }
+        merged
+    }
+}
+
+/// Reader for the blocks of a BGZF file
+struct Bgzf {
+    file: BufReader<File>,
+    block: Vec<u8>,
+    position: usize,
+    block_offset: u64,
+    next_block_offset: u64,
+}
+
+impl Bgzf {
+    fn new(file: File) -> Bgzf {
+        Bgzf {
+            file: BufReader::new(file),
+            block: Vec::new(),
+            position: 0,
+            block_offset: 0,
+            next_block_offset: 0,
+        }
+    }
+
+    /// Decompress the block at `offset` in the file, returns `false` at the end of the file
+    fn load_block(&mut self, offset: u64) -> Result<bool, Error> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.block.clear();
+        self.position = 0;
+        self.block_offset = offset;
+        self.next_block_offset = offset;
+        let mut header = [0; 12];
+        let mut read = 0;
+        while read < header.len() {
+            match self.file.read(&mut header[read..])? {
+                0 if read == 0 => return Ok(false),
+                0 => return Err(invalid_index(format!("truncated block at {}", offset))),
+                length => read += length,
+            }
+        }
+        if &header[..4] != BGZF_HEADER {
+            return Err(invalid_index(format!("no BGZF block at {}", offset)));
+        }
+        let mut extra = vec![0; u16::from_le_bytes([header[10], header[11]]) as usize];
+        self.file.read_exact(&mut extra)?;
+        let mut block_size = None;
+        let mut subfields = &extra[..];
+        while subfields.len() >= 4 {
+            let length = u16::from_le_bytes([subfields[2], subfields[3]]) as usize;
+            let data = subfields.get(4..4 + length).unwrap_or_default();
+            if subfields[..2] == b"BC"[..] && length == 2 {
+                block_size = Some(u16::from_le_bytes([data[0], data[1]]) as usize + 1);
+            }
+            subfields = subfields.get(4 + length..).unwrap_or_default();
+        }
+        let rest = block_size
+            .and_then(|size| size.checked_sub(header.len() + extra.len()))
+            .filter(|&rest| rest >= 8)
+            .ok_or_else(|| invalid_index(format!("no BGZF block size at {}", offset)))?;
+        let mut compressed = vec![0; rest];
+        self.file.read_exact(&mut compressed)?;
+        let (payload, trailer) = compressed.split_at(rest - 8);
+        DeflateDecoder::new(payload).read_to_end(&mut self.block)?;
+        let length = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if self.block.len() != length as usize {
+            return Err(invalid_index(format!("corrupt BGZF block at {}", offset)));
+        }
+        self.next_block_offset = offset + (header.len() + extra.len() + rest) as u64;
+        Ok(true)
+    }
+
+    /// Go to a virtual offset, which is the offset of a block shifted by 16 bits plus the
+    /// position in the decompressed block
+    fn seek(&mut self, virtual_offset: u64) -> Result<(), Error> {
+        let offset = virtual_offset >> 16;
+        let is_loaded = offset == self.block_offset && self.next_block_offset > offset;
+        if !is_loaded {
+            self.load_block(offset)?;
+        }
+        self.position = ((virtual_offset & 0xffff) as usize).min(self.block.len());
+        Ok(())
+    }
+
+    /// Make sure that there is data left in the current block, returns `false` at the end
+    fn fill(&mut self) -> Result<bool, Error> {
+        while self.position == self.block.len() {
+            if !self.load_block(self.next_block_offset)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read the next line into `line` and get its virtual offset
+    fn read_line(&mut self, line: &mut Vec<u8>) -> Result<Option<u64>, Error> {
+        if !self.fill()? {
+            return Ok(None);
+        }
+        let virtual_offset = self.block_offset << 16 | self.position as u64;
+        loop {
+            let rest = &self.block[self.position..];
+            match rest.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => {
+                    line.extend_from_slice(&rest[..=newline]);
+                    self.position += newline + 1;
+                    return Ok(Some(virtual_offset));
+                }
+                None => {
+                    line.extend_from_slice(rest);
+                    self.position = self.block.len();
+                    if !self.fill()? {
+                        return Ok(Some(virtual_offset));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parse a region like `chr1:10,000-20,000` into a 0-based, half-open range
+fn parse_region(range: &str) -> Option<(u64, u64)> {
+    let range = range.replace(',', "");
+    let (begin, end) = range.split_once('-').unwrap_or((&range, ""));
+    let begin: u64 = begin.parse().ok()?;
+    let end = match end {
+        "" => u64::MAX,
+        end => end.parse().ok()?,
+    };
+    if begin == 0 || begin > end {
+        return None;
+    }
+    Some((begin - 1, end))
+}
+
+/// Get the number of reference bases that a SAM CIGAR string covers
+fn cigar_length(cigar: &str) -> u64 {
+    let mut length = 0;
+    let mut number = 0;
+    for c in cigar.chars() {
+        match c.to_digit(10) {
+            Some(digit) => number = number * 10 + u64::from(digit),
+            None => {
+                if matches!(c, 'M' | 'D' | 'N' | '=' | 'X') {
+                    length += number;
+                }
+                number = 0;
+            }
+        }
+    }
+    length
+}
+
+/// A BGZF-compressed tab file with a tabix or CSI index.
+///
+/// Files that were compressed with `bgzip` and indexed with `tabix` can be queried for the
+/// records that overlap a genomic region without reading the whole file. The index knows
+/// which columns hold the sequence name and the positions, so no further configuration is
+/// needed. Lines that start with the meta character of the index, usually `#`, are skipped.
+///
+/// This type is only available with the `tabix` feature.
+///
+/// ```no_run
+/// use tabfile::IndexedTabfile;
+///
+/// let mut variants = IndexedTabfile::open("variants.vcf.gz").unwrap();
+/// for record in variants.query("chr1:10000-20000").unwrap() {
+///     let record = record.unwrap();
+///     println!("{} {}", &record[0], &record[1]);
+/// }
+/// ```
+pub struct IndexedTabfile {
+    data: Bgzf,
+    index: Index,
+    dialect: Dialect,
+}
+
+impl IndexedTabfile {
+    /// Open a BGZF file and its index `path.tbi` or `path.csi`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IndexedTabfile, Error> {
+        let path = path.as_ref();
+        let index_path = ["tbi", "csi"]
+            .iter()
+            .map(|extension| {
+                let mut name = OsString::from(path.as_os_str());
+                name.push(".");
+                name.push(extension);
+                PathBuf::from(name)
+            })
+            .find(|index_path| index_path.exists())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("no index {}.tbi or .csi", path.display()),
+                )
+            })?;
+        IndexedTabfile::with_index(path, index_path)
+    }
+
+    /// Open a BGZF file with an index in another place
+    pub fn with_index<P: AsRef<Path>, Q: AsRef<Path>>(
+        path: P,
+        index_path: Q,
+    ) -> Result<IndexedTabfile, Error> {
+        let index = Index::read(index_path.as_ref())?;
+        let dialect = match index.columns.meta {
+            Some(meta) => Dialect::new().comment_character(meta),
+            None => Dialect::new().comment_prefixes(&[]),
+        };
+        Ok(IndexedTabfile {
+            data: Bgzf::new(File::open(path)?),
+            index,
+            dialect,
+        })
+    }
+
+    /// Get the names of the sequences in the index
+    pub fn sequences(&self) -> &[String] {
+        &self.index.names
+    }
+
+    /// Iterate over the records that overlap `region`.
+    ///
+    /// A region is a sequence name like `chr1`, optionally followed by a 1-based, inclusive
+    /// range like `chr1:10000-20000` or an open range like `chr1:10000`. Commas in the numbers
+    /// are ignored. A sequence that is not in the index has no records. The records have no
+    /// line number, because it is unknown without reading the whole file, and their
+    /// [byte offset](crate::Record::byte_offset) is the virtual offset of the line in the BGZF
+    /// file. An error of kind `InvalidInput` is returned for a malformed region.
+    pub fn query(&mut self, region: &str) -> Result<RegionRecords<'_>, Error> {
+        let (sequence, begin, end) = if self.index.names.iter().any(|name| name == region) {
+            (region, 0, u64::MAX)
+        } else {
+            let invalid_region = || {
+                code::error(
+                    ErrorKind::InvalidInput,
+                    ErrorCode::InvalidRegion,
+                    format!("invalid region `{}`", region),
+                )
+            };
+            let (sequence, range) = region.rsplit_once(':').ok_or_else(invalid_region)?;
+            let (begin, end) = parse_region(range).ok_or_else(invalid_region)?;
+            (sequence, begin, end)
+        };
+        let chunks = match self.index.names.iter().position(|name| name == sequence) {
+            Some(position) => {
+                let reference = &self.index.references[position];
+                self.index.chunks(reference, begin, end)
+            }
+            None => Vec::new(),
+        };
+        Ok(RegionRecords {
+            tabfile: self,
+            sequence: sequence.to_string(),
+            begin,
+            end,
+            chunks,
+            chunk: 0,
+            in_chunk: false,
+        })
+    }
+}
+
+/// Iterator over the records of a region.
+///
+/// See [IndexedTabfile::query].
+pub struct RegionRecords<'a> {
+    tabfile: &'a mut IndexedTabfile,
+    sequence: String,
+    begin: u64,
+    end: u64,
+    chunks: Vec<(u64, u64)>,
+    chunk: usize,
+    in_chunk: bool,
+}
+
+impl RegionRecords<'_> {
+    /// Get the 0-based, half-open range that `record` covers
+    fn range(&self, record: &Record) -> Result<(u64, u64), Error> {
+        let columns = &self.tabfile.index.columns;
+        let position = |column: usize| {
+            let field = record.get_opt(column).unwrap_or("");
+            field.parse::<u64>().map_err(|_| {
+                code::invalid_data(
+                    ErrorCode::InvalidField,
+                    format!("invalid position `{}` in column {}", field, column + 1),
+                )
+            })
+        };
+        let mut begin = position(columns.begin)?;
+        if columns.format & ZERO_BASED == 0 {
+            begin = begin.saturating_sub(1);
+        }
+        let end = match (columns.format & 0xffff, columns.end) {
+            (FORMAT_SAM, _) => begin + cigar_length(record.get_opt(5).unwrap_or("")).max(1),
+            (FORMAT_VCF, _) => begin + (record.get_opt(3).unwrap_or("").len() as u64).max(1),
+            // an inclusive 1-based end is the exclusive 0-based end
+            (_, Some(column)) => position(column)?,
+            (_, None) => begin + 1,
+        };
+        Ok((begin, end))
+    }
+}
+
+impl Iterator for RegionRecords<'_> {
+    type Item = Result<Record, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (chunk_begin, chunk_end) = *self.chunks.get(self.chunk)?;
+            let data = &mut self.tabfile.data;
+            if !self.in_chunk {
+                if let Err(e) = data.seek(chunk_begin) {
+                    self.chunk = self.chunks.len();
+                    return Some(Err(e));
+                }
+                self.in_chunk = true;
+            }
+            let mut line = Vec::new();
+            let virtual_offset = match data.read_line(&mut line) {
+                Ok(Some(virtual_offset)) if virtual_offset < chunk_end => virtual_offset,
+                Ok(_) => {
+                    self.chunk += 1;
+                    self.in_chunk = false;
+                    continue;
+                }
+                Err(e) => {
+                    self.chunk = self.chunks.len();
+                    return Some(Err(e));
+                }
+            };
+            let line = match String::from_utf8(line) {
+                Ok(line) => line,
+                Err(_) => {
+                    return Some(Err(code::invalid_data(
+                        ErrorCode::InvalidUtf8,
+                        format!("the line at offset {} is not valid UTF-8", virtual_offset),
+                    )))
+                }
+            };
+            let dialect = &self.tabfile.dialect;
+            if !dialect.is_data_line(&line) {
+                continue;
+            }
+            let record = dialect.record(line, Vec::new(), 0, virtual_offset, None);
+            if record.get_opt(self.tabfile.index.columns.sequence) != Some(self.sequence.as_str()) {
+                continue;
+            }
+            let (begin, end) = match self.range(&record) {
+                Ok(range) => range,
+                Err(e) => return Some(Err(e)),
+            };
+            if begin >= self.end {
+                // the records are sorted, so all further records are behind the region
+                self.chunk = self.chunks.len();
+                return None;
+            }
+            if end > self.begin {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
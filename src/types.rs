@@ -0,0 +1,138 @@
+//! Checking the values of a tab file against expected column types
+
+use crate::Tabfile;
+use std::io::Error;
+
+/// The type that the values of a column are expected to have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ColumnType {
+    /// Any value is accepted
+    Text,
+    /// A signed 64-bit integer like `-42`
+    Integer,
+    /// A 64-bit floating point number like `3.14` or `1e-5`
+    Float,
+    /// Either `true` or `false`
+    Boolean,
+}
+
+impl ColumnType {
+    /// Check whether `value` can be parsed as this type
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            ColumnType::Text => true,
+            ColumnType::Integer => value.parse::<i64>().is_ok(),
+            ColumnType::Float => value.parse::<f64>().is_ok(),
+            ColumnType::Boolean => value.parse::<bool>().is_ok(),
+        }
+    }
+}
+
+/// A value that could not be parsed as the type of its column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoercionFailure {
+    line_number: usize,
+    value: String,
+}
+
+impl CoercionFailure {
+    /// Get the line number of the record that contains the value
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Get the value that could not be parsed
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Parse failures of a single column.
+#[derive(Clone, Debug)]
+pub struct ColumnReport {
+    column_type: ColumnType,
+    failures: usize,
+    examples: Vec<CoercionFailure>,
+}
+
+impl ColumnReport {
+    /// Get the type that the column was checked against
+    pub fn column_type(&self) -> ColumnType {
+        self.column_type
+    }
+
+    /// Get the number of values that could not be parsed
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// Get the first few values that could not be parsed
+    pub fn examples(&self) -> &[CoercionFailure] {
+        &self.examples
+    }
+}
+
+/// Per-column summary of values that do not match the declared column types.
+///
+/// Create a report with [Tabfile::coercion_report](struct.Tabfile.html#method.coercion_report).
+#[derive(Clone, Debug)]
+pub struct CoercionReport {
+    columns: Vec<ColumnReport>,
+}
+
+impl CoercionReport {
+    /// Get the reports of the individual columns in the order of the declared types
+    pub fn columns(&self) -> &[ColumnReport] {
+        &self.columns
+    }
+
+    /// Check whether all values could be parsed
+    pub fn is_clean(&self) -> bool {
+        self.columns.iter().all(|column| column.failures == 0)
+    }
+}
+
+impl Tabfile {
+    /// Check every record against the declared column types.
+    ///
+    /// Instead of stopping at the first value that does not match its type, all values are
+    /// checked and the failures are counted per column. Up to `max_examples` failing values are
+    /// kept for every column together with their line numbers. Fields that are missing from short
+    /// rows or that are [null](struct.Tabfile.html#method.null_tokens) are not counted as
+    /// failures and columns without a declared type are ignored.
+    pub fn coercion_report(
+        self,
+        types: &[ColumnType],
+        max_examples: usize,
+    ) -> Result<CoercionReport, Error> {
+        let mut columns: Vec<ColumnReport> = types
+            .iter()
+            .map(|&column_type| ColumnReport {
+                column_type,
+                failures: 0,
+                examples: Vec::new(),
+            })
+            .collect();
+        for record in self {
+            let record = record?;
+            for (i, column) in columns.iter_mut().enumerate() {
+                let value = match record.get_opt(i) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                if !column.column_type.accepts(value) {
+                    column.failures += 1;
+                    if column.examples.len() < max_examples {
+                        column.examples.push(CoercionFailure {
+                            line_number: record.line_number(),
+                            value: value.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(CoercionReport { columns })
+    }
+}
@@ -0,0 +1,46 @@
+//! Reading VCF files with their meta-information and header line
+
+use crate::{Header, HeaderedTabfile, Tabfile};
+use std::io::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The prefix of the meta-information lines of a VCF file
+const META_PREFIX: &str = "##";
+
+impl HeaderedTabfile {
+    /// Open a VCF file.
+    ///
+    /// VCF files start with meta-information lines like `##fileformat=VCFv4.2`, followed by a
+    /// header line that starts with a single `#`, e.g. `#CHROM POS ID ...`. Only lines starting
+    /// with `##` are comments, so the `#CHROM` line becomes the header. The `#` is removed from
+    /// the first column name, so the columns are called `CHROM`, `POS` and so on. The
+    /// meta-information lines are available through
+    /// [leading_comments](#method.leading_comments) once the header has been read.
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use tempfile::NamedTempFile;
+    /// # let mut file = NamedTempFile::new().unwrap();
+    /// # file.write_all(b"##fileformat=VCFv4.2\n#CHROM\tPOS\tID\nchr1\t1234\trs1\n");
+    /// use tabfile::HeaderedTabfile;
+    ///
+    /// let mut vcf_file = HeaderedTabfile::vcf(file.path()).unwrap();
+    /// assert_eq!(vcf_file.leading_comments(), &["##fileformat=VCFv4.2"]);
+    /// let record = vcf_file.next().unwrap().unwrap();
+    /// assert_eq!(&record["CHROM"], "chr1");
+    /// assert_eq!(&record["ID"], "rs1");
+    /// ```
+    pub fn vcf<P: AsRef<Path>>(path: P) -> Result<HeaderedTabfile, Error> {
+        let tabfile = Tabfile::open(path)?.comment_prefix(META_PREFIX);
+        let mut vcf_file = HeaderedTabfile::new(tabfile)?;
+        let names = vcf_file.header.names().iter().enumerate().map(|(i, name)| {
+            match name.strip_prefix('#') {
+                Some(name) if i == 0 => name.to_string(),
+                _ => name.clone(),
+            }
+        });
+        vcf_file.header = Arc::new(Header::new(names));
+        Ok(vcf_file)
+    }
+}
@@ -0,0 +1,277 @@
+//! Writing records to a tab file
+
+use crate::code::{self, ErrorCode};
+use crate::{EscapeStyle, IntoRecord, Record};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+/// When [Tabwriter](struct.Tabwriter.html) puts fields in quotes.
+///
+/// Quoted fields start and end with the quote character, and quote characters inside them are
+/// doubled, like [Tabfile::quote_character](struct.Tabfile.html#method.quote_character) reads
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotePolicy {
+    /// Never quote fields, which is the default. A field that contains the separator, a line
+    /// break or the record terminator cannot be written and is rejected with an error of
+    /// kind `InvalidData`, unless escaping removes the line break.
+    Never,
+    /// Quote the fields that contain the separator, a line break, the record terminator or
+    /// the quote character
+    WhenNeeded,
+    /// Quote every field
+    Always,
+}
+
+/// Writer for tab files, the counterpart of [Tabfile](struct.Tabfile.html).
+///
+/// Every call writes one line: the fields joined by the separator, followed by the record
+/// terminator. By default, the separator is a tab, the terminator is `'\n'`, no header is
+/// written and the fields are neither escaped nor quoted, see
+/// [quote_policy](#method.quote_policy) and [escape](#method.escape) for fields that contain
+/// the separator or line breaks.
+///
+/// ```
+/// use tabfile::Tabwriter;
+///
+/// let mut writer = Tabwriter::new(Vec::new());
+/// writer.write_fields(&["chr1", "1234", "A"]).unwrap();
+/// writer.write_fields(vec![String::from("chr2"), String::from("5678")]).unwrap();
+/// assert_eq!(writer.into_inner().unwrap(), b"chr1\t1234\tA\nchr2\t5678\n");
+/// ```
+pub struct Tabwriter<W: Write> {
+    writer: W,
+    separator: String,
+    terminator: u8,
+    header: bool,
+    quote_policy: QuotePolicy,
+    quote: char,
+    escape: EscapeStyle,
+    lines_written: usize,
+    /// The buffer for the line that is written next
+    line: String,
+}
+
+impl Tabwriter<BufWriter<File>> {
+    /// Create the file at `path`, replacing a file that already exists
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Tabwriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Tabwriter<W> {
+    /// Write the lines to `writer`, which should be buffered if it is a file or a socket
+    pub fn new(writer: W) -> Tabwriter<W> {
+        Tabwriter {
+            writer,
+            separator: "\t".to_string(),
+            terminator: b'\n',
+            header: false,
+            quote_policy: QuotePolicy::Never,
+            quote: '"',
+            escape: EscapeStyle::None,
+            lines_written: 0,
+            line: String::new(),
+        }
+    }
+
+    /// Set the separator between the fields. The default is a tab.
+    pub fn separator(mut self, sep: char) -> Self {
+        self.separator = sep.to_string();
+        self
+    }
+
+    /// Use a separator with more than one character like `"::"`
+    pub fn separator_str(mut self, sep: &str) -> Self {
+        self.separator = sep.to_string();
+        self
+    }
+
+    /// Set the byte that ends every line. The default is `b'\n'`.
+    pub fn record_terminator(mut self, terminator: u8) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Write the [column names](trait.IntoRecord.html#tymethod.column_names) of the row type
+    /// as a header before the first row of [write_row](#method.write_row), unless other lines
+    /// have been written before. The default is `false`.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Set when fields are put in quotes. The default is `QuotePolicy::Never`.
+    ///
+    /// ```
+    /// use tabfile::{QuotePolicy, Tabwriter};
+    ///
+    /// let mut writer = Tabwriter::new(Vec::new()).quote_policy(QuotePolicy::WhenNeeded);
+    /// writer.write_fields(&["1", "two\tthree", "5\" tall"]).unwrap();
+    /// let output = writer.into_inner().unwrap();
+    /// assert_eq!(output, b"1\t\"two\tthree\"\t\"5\"\" tall\"\n");
+    ///
+    /// let mut writer = Tabwriter::new(Vec::new());
+    /// assert!(writer.write_fields(&["first line\nsecond line"]).is_err());
+    /// ```
+    pub fn quote_policy(mut self, policy: QuotePolicy) -> Self {
+        self.quote_policy = policy;
+        self
+    }
+
+    /// Set the character that quoted fields are put in. The default is `'"'`.
+    pub fn quote_character(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Encode special characters in the fields with escape sequences, the counterpart of
+    /// [Tabfile::unescape](struct.Tabfile.html#method.unescape).
+    ///
+    /// With [EscapeStyle::Backslash](enum.EscapeStyle.html#variant.Backslash) a tab is
+    /// written as `\t`, a line break as `\n`, a carriage return as `\r` and a backslash as
+    /// `\\`, like PostgreSQL `COPY` does, so tab-separated fields do not need quotes. Fields
+    /// are escaped before they are quoted. The default is `EscapeStyle::None`.
+    ///
+    /// ```
+    /// use tabfile::{EscapeStyle, Tabwriter};
+    ///
+    /// let mut writer = Tabwriter::new(Vec::new()).escape(EscapeStyle::Backslash);
+    /// writer.write_fields(&["first\tsecond", "C:\\temp", "line\nbreak"]).unwrap();
+    /// let output = writer.into_inner().unwrap();
+    /// assert_eq!(output, b"first\\tsecond\tC:\\\\temp\tline\\nbreak\n");
+    /// ```
+    pub fn escape(mut self, style: EscapeStyle) -> Self {
+        self.escape = style;
+        self
+    }
+
+    /// Write one line with `fields`
+    ///
+    /// Returns an error of kind `InvalidData` if a field cannot be written with the
+    /// [quote policy](#method.quote_policy), in which case nothing is written.
+    pub fn write_fields<I, S>(&mut self, fields: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        let result = self.encode_line(&mut line, fields).and_then(|_| {
+            self.writer.write_all(line.as_bytes())?;
+            self.writer.write_all(&[self.terminator])
+        });
+        self.line = line;
+        result?;
+        self.lines_written += 1;
+        Ok(())
+    }
+
+    /// Append the encoded fields joined by the separator to `line`
+    fn encode_line<I, S>(&self, line: &mut String, fields: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                line.push_str(&self.separator);
+            }
+            line.push_str(&self.encode(field.as_ref(), i)?);
+        }
+        Ok(())
+    }
+
+    /// Escape and quote the field at `column` as configured
+    fn encode<'a>(&self, field: &'a str, column: usize) -> Result<Cow<'a, str>, Error> {
+        let field = match self.escape {
+            EscapeStyle::Backslash => escape_backslashes(field),
+            EscapeStyle::None => Cow::Borrowed(field),
+        };
+        let breaks_line = (!self.separator.is_empty() && field.contains(&*self.separator))
+            || field.contains(['\n', '\r'])
+            || field.as_bytes().contains(&self.terminator);
+        let quoted = match self.quote_policy {
+            QuotePolicy::Never if breaks_line => {
+                return Err(code::invalid_data(
+                    ErrorCode::UnwritableField,
+                    format!(
+                        "line {}: field {} contains the separator or a line break",
+                        self.lines_written + 1,
+                        column
+                    ),
+                ));
+            }
+            QuotePolicy::Never => false,
+            QuotePolicy::WhenNeeded => breaks_line || field.contains(self.quote),
+            QuotePolicy::Always => true,
+        };
+        if !quoted {
+            return Ok(field);
+        }
+        let quote = self.quote.to_string();
+        let doubled = format!("{}{}", quote, quote);
+        Ok(Cow::Owned(format!(
+            "{}{}{}",
+            quote,
+            field.replace(&quote, &doubled),
+            quote
+        )))
+    }
+
+    /// Write the fields of `record`, e.g. one that was read from another file
+    ///
+    /// Only the fields are written, so the record may come from a file with another separator.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), Error> {
+        self.write_fields(record.iter())
+    }
+
+    /// Write the fields of `row`, see [IntoRecord](trait.IntoRecord.html)
+    pub fn write_row<T: IntoRecord>(&mut self, row: &T) -> Result<(), Error> {
+        if self.header && self.lines_written == 0 {
+            self.write_fields(T::column_names())?;
+        }
+        self.write_fields(row.fields())
+    }
+
+    /// Get the number of lines written so far, including the header
+    pub fn lines_written(&self) -> usize {
+        self.lines_written
+    }
+
+    /// Get a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+
+    /// Flush the underlying writer and return it
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Replace tabs, line breaks and backslashes with escape sequences
+fn escape_backslashes(field: &str) -> Cow<'_, str> {
+    if !field.contains(['\t', '\n', '\r', '\\']) {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = String::with_capacity(field.len() + 2);
+    for c in field.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
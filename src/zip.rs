@@ -0,0 +1,96 @@
+//! Iterating over two tab files side by side
+
+use crate::code::{self, ErrorCode};
+use crate::{Record, RowIterator, Tabfile};
+use std::io::Error;
+
+/// Iterator over pairs of records from two files. See [zip](fn.zip.html).
+pub struct Zip {
+    a: RowIterator,
+    b: RowIterator,
+}
+
+impl Iterator for Zip {
+    type Item = Result<(Record, Record), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.a.next(), self.b.next()) {
+            (None, None) => None,
+            (Some(Err(e)), _) | (_, Some(Err(e))) => Some(Err(e)),
+            (Some(Ok(a)), Some(Ok(b))) => Some(Ok((a, b))),
+            (Some(Ok(a)), None) => Some(Err(length_mismatch("first", "second", &a))),
+            (None, Some(Ok(b))) => Some(Err(length_mismatch("second", "first", &b))),
+        }
+    }
+}
+
+fn length_mismatch(longer: &str, shorter: &str, first_extra: &Record) -> Error {
+    code::invalid_data(
+        ErrorCode::LengthMismatch,
+        format!(
+            "the {} file has more records than the {} file, starting at line {}",
+            longer,
+            shorter,
+            first_extra.line_number()
+        ),
+    )
+}
+
+/// Iterator over pairs of records from two files that pads the shorter file.
+/// See [zip_longest](fn.zip_longest.html).
+pub struct ZipLongest {
+    a: RowIterator,
+    b: RowIterator,
+}
+
+impl Iterator for ZipLongest {
+    type Item = Result<(Option<Record>, Option<Record>), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.a.next(), self.b.next()) {
+            (None, None) => None,
+            (Some(Err(e)), _) | (_, Some(Err(e))) => Some(Err(e)),
+            (a, b) => Some(Ok((a.and_then(Result::ok), b.and_then(Result::ok)))),
+        }
+    }
+}
+
+/// Iterate over the records of two files row by row.
+///
+/// The records are paired in the order in which they are read, so the options of each
+/// `Tabfile`, like `skip_lines`, decide which lines are compared. If one file has more records
+/// than the other, an error of kind `InvalidData` is returned after the last pair. Use
+/// [zip_longest](fn.zip_longest.html) to get the remaining records instead.
+///
+/// ```
+/// # use std::io::Write;
+/// # use tempfile::NamedTempFile;
+/// # let mut expected = NamedTempFile::new().unwrap();
+/// # expected.write_all(b"a\t1\nb\t2\n");
+/// # let mut actual = NamedTempFile::new().unwrap();
+/// # actual.write_all(b"a\t1\nb\t3\n");
+/// use tabfile::{zip, Tabfile};
+///
+/// let expected = Tabfile::open(expected.path()).unwrap();
+/// let actual = Tabfile::open(actual.path()).unwrap();
+/// for pair in zip(expected, actual) {
+///     let (expected, actual) = pair.unwrap();
+///     if expected.fields() != actual.fields() {
+///         println!("line {} differs", actual.line_number());
+///     }
+/// }
+/// ```
+pub fn zip(a: Tabfile, b: Tabfile) -> Zip {
+    Zip {
+        a: a.into_iter(),
+        b: b.into_iter(),
+    }
+}
+
+/// Iterate over the records of two files row by row until both files are exhausted.
+///
+/// Once the shorter file runs out of records, its side of the pair is `None`.
+pub fn zip_longest(a: Tabfile, b: Tabfile) -> ZipLongest {
+    ZipLongest {
+        a: a.into_iter(),
+        b: b.into_iter(),
+    }
+}
@@ -0,0 +1,166 @@
+//! Derive macros for the `FromRecord` and `IntoRecord` traits of the `tabfile` crate
+//!
+//! Use this crate through the `derive` feature of `tabfile` instead of depending on it directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Type};
+
+/// Derive `tabfile::FromRecord` for a struct with named fields.
+///
+/// See the documentation of `FromRecord` in the `tabfile` crate for the supported attributes.
+#[proc_macro_derive(FromRecord, attributes(tabfile))]
+pub fn derive_from_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derive `tabfile::IntoRecord` for a struct with named fields.
+///
+/// See the documentation of `IntoRecord` in the `tabfile` crate for the supported attributes.
+#[proc_macro_derive(IntoRecord, attributes(tabfile))]
+pub fn derive_into_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_into_record(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Get the named fields of a struct or an error that mentions `trait_name`
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    trait_name: &str,
+) -> Result<&'a Punctuated<syn::Field, Comma>, Error> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "{} can only be derived for structs with named fields",
+                    trait_name
+                ),
+            )),
+        },
+        _ => Err(Error::new(
+            Span::call_site(),
+            format!("{} can only be derived for structs", trait_name),
+        )),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = named_fields(input, "FromRecord")?;
+    let mut initializers = Vec::new();
+    for (position, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let field_name = ident.to_string();
+        let column = column_name(field)?.unwrap_or_else(|| field_name.clone());
+        let parse = match option_inner_type(&field.ty) {
+            Some(inner) => quote! { parse_optional::<#inner> },
+            None => {
+                let ty = &field.ty;
+                quote! { parse_required::<#ty> }
+            }
+        };
+        initializers.push(quote! {
+            #ident: ::tabfile::__private::#parse(
+                record,
+                header,
+                #position,
+                #field_name,
+                #column,
+            )?
+        });
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tabfile::FromRecord for #name #ty_generics #where_clause {
+            fn from_record(
+                record: &::tabfile::Record,
+                header: ::std::option::Option<&::tabfile::Header>,
+            ) -> ::std::result::Result<Self, ::std::io::Error> {
+                ::std::result::Result::Ok(#name {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
+
+fn expand_into_record(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = named_fields(input, "IntoRecord")?;
+    let mut columns = Vec::new();
+    let mut values = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        columns.push(column_name(field)?.unwrap_or_else(|| ident.to_string()));
+        values.push(match option_inner_type(&field.ty) {
+            Some(_) => quote! { ::tabfile::__private::format_optional(&self.#ident) },
+            None => quote! { ::tabfile::__private::format_required(&self.#ident) },
+        });
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tabfile::IntoRecord for #name #ty_generics #where_clause {
+            fn column_names() -> ::std::vec::Vec<&'static str> {
+                ::std::vec![#(#columns),*]
+            }
+
+            fn fields(&self) -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(#values),*]
+            }
+        }
+    })
+}
+
+/// Read the column name from a `#[tabfile(rename = "...")]` attribute
+fn column_name(field: &syn::Field) -> Result<Option<String>, Error> {
+    let mut column = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("tabfile"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                column = Some(name.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown tabfile attribute, expected `rename`"))
+            }
+        })?;
+    }
+    Ok(column)
+}
+
+/// Get `T` if `ty` is written as `Option<T>`
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                syn::GenericArgument::Type(inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}